@@ -0,0 +1,31 @@
+#![no_main]
+
+// Standard paths
+use std::convert::TryFrom;
+
+// Crate paths
+use transactions_processor::api::currency::Currency;
+
+// External paths
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // Amounts are read from csv as text, anything else is rejected earlier
+    let input = match std::str::from_utf8(data) {
+        Ok(input) => input,
+        Err(_) => return,
+    };
+
+    // Any input has to give either value or error, never panic
+    if let Ok(amount) = Currency::try_from(input) {
+        // Written amount is always read back the same
+        assert_eq!(Currency::try_from(amount.to_string().as_str()), Ok(amount));
+
+        // Arithmetic on parsed values is checked
+        let mut sum = amount;
+        let _ = sum.add(amount);
+        let _ = sum.add(Currency::max());
+        let mut difference = Currency::zero();
+        let _ = difference.substract(amount);
+    }
+});
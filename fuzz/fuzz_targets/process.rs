@@ -0,0 +1,22 @@
+#![no_main]
+
+// Standard paths
+use std::io;
+
+// Crate paths
+use transactions_processor::options::Options;
+use transactions_processor::process_reader;
+use transactions_processor::write_accounts;
+
+// External paths
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // Whole input is treated as csv file with transactions
+    // Broken records are only reported, fatal errors finish processing early
+    if let Ok(engine) = process_reader(data, "fuzz", &Options::default()) {
+        write_accounts(&engine, &mut io::sink(), &Options::default()).unwrap();
+        // Funds are conserved whatever records were accepted
+        assert!(engine.reconcile().is_balanced());
+    }
+});
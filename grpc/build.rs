@@ -0,0 +1,50 @@
+//! Generate service of `proto/transactions.proto`
+//!
+//! Messages are written by hand in `src/proto.rs`, so protoc is not needed to build.
+
+// External paths
+use tonic_build::manual::Builder;
+use tonic_build::manual::Method;
+use tonic_build::manual::Service;
+
+const CODEC: &str = "tonic::codec::ProstCodec";
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    let service = Service::builder()
+        .name("Transactions")
+        .package("transactions")
+        .method(
+            Method::builder()
+                .name("submit")
+                .route_name("Submit")
+                .input_type("crate::proto::Transaction")
+                .output_type("crate::proto::Outcome")
+                .codec_path(CODEC)
+                .build(),
+        )
+        .method(
+            Method::builder()
+                .name("submit_stream")
+                .route_name("SubmitStream")
+                .input_type("crate::proto::Transaction")
+                .output_type("crate::proto::Outcome")
+                .codec_path(CODEC)
+                .client_streaming()
+                .server_streaming()
+                .build(),
+        )
+        .method(
+            Method::builder()
+                .name("get_accounts")
+                .route_name("GetAccounts")
+                .input_type("crate::proto::AccountsRequest")
+                .output_type("crate::proto::Accounts")
+                .codec_path(CODEC)
+                .build(),
+        )
+        .build();
+
+    Builder::new().compile(&[service]);
+}
@@ -0,0 +1,7 @@
+#![forbid(unsafe_code)]
+
+//! gRPC api of engine to process transactions
+
+// Crate modules
+pub mod proto;
+pub mod server;
@@ -0,0 +1,43 @@
+#![forbid(unsafe_code)]
+
+// Standard paths
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+// Crate paths
+use transactions_processor::api::engine::Engine;
+use transactions_processor::args::ProcessingArgs;
+use transactions_processor::service::Service;
+use transactions_processor_grpc::server::TransactionsService;
+
+// External paths
+use clap::Parser;
+
+/// gRPC server applying transactions to shared engine
+#[derive(Parser)]
+#[command(version)]
+struct Args {
+    /// Address to listen on
+    #[arg(long, default_value = "127.0.0.1:50051")]
+    address: SocketAddr,
+
+    #[command(flatten)]
+    processing: ProcessingArgs,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+
+    // The same options as processing of file by command line
+    let options = args.processing.into_options()?;
+    let engine = Engine::with_options(options.engine.clone());
+    let service = TransactionsService::new(Arc::new(Service::new(engine, options)));
+
+    tonic::transport::Server::builder()
+        .add_service(service.into_server())
+        .serve(args.address)
+        .await?;
+
+    Ok(())
+}
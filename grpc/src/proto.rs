@@ -0,0 +1,471 @@
+//! Messages of `proto/transactions.proto`
+
+// Crate paths
+use transactions_processor::api::transactions;
+
+// External paths
+use prost::Enumeration;
+use prost::Message;
+
+/// Generated client and server of `Transactions` service
+pub mod service {
+    include!(concat!(env!("OUT_DIR"), "/transactions.Transactions.rs"));
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Enumeration)]
+#[repr(i32)]
+pub enum Type {
+    /// Not set by client, always rejected
+    TypeUnspecified = 0,
+    Deposit = 1,
+    Withdrawal = 2,
+    Dispute = 3,
+    Resolve = 4,
+    Chargeback = 5,
+    Exchange = 6,
+    Transfer = 7,
+    Lock = 8,
+    Unlock = 9,
+    Freeze = 10,
+    Close = 11,
+}
+
+impl TryFrom<Type> for transactions::Type {
+    type Error = String;
+
+    fn try_from(type_: Type) -> Result<Self, Self::Error> {
+        match type_ {
+            Type::TypeUnspecified => Err(String::from("type is not specified")),
+            Type::Deposit => Ok(transactions::Type::Deposit),
+            Type::Withdrawal => Ok(transactions::Type::Withdrawal),
+            Type::Dispute => Ok(transactions::Type::Dispute),
+            Type::Resolve => Ok(transactions::Type::Resolve),
+            Type::Chargeback => Ok(transactions::Type::Chargeback),
+            Type::Exchange => Ok(transactions::Type::Exchange),
+            Type::Transfer => Ok(transactions::Type::Transfer),
+            Type::Lock => Ok(transactions::Type::Lock),
+            Type::Unlock => Ok(transactions::Type::Unlock),
+            Type::Freeze => Ok(transactions::Type::Freeze),
+            Type::Close => Ok(transactions::Type::Close),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Transaction {
+    #[prost(enumeration = "Type", tag = "1")]
+    pub r#type: i32,
+    #[prost(uint32, tag = "2")]
+    pub client: u32,
+    #[prost(uint32, tag = "3")]
+    pub tx: u32,
+    #[prost(string, optional, tag = "4")]
+    pub amount: Option<String>,
+    #[prost(string, optional, tag = "5")]
+    pub asset: Option<String>,
+    #[prost(uint32, optional, tag = "6")]
+    pub to_client: Option<u32>,
+    #[prost(uint64, optional, tag = "7")]
+    pub timestamp: Option<u64>,
+    #[prost(string, optional, tag = "8")]
+    pub to_asset: Option<String>,
+    #[prost(string, optional, tag = "9")]
+    pub reason: Option<String>,
+}
+
+impl Transaction {
+    /// Borrow fields as transaction of engine, fails if any is out of range
+    pub fn to_transaction(&self) -> Result<transactions::Transaction<'_>, String> {
+        let type_ =
+            Type::try_from(self.r#type).map_err(|_| format!("unknown type: {}", self.r#type))?;
+        let type_ = transactions::Type::try_from(type_)?;
+        let client = u16::try_from(self.client)
+            .map_err(|_| format!("client out of range: {}", self.client))?;
+        let to_client = self
+            .to_client
+            .map(|to_client| {
+                u16::try_from(to_client)
+                    .map_err(|_| format!("to_client out of range: {}", to_client))
+            })
+            .transpose()?;
+
+        Ok(transactions::Transaction {
+            type_,
+            client,
+            tx: self.tx,
+            amount: self.amount.as_deref(),
+            asset: self.asset.as_deref(),
+            to_client,
+            timestamp: self.timestamp,
+            to_asset: self.to_asset.as_deref(),
+            reason: self.reason.as_deref(),
+        })
+    }
+}
+
+/// Declare codes of errors once, both as enum and as names given by `code` of errors
+macro_rules! error_codes {
+    ($($name:ident = $value:literal,)*) => {
+        /// Errors which reject single transaction
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Enumeration)]
+        #[repr(i32)]
+        pub enum ErrorCode {
+            /// Error which is not caused by transaction itself
+            ErrorCodeUnknown = 0,
+            $($name = $value,)*
+        }
+
+        impl ErrorCode {
+            /// Get code of name of error variant, e.g. AccountLocked
+            pub fn from_code(code: &str) -> Self {
+                match code {
+                    $(stringify!($name) => ErrorCode::$name,)*
+                    _ => ErrorCode::ErrorCodeUnknown,
+                }
+            }
+
+            /// All known codes
+            pub fn all() -> &'static [ErrorCode] {
+                &[$(ErrorCode::$name,)*]
+            }
+        }
+    };
+}
+
+error_codes! {
+    AccountLocked = 1,
+    AccountFrozen = 2,
+    AccountClosed = 3,
+    CannotCloseAccountWithFunds = 4,
+    AccountDoesNotExist = 5,
+    CannotFindAccount = 6,
+    TransactionNotUnique = 7,
+    CannotFindTransaction = 8,
+    CannotDeposit = 9,
+    CannotDepositTotalExceededMaxLimit = 10,
+    DepositTryAgain = 11,
+    CannotWithdrawal = 12,
+    DisputeAlreadyDisputed = 13,
+    DisputeCannotSubstractAvailable = 14,
+    DisputeCannotAddHeld = 15,
+    TransactionNotDisputed = 16,
+    ResolveCannotAddAvailable = 17,
+    ResolveCannotSubstractHeld = 18,
+    ChargebackCannotSubstractHeld = 19,
+    ChargebackCannotAddAvailable = 20,
+    NotPartyOfTransaction = 21,
+    TransferToItself = 22,
+    ExchangeSameAsset = 23,
+    CannotFindRate = 24,
+    ExchangeOutOfRange = 25,
+    FeeExceedsAmount = 26,
+    FeeOutOfRange = 27,
+    CannotRefundFee = 28,
+    CannotChargeFee = 29,
+    WithdrawalAboveLimit = 30,
+    DepositAboveLimit = 31,
+    WithdrawalCapExceeded = 32,
+    TooManyOperations = 33,
+    LimitNeedsTimestamp = 34,
+    MissedMandatoryToClientInInputRecord = 35,
+    MissedMandatoryToAssetInInputRecord = 36,
+    MissedMandatoryAmountInInputRecord = 37,
+    CannotParseMandatoryInputAmountInInputRecord = 38,
+    AssetMismatch = 39,
+    ExchangeToZero = 40,
+    TransactionNotDisputable = 41,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Rejection {
+    #[prost(enumeration = "ErrorCode", tag = "1")]
+    pub code: i32,
+    #[prost(string, tag = "2")]
+    pub message: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Outcome {
+    #[prost(bool, tag = "1")]
+    pub applied: bool,
+    #[prost(string, optional, tag = "2")]
+    pub rounded: Option<String>,
+    #[prost(message, optional, tag = "3")]
+    pub rejection: Option<Rejection>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct AccountsRequest {
+    #[prost(uint32, optional, tag = "1")]
+    pub client: Option<u32>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Account {
+    #[prost(uint32, tag = "1")]
+    pub client: u32,
+    #[prost(string, tag = "2")]
+    pub asset: String,
+    #[prost(string, tag = "3")]
+    pub available: String,
+    #[prost(string, tag = "4")]
+    pub held: String,
+    #[prost(string, tag = "5")]
+    pub total: String,
+    #[prost(bool, tag = "6")]
+    pub locked: bool,
+    #[prost(string, tag = "7")]
+    pub status: String,
+    #[prost(string, optional, tag = "8")]
+    pub reason: Option<String>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Accounts {
+    #[prost(message, repeated, tag = "1")]
+    pub accounts: Vec<Account>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // External paths
+    use transactions_processor::api::asset::Asset;
+    use transactions_processor::api::currency::balance::Balance;
+    use transactions_processor::api::currency::error::CurrencyError;
+    use transactions_processor::api::currency::Currency;
+    use transactions_processor::api::engine::error::EngineError;
+    use transactions_processor::api::engine::limits::Window;
+
+    const PROTO: &str = include_str!("../proto/transactions.proto");
+
+    /// Name of enum value in proto, e.g. ACCOUNT_LOCKED
+    fn proto_name(value: impl std::fmt::Debug) -> String {
+        let mut name = String::new();
+        for (index, character) in format!("{:?}", value).chars().enumerate() {
+            if character.is_uppercase() && index > 0 {
+                name.push('_');
+            }
+            name.push(character.to_ascii_uppercase());
+        }
+        name
+    }
+
+    /// One error of each variant of engine
+    fn engine_errors() -> Vec<EngineError> {
+        vec![
+            EngineError::AccountLocked(1),
+            EngineError::AccountFrozen(1),
+            EngineError::AccountClosed(1),
+            EngineError::CannotCloseAccountWithFunds(1),
+            EngineError::AccountDoesNotExist(1),
+            EngineError::CannotFindAccount(1),
+            EngineError::TransactionNotUnique(1),
+            EngineError::CannotFindTransaction(1),
+            EngineError::CannotDeposit {
+                client: 1,
+                tx: 1,
+                amount: Currency::zero(),
+                source: CurrencyError::AddingOtherOutOfRange,
+            },
+            EngineError::CannotDepositTotalExceededMaxLimit {
+                client: 1,
+                tx: 1,
+                amount: Currency::zero(),
+                available: Balance::zero(),
+                held: Currency::zero(),
+                source: CurrencyError::AddingOtherOutOfRange,
+            },
+            EngineError::DepositTryAgain(1),
+            EngineError::CannotWithdrawal {
+                client: 1,
+                tx: 1,
+                amount: Currency::zero(),
+                source: CurrencyError::AddingOtherOutOfRange,
+            },
+            EngineError::DisputeAlreadyDisputed(1),
+            EngineError::DisputeCannotSubstractAvailable {
+                source: CurrencyError::AddingOtherOutOfRange,
+            },
+            EngineError::DisputeCannotAddHeld {
+                source: CurrencyError::AddingOtherOutOfRange,
+            },
+            EngineError::TransactionNotDisputed(1),
+            EngineError::TransactionNotDisputable(1),
+            EngineError::ResolveCannotAddAvailable {
+                source: CurrencyError::AddingOtherOutOfRange,
+            },
+            EngineError::ResolveCannotSubstractHeld {
+                source: CurrencyError::AddingOtherOutOfRange,
+            },
+            EngineError::ChargebackCannotSubstractHeld {
+                source: CurrencyError::AddingOtherOutOfRange,
+            },
+            EngineError::ChargebackCannotAddAvailable {
+                source: CurrencyError::AddingOtherOutOfRange,
+            },
+            EngineError::NotPartyOfTransaction { client: 1, tx: 1 },
+            EngineError::TransferToItself(1),
+            EngineError::ExchangeSameAsset(1),
+            EngineError::CannotFindRate {
+                from: Asset::default(),
+                to: Asset::default(),
+            },
+            EngineError::ExchangeOutOfRange {
+                tx: 1,
+                amount: Currency::zero(),
+            },
+            EngineError::ExchangeToZero {
+                tx: 1,
+                amount: Currency::zero(),
+            },
+            EngineError::FeeExceedsAmount {
+                tx: 1,
+                amount: Currency::zero(),
+                fee: Currency::zero(),
+            },
+            EngineError::FeeOutOfRange {
+                tx: 1,
+                amount: Currency::zero(),
+            },
+            EngineError::CannotRefundFee {
+                source: CurrencyError::AddingOtherOutOfRange,
+            },
+            EngineError::CannotChargeFee {
+                source: CurrencyError::AddingOtherOutOfRange,
+            },
+            EngineError::WithdrawalAboveLimit {
+                client: 1,
+                tx: 1,
+                amount: Currency::zero(),
+                limit: Currency::zero(),
+            },
+            EngineError::DepositAboveLimit {
+                client: 1,
+                tx: 1,
+                amount: Currency::zero(),
+                limit: Currency::zero(),
+            },
+            EngineError::WithdrawalCapExceeded {
+                client: 1,
+                tx: 1,
+                amount: Currency::zero(),
+                cap: Currency::zero(),
+                window: Window::Day,
+            },
+            EngineError::TooManyOperations {
+                client: 1,
+                tx: 1,
+                count: 1,
+                window: Window::Day,
+            },
+            EngineError::LimitNeedsTimestamp(1),
+        ]
+    }
+
+    /// Compiler stops here when new variant is not listed in `engine_errors`
+    fn listed(err: &EngineError) -> bool {
+        match err {
+            EngineError::AccountLocked(..)
+            | EngineError::AccountFrozen(..)
+            | EngineError::AccountClosed(..)
+            | EngineError::CannotCloseAccountWithFunds(..)
+            | EngineError::AccountDoesNotExist(..)
+            | EngineError::CannotFindAccount(..)
+            | EngineError::TransactionNotUnique(..)
+            | EngineError::CannotFindTransaction(..)
+            | EngineError::CannotDeposit { .. }
+            | EngineError::CannotDepositTotalExceededMaxLimit { .. }
+            | EngineError::DepositTryAgain(..)
+            | EngineError::CannotWithdrawal { .. }
+            | EngineError::DisputeAlreadyDisputed(..)
+            | EngineError::DisputeCannotSubstractAvailable { .. }
+            | EngineError::DisputeCannotAddHeld { .. }
+            | EngineError::TransactionNotDisputed(..)
+            | EngineError::TransactionNotDisputable(..)
+            | EngineError::ResolveCannotAddAvailable { .. }
+            | EngineError::ResolveCannotSubstractHeld { .. }
+            | EngineError::ChargebackCannotSubstractHeld { .. }
+            | EngineError::ChargebackCannotAddAvailable { .. }
+            | EngineError::NotPartyOfTransaction { .. }
+            | EngineError::TransferToItself(..)
+            | EngineError::ExchangeSameAsset(..)
+            | EngineError::CannotFindRate { .. }
+            | EngineError::ExchangeOutOfRange { .. }
+            | EngineError::ExchangeToZero { .. }
+            | EngineError::FeeExceedsAmount { .. }
+            | EngineError::FeeOutOfRange { .. }
+            | EngineError::CannotRefundFee { .. }
+            | EngineError::CannotChargeFee { .. }
+            | EngineError::WithdrawalAboveLimit { .. }
+            | EngineError::DepositAboveLimit { .. }
+            | EngineError::WithdrawalCapExceeded { .. }
+            | EngineError::TooManyOperations { .. }
+            | EngineError::LimitNeedsTimestamp(..) => true,
+        }
+    }
+
+    #[test]
+    fn engine_errors_have_codes() {
+        for err in engine_errors() {
+            assert!(listed(&err));
+            assert_ne!(
+                ErrorCode::from_code(err.code()),
+                ErrorCode::ErrorCodeUnknown,
+                "missed code of: {:?}",
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn error_codes_match_proto() {
+        for code in ErrorCode::all() {
+            let line = format!("{} = {};", proto_name(*code), *code as i32);
+            assert!(PROTO.contains(&line), "missed in proto: {}", line);
+        }
+        assert_eq!(
+            ErrorCode::from_code("AccountLocked"),
+            ErrorCode::AccountLocked
+        );
+        assert_eq!(
+            ErrorCode::from_code("Unbalanced"),
+            ErrorCode::ErrorCodeUnknown
+        );
+    }
+
+    #[test]
+    fn types_match_proto() {
+        for value in 0..=Type::Close as i32 {
+            // Should never panic unless enum has gaps
+            let type_ = Type::try_from(value).unwrap();
+            let line = format!("{} = {};", proto_name(type_), value);
+            assert!(PROTO.contains(&line), "missed in proto: {}", line);
+        }
+    }
+
+    #[test]
+    fn transaction_without_type() {
+        let transaction = Transaction {
+            client: 1,
+            tx: 1,
+            amount: Some(String::from("1.0")),
+            ..Transaction::default()
+        };
+        assert_eq!(
+            transaction.to_transaction().err(),
+            Some(String::from("type is not specified"))
+        );
+    }
+
+    #[test]
+    fn transaction_out_of_range() {
+        let transaction = Transaction {
+            r#type: Type::Deposit as i32,
+            client: 70000,
+            ..Transaction::default()
+        };
+        assert!(transaction.to_transaction().is_err());
+    }
+}
@@ -0,0 +1,162 @@
+//! Implementation of `Transactions` service backed by shared engine
+
+// Standard paths
+use std::sync::Arc;
+
+// Crate paths
+use crate::proto::service::transactions_server;
+use crate::proto::Account;
+use crate::proto::Accounts;
+use crate::proto::AccountsRequest;
+use crate::proto::ErrorCode;
+use crate::proto::Outcome;
+use crate::proto::Rejection;
+use crate::proto::Transaction;
+use transactions_processor::service::Service;
+use transactions_processor::Processed;
+
+// External paths
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::StreamExt;
+use tonic::Request;
+use tonic::Response;
+use tonic::Status;
+use tonic::Streaming;
+
+/// Outcomes of stream waiting to be sent before next transactions are applied
+const STREAM_BUFFER: usize = 64;
+
+#[derive(Clone)]
+pub struct TransactionsService {
+    service: Arc<Service>,
+}
+
+impl TransactionsService {
+    pub fn new(service: Arc<Service>) -> Self {
+        Self { service }
+    }
+
+    /// Server to add to router of tonic
+    pub fn into_server(self) -> transactions_server::TransactionsServer<Self> {
+        transactions_server::TransactionsServer::new(self)
+    }
+
+    /// Fails only if message cannot be transaction of engine
+    fn submit_one(&self, transaction: &Transaction) -> Result<Outcome, String> {
+        let transaction = transaction.to_transaction()?;
+
+        Ok(outcome(self.service.submit(&transaction)))
+    }
+}
+
+/// Run work which may wait for locks of engine off threads of async runtime
+async fn blocking<T, F>(work: F) -> Result<T, Status>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    tokio::task::spawn_blocking(work)
+        .await
+        .map_err(|err| Status::internal(format!("{:?}", err)))
+}
+
+fn outcome(processed: Processed) -> Outcome {
+    match processed {
+        Ok((_, rounded)) => Outcome {
+            applied: true,
+            rounded: rounded.map(|rounded| rounded.rounded.to_string()),
+            rejection: None,
+        },
+        Err(err) => Outcome {
+            applied: false,
+            rounded: None,
+            rejection: Some(Rejection {
+                code: ErrorCode::from_code(err.code()) as i32,
+                message: format!("{:?}", err),
+            }),
+        },
+    }
+}
+
+#[tonic::async_trait]
+impl transactions_server::Transactions for TransactionsService {
+    async fn submit(&self, request: Request<Transaction>) -> Result<Response<Outcome>, Status> {
+        let service = self.clone();
+        let transaction = request.into_inner();
+
+        blocking(move || service.submit_one(&transaction))
+            .await?
+            .map(Response::new)
+            .map_err(Status::invalid_argument)
+    }
+
+    type SubmitStreamStream = ReceiverStream<Result<Outcome, Status>>;
+
+    async fn submit_stream(
+        &self,
+        request: Request<Streaming<Transaction>>,
+    ) -> Result<Response<Self::SubmitStreamStream>, Status> {
+        let mut transactions = request.into_inner();
+        let (sender, receiver) = mpsc::channel(STREAM_BUFFER);
+        let service = self.clone();
+
+        tokio::spawn(async move {
+            while let Some(transaction) = transactions.next().await {
+                // Invalid message ends stream, as following ones may depend on it
+                let result = match transaction {
+                    Ok(transaction) => {
+                        let service = service.clone();
+                        match blocking(move || service.submit_one(&transaction)).await {
+                            Ok(submitted) => submitted.map_err(Status::invalid_argument),
+                            Err(status) => Err(status),
+                        }
+                    }
+                    Err(status) => Err(status),
+                };
+                let failed = result.is_err();
+                // Waits while client does not read outcomes, so it is not flooded
+                if sender.send(result).await.is_err() || failed {
+                    break;
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(receiver)))
+    }
+
+    async fn get_accounts(
+        &self,
+        request: Request<AccountsRequest>,
+    ) -> Result<Response<Accounts>, Status> {
+        let client = request.get_ref().client;
+        let service = self.service.clone();
+
+        let accounts = blocking(move || {
+            // Consistent view even if other requests apply transactions meanwhile
+            let snapshot = service.engine().snapshot();
+
+            snapshot
+                .accounts()
+                .iter()
+                .filter(|(id, _)| client.is_none_or(|client| u32::from(id.client) == client))
+                .map(|(id, account)| Account {
+                    client: u32::from(id.client),
+                    asset: id.asset.to_string(),
+                    available: account.available.to_string(),
+                    held: account.held.to_string(),
+                    total: account
+                        .total()
+                        .map(|total| total.to_string())
+                        .unwrap_or_default(),
+                    locked: account.is_locked(),
+                    status: account.status.name().to_string(),
+                    reason: account.status.reason().map(str::to_string),
+                })
+                .collect()
+        })
+        .await?;
+
+        Ok(Response::new(Accounts { accounts }))
+    }
+}
@@ -0,0 +1,114 @@
+// Standard paths
+use std::error::Error;
+use std::sync::Arc;
+
+// Crate paths
+use transactions_processor::api::engine::Engine;
+use transactions_processor::options::Options;
+use transactions_processor::service::Service;
+use transactions_processor_grpc::proto::service::transactions_client::TransactionsClient;
+use transactions_processor_grpc::proto::AccountsRequest;
+use transactions_processor_grpc::proto::ErrorCode;
+use transactions_processor_grpc::proto::Transaction;
+use transactions_processor_grpc::proto::Type;
+use transactions_processor_grpc::server::TransactionsService;
+
+// External paths
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::TcpListenerStream;
+use tokio_stream::StreamExt;
+use tonic::transport::Channel;
+
+/// Run server on any free port of loopback and connect to it
+async fn start() -> Result<TransactionsClient<Channel>, Box<dyn Error>> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let address = listener.local_addr()?;
+    let service =
+        TransactionsService::new(Arc::new(Service::new(Engine::new(), Options::default())));
+
+    tokio::spawn(
+        tonic::transport::Server::builder()
+            .add_service(service.into_server())
+            .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+
+    Ok(TransactionsClient::connect(format!("http://{}", address)).await?)
+}
+
+fn transaction(type_: Type, client: u32, tx: u32, amount: Option<&str>) -> Transaction {
+    Transaction {
+        r#type: type_ as i32,
+        client,
+        tx,
+        amount: amount.map(str::to_string),
+        ..Transaction::default()
+    }
+}
+
+#[tokio::test]
+async fn submit_and_get_accounts() -> Result<(), Box<dyn Error>> {
+    let mut client = start().await?;
+
+    let outcome = client
+        .submit(transaction(Type::Deposit, 1, 1, Some("10.0")))
+        .await?
+        .into_inner();
+    assert!(outcome.applied);
+
+    let outcome = client
+        .submit(transaction(Type::Withdrawal, 1, 2, Some("20.0")))
+        .await?
+        .into_inner();
+    assert!(!outcome.applied);
+    let rejection = outcome.rejection.ok_or("missed rejection")?;
+    assert_eq!(rejection.code, ErrorCode::CannotWithdrawal as i32);
+
+    let status = client
+        .submit(transaction(Type::Deposit, 70000, 3, Some("1.0")))
+        .await
+        .err()
+        .ok_or("client out of range accepted")?;
+    assert_eq!(status.code(), tonic::Code::InvalidArgument);
+
+    let accounts = client
+        .get_accounts(AccountsRequest { client: Some(1) })
+        .await?
+        .into_inner()
+        .accounts;
+    assert_eq!(accounts.len(), 1);
+    assert_eq!(accounts[0].available, "10.0");
+    assert_eq!(accounts[0].status, "active");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn submit_stream() -> Result<(), Box<dyn Error>> {
+    let mut client = start().await?;
+
+    let transactions = vec![
+        transaction(Type::Deposit, 2, 1, Some("5.0")),
+        transaction(Type::Dispute, 2, 1, None),
+        transaction(Type::Chargeback, 2, 1, None),
+        transaction(Type::Deposit, 2, 2, Some("1.0")),
+    ];
+    let mut outcomes = client
+        .submit_stream(tokio_stream::iter(transactions))
+        .await?
+        .into_inner();
+
+    let mut applied = Vec::new();
+    while let Some(outcome) = outcomes.next().await {
+        applied.push(outcome?.applied);
+    }
+    assert_eq!(applied, [true, true, true, false]);
+
+    let accounts = client
+        .get_accounts(AccountsRequest { client: None })
+        .await?
+        .into_inner()
+        .accounts;
+    assert!(accounts[0].locked);
+
+    Ok(())
+}
@@ -1,3 +1,4 @@
+pub mod asset;
 pub mod currency;
 pub mod engine;
 pub mod error;
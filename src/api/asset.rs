@@ -0,0 +1,57 @@
+//! Assets held on accounts, e.g. currencies
+
+// Standard paths
+use std::collections::HashMap;
+use std::fmt;
+
+// Crate paths
+use crate::api::currency::parse::ParseOptions;
+
+// External paths
+use serde::Serialize;
+
+/// Code of asset, e.g. `USD` or `BTC`
+///
+/// Default asset has empty code. It is used for records without asset given,
+/// so files without asset column are processed as before.
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+pub struct Asset(String);
+
+impl Asset {
+    pub fn new(code: &str) -> Self {
+        Self(code.to_string())
+    }
+
+    pub fn code(&self) -> &str {
+        &self.0
+    }
+
+    pub fn is_default(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl fmt::Display for Asset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Settings which differ between assets
+#[derive(Debug, Clone, Default)]
+pub struct AssetOptions {
+    /// Max number of fractional digits of amounts in given asset
+    ///
+    /// Assets not listed use full scale of amounts.
+    pub precisions: HashMap<Asset, u32>,
+}
+
+impl AssetOptions {
+    /// Adjust `options` to read amounts of given `asset`
+    pub fn parse_options(&self, asset: &Asset, options: &ParseOptions) -> ParseOptions {
+        ParseOptions {
+            precision: self.precisions.get(asset).copied().or(options.precision),
+            ..*options
+        }
+    }
+}
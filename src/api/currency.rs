@@ -1,41 +1,98 @@
-// TODO internal type u32
+// Standard paths
 use std::convert::TryFrom;
 use std::fmt;
+use std::iter::Sum;
+use std::marker::PhantomData;
+use std::str::FromStr;
 
 // Crate paths
+use crate::api::currency::backing::Backing;
 use crate::api::currency::error::CurrencyError;
+use crate::api::currency::format::Format;
+use crate::api::currency::parse::ParseOptions;
+use crate::api::currency::rounding::RoundingMode;
+
+// External paths
+use serde::de;
+use serde::Deserialize;
+use serde::Deserializer;
+use serde::Serialize;
+use serde::Serializer;
 
 // Crate modules
+pub mod backing;
+pub mod balance;
 pub mod error;
+pub mod format;
+pub mod parse;
+pub mod rounding;
 
 const PRECISION: usize = 4;
-const BASE: u64 = 10_u64.pow(PRECISION as u32);
 
-#[derive(Debug, Copy, Clone, PartialEq)]
-pub struct Currency(u64);
+/// Amount used by engine
+///
+/// It is stored as unsigned integer on 64 bits with 4 fractional digits. It is
+/// the only amount engine, input and report work with, other `Decimal` types
+/// are not accepted by them.
+pub type Currency = Decimal<u64, { PRECISION as u32 }>;
+
+/// Amount with wide range to sum many amounts of engine without overflow
+pub type WideCurrency = Decimal<u128, { PRECISION as u32 }>;
+
+/// Fixed point amount with `SCALE` fractional digits kept as integer `T`
+///
+/// Any `SCALE` which does not fit into `T` is rejected at compile time.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Decimal<T: Backing, const SCALE: u32>(T);
+
+impl<T: Backing, const SCALE: u32> Decimal<T, SCALE> {
+    const SCALE_FITS_BACKING: () = assert!(SCALE <= T::MAX_SCALE, "scale too big for backing");
 
-// TODO generic types for decimal and fractional
-impl Currency {
-    pub fn new(decimal: u64, fractional: u64) -> Result<Self, CurrencyError> {
-        if fractional >= BASE {
-            return Err(CurrencyError::FractionalOutOfRange(fractional));
+    pub fn new(decimal: T, fractional: T) -> Result<Self, CurrencyError> {
+        let base = Self::base();
+
+        if fractional < T::ZERO || fractional >= base {
+            return Err(CurrencyError::FractionalOutOfRange(fractional.to_string()));
         }
 
-        let value = decimal.checked_mul(BASE).ok_or(
-            CurrencyError::DecimalMultipliedByPrecisionOutOfRange(decimal),
-        )?;
+        let value = decimal.checked_mul(base).ok_or_else(|| {
+            CurrencyError::DecimalMultipliedByPrecisionOutOfRange(decimal.to_string())
+        })?;
 
-        let value = value.checked_add(fractional).ok_or(
-            CurrencyError::DecimalAddedFractionalOutOfRange(decimal, fractional),
-        )?;
+        let value = value.checked_add(fractional).ok_or_else(|| {
+            CurrencyError::DecimalAddedFractionalOutOfRange(
+                decimal.to_string(),
+                fractional.to_string(),
+            )
+        })?;
 
         Ok(Self(value))
     }
 
+    pub const fn zero() -> Self {
+        Self(T::ZERO)
+    }
+
     pub fn max() -> Self {
+        let base = Self::base();
+
         // Go through checks in new to never bypass them
         // Should never panic unless logic is buggy
-        Self::new(u64::MAX / BASE, u64::MAX % BASE).unwrap()
+        Self::new(T::MAX / base, T::MAX % base).unwrap()
+    }
+
+    /// Number of fractional digits
+    pub const fn scale() -> u32 {
+        SCALE
+    }
+
+    /// Internal value of one unit of amount
+    pub fn base() -> T {
+        // Evaluated for each used scale, fails build if it does not fit
+        #[allow(clippy::let_unit_value)]
+        let () = Self::SCALE_FITS_BACKING;
+
+        T::pow10(SCALE)
     }
 
     pub fn add(&mut self, other: Self) -> Result<(), CurrencyError> {
@@ -55,39 +112,150 @@ impl Currency {
 
         Ok(())
     }
+
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        self.0.checked_add(other.0).map(Self)
+    }
+
+    /// Substract other value, `None` if result would be negative
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        self.0.checked_sub(other.0).map(Self)
+    }
+
+    /// Multiply by ratio of `numerator` and `denominator`, e.g. percentage or rate
+    ///
+    /// Digits beyond scale are rounded with given `rounding` mode.
+    pub fn checked_mul_ratio(
+        self,
+        numerator: T,
+        denominator: T,
+        rounding: RoundingMode,
+    ) -> Option<Self> {
+        let (quotient, remainder) = self.0.checked_mul_div(numerator, denominator)?;
+
+        if rounding.rounds_up(quotient, remainder, denominator) {
+            return quotient.checked_add(T::ONE).map(Self);
+        }
+
+        Some(Self(quotient))
+    }
+
+    /// Multiply by other fixed point value of any scale, e.g. exchange rate
+    ///
+    /// Digits beyond scale are rounded with given `rounding` mode.
+    pub fn checked_mul_decimal<const OTHER_SCALE: u32>(
+        self,
+        other: Decimal<T, OTHER_SCALE>,
+        rounding: RoundingMode,
+    ) -> Option<Self> {
+        self.checked_mul_ratio(other.0, Decimal::<T, OTHER_SCALE>::base(), rounding)
+    }
+
+    /// Take given percent of value, e.g. fee
+    ///
+    /// Digits beyond scale are rounded with given `rounding` mode.
+    pub fn checked_mul_percent<const OTHER_SCALE: u32>(
+        self,
+        percent: Decimal<T, OTHER_SCALE>,
+        rounding: RoundingMode,
+    ) -> Option<Self> {
+        let hundred = T::pow10(2);
+        let denominator = Decimal::<T, OTHER_SCALE>::base().checked_mul(hundred)?;
+        self.checked_mul_ratio(percent.0, denominator, rounding)
+    }
+
+    /// Text representation with all fractional digits, it never loses precision
+    pub(crate) fn to_fixed_string(self) -> String {
+        let base = Self::base();
+        if SCALE == 0 {
+            return (self.0 / base).to_string();
+        }
+
+        format!(
+            "{}.{:0>width$}",
+            self.0 / base,
+            (self.0 % base).to_string(),
+            width = SCALE as usize
+        )
+    }
 }
 
-impl TryFrom<&str> for Currency {
+impl<T: Backing, const SCALE: u32> TryFrom<&str> for Decimal<T, SCALE> {
     type Error = CurrencyError;
 
     fn try_from(input: &str) -> Result<Self, CurrencyError> {
-        let mut parts = input.split('.');
+        Self::parse_with(input, &ParseOptions::default()).map(|parsed| parsed.value)
+    }
+}
 
-        // Even when input is empty, desimal part is read from iterator as empty
-        let decimal = parts.next().ok_or(CurrencyError::CannotGetDecimalPart)?;
-        let decimal = decimal
-            .parse::<u64>()
-            .map_err(|err| CurrencyError::CannotParseDecimalPart { source: err })?;
+impl<T: Backing, const SCALE: u32> FromStr for Decimal<T, SCALE> {
+    type Err = CurrencyError;
 
-        let fractional = parts.next().unwrap_or("0");
-        if fractional.len() > PRECISION {
-            return Err(CurrencyError::FractionalTooLong(fractional.to_string()));
-        }
-        let fractional = String::from(fractional) + &"0".repeat(PRECISION - fractional.len());
-        let fractional = fractional
-            .parse::<u64>()
-            .map_err(|err| CurrencyError::CannotParseFractionalPart { source: err })?;
+    fn from_str(input: &str) -> Result<Self, CurrencyError> {
+        Self::try_from(input)
+    }
+}
 
-        Self::new(decimal, fractional)
+/// Sum which gives error instead of overflow
+impl<T: Backing, const SCALE: u32> Sum<Decimal<T, SCALE>>
+    for Result<Decimal<T, SCALE>, CurrencyError>
+{
+    fn sum<I: Iterator<Item = Decimal<T, SCALE>>>(mut iter: I) -> Self {
+        iter.try_fold(Decimal::zero(), |mut sum, item| {
+            sum.add(item)?;
+            Ok(sum)
+        })
     }
 }
 
-impl fmt::Display for Currency {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let decimal = self.0 / BASE;
-        let fractional = self.0 % BASE;
+impl<'a, T: Backing, const SCALE: u32> Sum<&'a Decimal<T, SCALE>>
+    for Result<Decimal<T, SCALE>, CurrencyError>
+{
+    fn sum<I: Iterator<Item = &'a Decimal<T, SCALE>>>(iter: I) -> Self {
+        iter.copied().sum()
+    }
+}
+
+impl<const SCALE: u32> From<Decimal<u64, SCALE>> for Decimal<u128, SCALE> {
+    fn from(value: Decimal<u64, SCALE>) -> Self {
+        Self(u128::from(value.0))
+    }
+}
+
+/// Serialized as decimal string to not lose precision on the way
+impl<T: Backing, const SCALE: u32> Serialize for Decimal<T, SCALE> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_fixed_string())
+    }
+}
+
+impl<'de, T: Backing, const SCALE: u32> Deserialize<'de> for Decimal<T, SCALE> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_str(DecimalStrVisitor::<Self>(PhantomData))
+    }
+}
 
-        write!(f, "{}.{}", decimal, fractional)
+/// Deserialize any amount from its decimal string
+struct DecimalStrVisitor<V>(PhantomData<V>);
+
+impl<'de, V> de::Visitor<'de> for DecimalStrVisitor<V>
+where
+    V: for<'a> TryFrom<&'a str, Error = CurrencyError>,
+{
+    type Value = V;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "amount as decimal string")
+    }
+
+    fn visit_str<E: de::Error>(self, input: &str) -> Result<V, E> {
+        V::try_from(input).map_err(de::Error::custom)
+    }
+}
+
+impl<T: Backing, const SCALE: u32> fmt::Display for Decimal<T, SCALE> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", Format::default().format(*self))
     }
 }
 
@@ -97,11 +265,18 @@ mod tests {
     use super::*;
     use assert_matches::assert_matches;
 
+    const BASE: u64 = 10_u64.pow(PRECISION as u32);
+
     #[test]
     fn correct_min_value() {
         assert!(Currency::new(0, 0).is_ok());
     }
 
+    #[test]
+    fn zero_is_min_value() {
+        assert_eq!(Currency::zero(), Currency::new(0, 0).unwrap());
+    }
+
     #[test]
     fn correct_max_decimal_min_fractional() {
         assert!(Currency::new(u64::MAX / BASE, 0).is_ok());
@@ -217,4 +392,146 @@ mod tests {
             Err(CurrencyError::CannotParseFractionalPart { .. })
         );
     }
+
+    #[test]
+    fn correct_max_value_of_wide_backing() {
+        assert_eq!(
+            Decimal::<u128, 18>::max(),
+            Decimal::<u128, 18>::new(u128::MAX / 10_u128.pow(18), u128::MAX % 10_u128.pow(18))
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn compare_parsed_long_fractional_of_wide_backing() {
+        let expected = Decimal::<u128, 18>::new(1, 1).unwrap();
+        let parsed = Decimal::<u128, 18>::try_from("1.000000000000000001").unwrap();
+        assert_eq!(parsed, expected);
+    }
+
+    #[test]
+    fn ok_to_parse_above_u64_range_with_wide_backing() {
+        assert!(Decimal::<i128, 8>::try_from("18446744073709551616.12345678").is_ok());
+    }
+
+    #[test]
+    fn cannot_parse_negative_with_signed_backing() {
+        assert_matches!(
+            Decimal::<i128, 8>::try_from("-1.0"),
+            Err(CurrencyError::CannotParseDecimalPart { .. })
+        );
+    }
+
+    #[test]
+    fn incorrect_negative_with_signed_backing() {
+        assert_matches!(
+            Decimal::<i128, 8>::new(-1, 0),
+            Err(CurrencyError::DecimalMultipliedByPrecisionOutOfRange(..))
+        );
+        assert_matches!(
+            Decimal::<i128, 8>::new(1, -1),
+            Err(CurrencyError::FractionalOutOfRange(..))
+        );
+    }
+
+    #[test]
+    fn incorrect_substract_underflow_with_signed_backing() {
+        let mut first = Decimal::<i128, 8>::new(1, 1).unwrap();
+        let second = Decimal::<i128, 8>::new(2, 2).unwrap();
+        assert_matches!(
+            first.substract(second),
+            Err(CurrencyError::SubstractingOtherNegative)
+        );
+    }
+
+    #[test]
+    fn compare_order() {
+        let less = Currency::new(1, 1).unwrap();
+        let more = Currency::new(1, 2).unwrap();
+        assert!(less < more);
+        assert_eq!(more.max(less), more);
+    }
+
+    #[test]
+    fn parse_from_str() {
+        assert_eq!("1.5".parse::<Currency>(), Currency::new(1, 5000));
+    }
+
+    #[test]
+    fn checked_add_and_sub_give_new_values() {
+        let first = Currency::new(1, 1).unwrap();
+        let second = Currency::new(2, 2).unwrap();
+        assert_eq!(first.checked_add(second), Currency::new(3, 3).ok());
+        assert_eq!(second.checked_sub(first), Some(first));
+        assert_eq!(first.checked_sub(second), None);
+        assert_eq!(Currency::max().checked_add(first), None);
+    }
+
+    #[test]
+    fn checked_mul_ratio_rounds() {
+        // 0.0005 * 1 / 2
+        let amount = Currency::new(0, 5).unwrap();
+        let half = |rounding| amount.checked_mul_ratio(1, 2, rounding);
+        assert_eq!(half(RoundingMode::Truncate), Currency::new(0, 2).ok());
+        assert_eq!(half(RoundingMode::Up), Currency::new(0, 3).ok());
+        assert_eq!(half(RoundingMode::HalfUp), Currency::new(0, 3).ok());
+        assert_eq!(half(RoundingMode::HalfEven), Currency::new(0, 2).ok());
+    }
+
+    #[test]
+    fn checked_mul_percent_rounded() {
+        let amount = Currency::new(10, 100).unwrap();
+        let percent = Decimal::<u64, 2>::new(1, 50).unwrap();
+        assert_eq!(
+            amount.checked_mul_percent(percent, RoundingMode::HalfUp),
+            Some(Currency::new(0, 1502).unwrap())
+        );
+        assert_eq!(
+            amount.checked_mul_percent(percent, RoundingMode::Truncate),
+            Some(Currency::new(0, 1501).unwrap())
+        );
+    }
+
+    #[test]
+    fn checked_mul_ratio_of_max() {
+        let max = Currency::max();
+        assert_eq!(
+            max.checked_mul_ratio(3, 3, RoundingMode::Truncate),
+            Some(max)
+        );
+        assert_eq!(max.checked_mul_ratio(2, 1, RoundingMode::Truncate), None);
+        assert_eq!(max.checked_mul_ratio(1, 0, RoundingMode::Truncate), None);
+    }
+
+    #[test]
+    fn sum_amounts() {
+        let amounts = [Currency::new(1, 1).unwrap(), Currency::new(2, 2).unwrap()];
+        let sum: Result<Currency, CurrencyError> = amounts.iter().sum();
+        assert_eq!(sum, Currency::new(3, 3));
+    }
+
+    #[test]
+    fn sum_amounts_overflow() {
+        let amounts = [Currency::max(), Currency::new(0, 1).unwrap()];
+        let sum: Result<Currency, CurrencyError> = amounts.iter().copied().sum();
+        assert_matches!(sum, Err(CurrencyError::AddingOtherOutOfRange));
+    }
+
+    #[test]
+    fn serialize_and_deserialize_as_decimal_string() {
+        let amount = Currency::new(1, 500).unwrap();
+
+        let mut wtr = csv::WriterBuilder::new()
+            .has_headers(false)
+            .from_writer(vec![]);
+        wtr.serialize(amount).unwrap();
+        let serialized = String::from_utf8(wtr.into_inner().unwrap()).unwrap();
+        assert_eq!(serialized, "1.0500\n");
+
+        let mut rdr = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .from_reader(serialized.as_bytes());
+        let deserialized: Currency = rdr.deserialize().next().unwrap().unwrap();
+        assert_eq!(deserialized, amount);
+    }
 }
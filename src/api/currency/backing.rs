@@ -0,0 +1,125 @@
+//! Integer types able to keep value of currency
+
+// Standard paths
+use std::fmt;
+use std::hash::Hash;
+use std::num::ParseIntError;
+use std::ops::Div;
+use std::ops::Rem;
+
+/// Integer used as internal representation of currency
+///
+/// Value of currency is never negative, even if integer is signed. Signed
+/// integer only gives range.
+pub trait Backing:
+    Copy + fmt::Debug + fmt::Display + Eq + Ord + Hash + Div<Output = Self> + Rem<Output = Self>
+{
+    const ZERO: Self;
+    const ONE: Self;
+    const MAX: Self;
+    /// Max number of fractional digits which can be represented at all
+    const MAX_SCALE: u32;
+
+    fn checked_add(self, other: Self) -> Option<Self>;
+    /// Substract, but never go below zero
+    fn checked_sub(self, other: Self) -> Option<Self>;
+    fn checked_mul(self, other: Self) -> Option<Self>;
+    fn is_odd(self) -> bool;
+    fn pow10(exp: u32) -> Self;
+    /// Parse unsigned decimal number
+    fn parse(input: &str) -> Result<Self, ParseIntError>;
+
+    /// Multiply by `numerator` and divide by `denominator`
+    ///
+    /// Gives quotient and remainder. Intermediate product is split to not
+    /// overflow as long as remainder multiplied by `numerator` fits.
+    fn checked_mul_div(self, numerator: Self, denominator: Self) -> Option<(Self, Self)> {
+        if numerator < Self::ZERO || denominator <= Self::ZERO {
+            return None;
+        }
+
+        let quotient = self / denominator;
+        let remainder = self % denominator;
+
+        let high = quotient.checked_mul(numerator)?;
+        let low = remainder.checked_mul(numerator)?;
+
+        Some((high.checked_add(low / denominator)?, low % denominator))
+    }
+}
+
+macro_rules! impl_backing_unsigned {
+    ($type:ty) => {
+        impl Backing for $type {
+            const ZERO: Self = 0;
+            const ONE: Self = 1;
+            const MAX: Self = <$type>::MAX;
+            const MAX_SCALE: u32 = <$type>::MAX.ilog10();
+
+            fn checked_add(self, other: Self) -> Option<Self> {
+                <$type>::checked_add(self, other)
+            }
+
+            fn checked_sub(self, other: Self) -> Option<Self> {
+                <$type>::checked_sub(self, other)
+            }
+
+            fn checked_mul(self, other: Self) -> Option<Self> {
+                <$type>::checked_mul(self, other)
+            }
+
+            fn is_odd(self) -> bool {
+                self % 2 == 1
+            }
+
+            fn pow10(exp: u32) -> Self {
+                <$type>::pow(10, exp)
+            }
+
+            fn parse(input: &str) -> Result<Self, ParseIntError> {
+                input.parse::<$type>()
+            }
+        }
+    };
+}
+
+macro_rules! impl_backing_signed {
+    ($type:ty, $unsigned:ty) => {
+        impl Backing for $type {
+            const ZERO: Self = 0;
+            const ONE: Self = 1;
+            const MAX: Self = <$type>::MAX;
+            const MAX_SCALE: u32 = <$type>::MAX.ilog10();
+
+            fn checked_add(self, other: Self) -> Option<Self> {
+                <$type>::checked_add(self, other).filter(|value| *value >= 0)
+            }
+
+            fn checked_sub(self, other: Self) -> Option<Self> {
+                <$type>::checked_sub(self, other).filter(|value| *value >= 0)
+            }
+
+            fn checked_mul(self, other: Self) -> Option<Self> {
+                <$type>::checked_mul(self, other).filter(|value| *value >= 0)
+            }
+
+            fn is_odd(self) -> bool {
+                self % 2 == 1
+            }
+
+            fn pow10(exp: u32) -> Self {
+                <$type>::pow(10, exp)
+            }
+
+            fn parse(input: &str) -> Result<Self, ParseIntError> {
+                // Reject sign the same way as unsigned types do
+                input.parse::<$unsigned>()?;
+                input.parse::<$type>()
+            }
+        }
+    };
+}
+
+impl_backing_unsigned!(u64);
+impl_backing_unsigned!(u128);
+impl_backing_signed!(i128, u128);
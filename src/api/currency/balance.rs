@@ -0,0 +1,350 @@
+//! Signed balance built on top of unsigned currency
+
+// Standard paths
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::fmt;
+use std::marker::PhantomData;
+use std::str::FromStr;
+
+// Crate paths
+use crate::api::currency::backing::Backing;
+use crate::api::currency::error::CurrencyError;
+use crate::api::currency::format::Format;
+use crate::api::currency::parse::parse;
+use crate::api::currency::parse::ParseOptions;
+use crate::api::currency::parse::Parsed;
+use crate::api::currency::Currency;
+use crate::api::currency::Decimal;
+use crate::api::currency::DecimalStrVisitor;
+
+// External paths
+use serde::Deserialize;
+use serde::Deserializer;
+use serde::Serialize;
+use serde::Serializer;
+
+/// Signed counterpart of `Currency` used by engine
+pub type Balance = SignedDecimal<u64, { Currency::scale() }>;
+
+/// Signed counterpart of `WideCurrency` to sum many balances without overflow
+pub type WideBalance = SignedDecimal<u128, { Currency::scale() }>;
+
+/// Amount which may go below zero
+///
+/// `Decimal` itself is never negative. Signed decimal keeps sign aside of its
+/// magnitude, so range is the same for both positive and negative values.
+/// Zero is always kept as positive.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct SignedDecimal<T: Backing, const SCALE: u32> {
+    negative: bool,
+    magnitude: Decimal<T, SCALE>,
+}
+
+impl<T: Backing, const SCALE: u32> SignedDecimal<T, SCALE> {
+    pub const fn zero() -> Self {
+        Self {
+            negative: false,
+            magnitude: Decimal::zero(),
+        }
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    pub fn magnitude(&self) -> Decimal<T, SCALE> {
+        self.magnitude
+    }
+
+    pub fn add(&mut self, other: Decimal<T, SCALE>) -> Result<(), CurrencyError> {
+        if !self.negative {
+            return self.magnitude.add(other);
+        }
+
+        // Negative balance is paid off partially
+        let mut magnitude = self.magnitude;
+        if magnitude.substract(other).is_ok() {
+            *self = Self::new(true, magnitude);
+            return Ok(());
+        }
+
+        // Negative balance is paid off fully and it goes above zero
+        let mut magnitude = other;
+        magnitude.substract(self.magnitude)?;
+        *self = Self::new(false, magnitude);
+
+        Ok(())
+    }
+
+    /// Substract other value, but never go below zero
+    pub fn substract(&mut self, other: Decimal<T, SCALE>) -> Result<(), CurrencyError> {
+        if self.negative {
+            return Err(CurrencyError::SubstractingOtherNegative);
+        }
+
+        self.magnitude.substract(other)
+    }
+
+    /// Substract other value even if balance becomes negative
+    pub fn substract_allow_negative(
+        &mut self,
+        other: Decimal<T, SCALE>,
+    ) -> Result<(), CurrencyError> {
+        if self.negative {
+            return self
+                .magnitude
+                .add(other)
+                .map_err(|_| CurrencyError::SubstractingOtherOutOfRange);
+        }
+
+        // Enough funds to stay above zero
+        let mut magnitude = self.magnitude;
+        if magnitude.substract(other).is_ok() {
+            self.magnitude = magnitude;
+            return Ok(());
+        }
+
+        // Go below zero
+        let mut magnitude = other;
+        magnitude.substract(self.magnitude)?;
+        *self = Self::new(true, magnitude);
+
+        Ok(())
+    }
+
+    /// Add other signed value, e.g. to sum balances
+    pub fn add_signed(&mut self, other: Self) -> Result<(), CurrencyError> {
+        if other.negative {
+            self.substract_allow_negative(other.magnitude)
+        } else {
+            self.add(other.magnitude)
+        }
+    }
+
+    /// Substract other signed value, e.g. to get change between two balances
+    pub fn substract_signed(&mut self, other: Self) -> Result<(), CurrencyError> {
+        if other.negative {
+            self.add(other.magnitude)
+        } else {
+            self.substract_allow_negative(other.magnitude)
+        }
+    }
+
+    /// Read amount optionally preceded by minus sign
+    pub fn parse_with(input: &str, options: &ParseOptions) -> Result<Parsed<Self>, CurrencyError> {
+        let (negative, parsed) = parse(input, options, true)?;
+
+        Ok(Parsed {
+            value: Self::new(negative, parsed.value),
+            rounded: parsed.rounded,
+            code: parsed.code,
+        })
+    }
+
+    pub fn format(&self, format: &Format) -> String {
+        let sign = if self.negative { "-" } else { "" };
+
+        String::from(sign) + &format.format(self.magnitude)
+    }
+
+    fn new(negative: bool, magnitude: Decimal<T, SCALE>) -> Self {
+        Self {
+            negative: negative && magnitude != Decimal::zero(),
+            magnitude,
+        }
+    }
+}
+
+impl<T: Backing, const SCALE: u32> From<Decimal<T, SCALE>> for SignedDecimal<T, SCALE> {
+    fn from(magnitude: Decimal<T, SCALE>) -> Self {
+        Self::new(false, magnitude)
+    }
+}
+
+impl<const SCALE: u32> From<SignedDecimal<u64, SCALE>> for SignedDecimal<u128, SCALE> {
+    fn from(value: SignedDecimal<u64, SCALE>) -> Self {
+        Self::new(value.negative, value.magnitude.into())
+    }
+}
+
+impl<T: Backing, const SCALE: u32> PartialOrd for SignedDecimal<T, SCALE> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Backing, const SCALE: u32> Ord for SignedDecimal<T, SCALE> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, false) => self.magnitude.cmp(&other.magnitude),
+            (true, true) => other.magnitude.cmp(&self.magnitude),
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+        }
+    }
+}
+
+impl<T: Backing, const SCALE: u32> TryFrom<&str> for SignedDecimal<T, SCALE> {
+    type Error = CurrencyError;
+
+    fn try_from(input: &str) -> Result<Self, CurrencyError> {
+        Self::parse_with(input, &ParseOptions::default()).map(|parsed| parsed.value)
+    }
+}
+
+impl<T: Backing, const SCALE: u32> FromStr for SignedDecimal<T, SCALE> {
+    type Err = CurrencyError;
+
+    fn from_str(input: &str) -> Result<Self, CurrencyError> {
+        Self::try_from(input)
+    }
+}
+
+impl<T: Backing, const SCALE: u32> Serialize for SignedDecimal<T, SCALE> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let sign = if self.negative { "-" } else { "" };
+
+        serializer.serialize_str(&(String::from(sign) + &self.magnitude.to_fixed_string()))
+    }
+}
+
+impl<'de, T: Backing, const SCALE: u32> Deserialize<'de> for SignedDecimal<T, SCALE> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_str(DecimalStrVisitor::<Self>(PhantomData))
+    }
+}
+
+impl<T: Backing, const SCALE: u32> fmt::Display for SignedDecimal<T, SCALE> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.negative {
+            write!(f, "-")?;
+        }
+
+        write!(f, "{}", self.magnitude)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use assert_matches::assert_matches;
+
+    #[test]
+    fn zero_is_not_negative() {
+        assert!(!Balance::zero().is_negative());
+    }
+
+    #[test]
+    fn correct_add_to_positive() {
+        let mut balance = Balance::from(Currency::new(1, 0).unwrap());
+        assert!(balance.add(Currency::new(1, 0).unwrap()).is_ok());
+        assert_eq!(balance, Balance::from(Currency::new(2, 0).unwrap()));
+    }
+
+    #[test]
+    fn incorrect_substract_below_zero() {
+        let mut balance = Balance::from(Currency::new(1, 0).unwrap());
+        assert_matches!(
+            balance.substract(Currency::new(2, 0).unwrap()),
+            Err(CurrencyError::SubstractingOtherNegative)
+        );
+    }
+
+    #[test]
+    fn correct_substract_allow_negative_below_zero() {
+        let mut balance = Balance::from(Currency::new(1, 0).unwrap());
+        assert!(balance
+            .substract_allow_negative(Currency::new(3, 0).unwrap())
+            .is_ok());
+        assert!(balance.is_negative());
+        assert_eq!(balance.magnitude(), Currency::new(2, 0).unwrap());
+    }
+
+    #[test]
+    fn correct_add_pays_off_negative_partially() {
+        let mut balance = Balance::zero();
+        balance
+            .substract_allow_negative(Currency::new(3, 0).unwrap())
+            .unwrap();
+        assert!(balance.add(Currency::new(1, 0).unwrap()).is_ok());
+        assert!(balance.is_negative());
+        assert_eq!(balance.magnitude(), Currency::new(2, 0).unwrap());
+    }
+
+    #[test]
+    fn correct_add_pays_off_negative_fully() {
+        let mut balance = Balance::zero();
+        balance
+            .substract_allow_negative(Currency::new(1, 0).unwrap())
+            .unwrap();
+        assert!(balance.add(Currency::new(1, 0).unwrap()).is_ok());
+        assert_eq!(balance, Balance::zero());
+        assert!(balance.add(Currency::new(1, 0).unwrap()).is_ok());
+        assert_eq!(balance, Balance::from(Currency::new(1, 0).unwrap()));
+    }
+
+    #[test]
+    fn incorrect_substract_on_negative() {
+        let mut balance = Balance::zero();
+        balance
+            .substract_allow_negative(Currency::new(1, 0).unwrap())
+            .unwrap();
+        assert_matches!(
+            balance.substract(Currency::new(0, 1).unwrap()),
+            Err(CurrencyError::SubstractingOtherNegative)
+        );
+    }
+
+    #[test]
+    fn incorrect_substract_allow_negative_out_of_range() {
+        let mut balance = Balance::zero();
+        balance.substract_allow_negative(Currency::max()).unwrap();
+        assert_matches!(
+            balance.substract_allow_negative(Currency::new(0, 1).unwrap()),
+            Err(CurrencyError::SubstractingOtherOutOfRange)
+        );
+    }
+
+    #[test]
+    fn display_negative() {
+        let mut balance = Balance::zero();
+        balance
+            .substract_allow_negative(Currency::new(1, 0).unwrap())
+            .unwrap();
+        assert_eq!(balance.to_string(), "-1.0");
+    }
+
+    #[test]
+    fn compare_order() {
+        let negative = "-2.0".parse::<Balance>().unwrap();
+        let less_negative = "-1.0".parse::<Balance>().unwrap();
+        let positive = "1.0".parse::<Balance>().unwrap();
+        assert!(negative < less_negative);
+        assert!(less_negative < Balance::zero());
+        assert!(Balance::zero() < positive);
+    }
+
+    #[test]
+    fn parse_negative_zero_as_zero() {
+        assert_eq!("-0".parse::<Balance>(), Ok(Balance::zero()));
+    }
+
+    #[test]
+    fn add_and_substract_signed() {
+        let mut balance = "1.0".parse::<Balance>().unwrap();
+        balance
+            .substract_signed("-2.5".parse::<Balance>().unwrap())
+            .unwrap();
+        assert_eq!(balance, "3.5".parse::<Balance>().unwrap());
+        balance
+            .substract_signed("4.0".parse::<Balance>().unwrap())
+            .unwrap();
+        assert_eq!(balance, "-0.5".parse::<Balance>().unwrap());
+        balance
+            .add_signed("-1.5".parse::<Balance>().unwrap())
+            .unwrap();
+        assert_eq!(balance, "-2.0".parse::<Balance>().unwrap());
+    }
+}
@@ -1,31 +1,53 @@
 //! Common API related to errors in amount
 
-// Standard modules paths
-use std::num::ParseIntError;
-
 // External paths
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq)]
 pub enum CurrencyError {
-    #[error("cannot find decimal part of amount before . character")]
-    CannotGetDecimalPart,
-    #[error("cannot parse decimal part of amount: {source:?}")]
-    CannotParseDecimalPart { source: ParseIntError },
-    #[error("cannot parse fractional part of amount: {source:?}")]
-    CannotParseFractionalPart { source: ParseIntError },
+    #[error("cannot parse decimal part of amount: unexpected {found:?} at position: {position}")]
+    CannotParseDecimalPart {
+        position: usize,
+        found: Option<char>,
+    },
+    #[error(
+        "cannot parse fractional part of amount: unexpected {found:?} at position: {position}"
+    )]
+    CannotParseFractionalPart {
+        position: usize,
+        found: Option<char>,
+    },
+    #[error("cannot parse exponent of amount: unexpected {found:?} at position: {position}")]
+    CannotParseExponent {
+        position: usize,
+        found: Option<char>,
+    },
+    #[error("cannot parse amount: unexpected {found:?} at position: {position}")]
+    UnexpectedCharacter { position: usize, found: char },
+    #[error("cannot parse exponent of amount: {0} as it is too big")]
+    ExponentOutOfRange(String),
+    #[error("cannot represent amount: {0} is out of supported range")]
+    DecimalOutOfRange(String),
     #[error("cannot parse fractional parst of amount as it is too long: {0}")]
     FractionalTooLong(String),
     #[error("cannot represent amount as value: {0} is out of supported range")]
-    DecimalMultipliedByPrecisionOutOfRange(u64),
+    DecimalMultipliedByPrecisionOutOfRange(String),
     #[error(
         "cannot represent amount as decimal: {0} and fractional: {1} are out of supported range"
     )]
-    DecimalAddedFractionalOutOfRange(u64, u64),
+    DecimalAddedFractionalOutOfRange(String, String),
+    #[error("cannot represent amount: {0} rounded up as it is out of supported range")]
+    RoundingOutOfRange(String),
     #[error("cannot represent amount fractional: {0} is out of supported range")]
-    FractionalOutOfRange(u64),
+    FractionalOutOfRange(String),
+    #[error("cannot read amount: {0} as thousands separator is misplaced")]
+    MisplacedThousandsSeparator(String),
+    #[error("cannot use format as written amounts could not be read back")]
+    AmbiguousFormat,
     #[error("cannot add other value as it would be out of supported range")]
     AddingOtherOutOfRange,
     #[error("cannot substract other value as it would be negative")]
     SubstractingOtherNegative,
+    #[error("cannot substract other value as it would be below supported range")]
+    SubstractingOtherOutOfRange,
 }
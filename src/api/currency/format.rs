@@ -0,0 +1,298 @@
+//! Writing amounts as text
+
+// Standard paths
+use std::convert::TryFrom;
+
+// Crate paths
+use crate::api::currency::backing::Backing;
+use crate::api::currency::error::CurrencyError;
+use crate::api::currency::Decimal;
+
+/// Which fractional digits to write
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Scale {
+    /// All fractional digits, e.g. `0.0500` or `1.0000`
+    Fixed,
+    /// All fractional digits, but whole amounts keep single zero as reports always had, e.g. `1.0`
+    Legacy,
+    /// Drop trailing zeros, but keep at least one digit, e.g. `0.05` or `1.0`
+    Trimmed,
+}
+
+/// How to write amounts as text
+///
+/// Any amount written with default format is read back by `TryFrom<&str>`.
+/// Amount written with any other format is read back by `Format::parse`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Format {
+    pub scale: Scale,
+    /// Separator put between each 3 digits of decimal part, e.g. `1,000.0`
+    pub thousands_separator: Option<char>,
+    /// Character put between decimal and fractional part
+    pub decimal_mark: char,
+}
+
+impl Default for Format {
+    fn default() -> Self {
+        Self {
+            scale: Scale::Legacy,
+            thousands_separator: None,
+            decimal_mark: '.',
+        }
+    }
+}
+
+impl Format {
+    /// Ensure written amounts can be read back without any doubt
+    pub fn validate(&self) -> Result<(), CurrencyError> {
+        let ambiguous = |mark: char| mark.is_ascii_digit() || mark == '-' || mark == '+';
+
+        if ambiguous(self.decimal_mark)
+            || self.thousands_separator.is_some_and(ambiguous)
+            || self.thousands_separator == Some(self.decimal_mark)
+        {
+            return Err(CurrencyError::AmbiguousFormat);
+        }
+
+        Ok(())
+    }
+
+    pub fn format<T: Backing, const SCALE: u32>(&self, amount: Decimal<T, SCALE>) -> String {
+        let base = Decimal::<T, SCALE>::base();
+        let decimal = (amount.0 / base).to_string();
+        let fractional = if SCALE == 0 {
+            String::new()
+        } else {
+            format!(
+                "{:0>width$}",
+                (amount.0 % base).to_string(),
+                width = SCALE as usize
+            )
+        };
+
+        let fractional = match (self.scale, fractional.trim_end_matches('0')) {
+            (Scale::Fixed, _) => fractional.as_str(),
+            (_, "") => &fractional[..fractional.len().min(1)],
+            (Scale::Legacy, _) => fractional.as_str(),
+            (Scale::Trimmed, trimmed) => trimmed,
+        };
+
+        let mut output = match self.thousands_separator {
+            Some(separator) => group_thousands(&decimal, separator),
+            None => decimal,
+        };
+
+        if !fractional.is_empty() {
+            output.push(self.decimal_mark);
+            output.push_str(fractional);
+        }
+
+        output
+    }
+
+    /// Read amount written with this format
+    pub fn parse<T: Backing, const SCALE: u32>(
+        &self,
+        input: &str,
+    ) -> Result<Decimal<T, SCALE>, CurrencyError> {
+        let (decimal, fractional) = match input.find(self.decimal_mark) {
+            Some(position) => (
+                &input[..position],
+                Some(&input[position + self.decimal_mark.len_utf8()..]),
+            ),
+            None => (input, None),
+        };
+
+        let mut normalized = match self.thousands_separator {
+            Some(separator) => ungroup_thousands(decimal, separator)?,
+            None => decimal.to_string(),
+        };
+
+        if let Some(fractional) = fractional {
+            normalized.push('.');
+            normalized.push_str(fractional);
+        }
+
+        Decimal::try_from(normalized.as_str())
+    }
+}
+
+fn group_thousands(digits: &str, separator: char) -> String {
+    let mut output = String::with_capacity(digits.len() + digits.len() / 3);
+
+    for (index, digit) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index).is_multiple_of(3) {
+            output.push(separator);
+        }
+        output.push(digit);
+    }
+
+    output
+}
+
+fn ungroup_thousands(input: &str, separator: char) -> Result<String, CurrencyError> {
+    let mut groups = input.split(separator);
+
+    // First group is never empty and the rest are full
+    let first = groups.next().unwrap_or("");
+    let mut output = first.to_string();
+    for group in groups {
+        if first.is_empty() || first.len() > 3 || group.len() != 3 {
+            return Err(CurrencyError::MisplacedThousandsSeparator(
+                input.to_string(),
+            ));
+        }
+        output.push_str(group);
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::api::currency::Currency;
+    use assert_matches::assert_matches;
+    use proptest::prelude::*;
+
+    const FIXED: Format = Format {
+        scale: Scale::Fixed,
+        thousands_separator: None,
+        decimal_mark: '.',
+    };
+
+    const TRIMMED: Format = Format {
+        scale: Scale::Trimmed,
+        thousands_separator: None,
+        decimal_mark: '.',
+    };
+
+    const LOCALE: Format = Format {
+        scale: Scale::Trimmed,
+        thousands_separator: Some(' '),
+        decimal_mark: ',',
+    };
+
+    #[test]
+    fn trimmed_keeps_leading_fractional_zeros() {
+        let amount = Currency::new(0, 500).unwrap();
+        assert_eq!(TRIMMED.format(amount), "0.05");
+    }
+
+    #[test]
+    fn trimmed_keeps_one_fractional_digit() {
+        let amount = Currency::new(1, 0).unwrap();
+        assert_eq!(TRIMMED.format(amount), "1.0");
+    }
+
+    #[test]
+    fn fixed_writes_all_fractional_digits() {
+        let amount = Currency::new(0, 500).unwrap();
+        assert_eq!(FIXED.format(amount), "0.0500");
+        assert_eq!(FIXED.format(Currency::new(1, 0).unwrap()), "1.0000");
+        assert_eq!(FIXED.format(Currency::zero()), "0.0000");
+    }
+
+    #[test]
+    fn legacy_keeps_one_digit_of_whole_amount() {
+        let amount = Currency::new(0, 500).unwrap();
+        assert_eq!(Format::default().format(amount), "0.0500");
+        assert_eq!(
+            Format::default().format(Currency::new(1, 0).unwrap()),
+            "1.0"
+        );
+        assert_eq!(Format::default().format(Currency::zero()), "0.0");
+    }
+
+    #[test]
+    fn without_scale_writes_only_decimal() {
+        let amount = Decimal::<u64, 0>::new(12, 0).unwrap();
+        assert_eq!(Format::default().format(amount), "12");
+        assert_eq!(FIXED.format(amount), "12");
+        assert_eq!(TRIMMED.format(amount), "12");
+    }
+
+    #[test]
+    fn thousands_separated() {
+        let amount = Currency::new(1234567, 5000).unwrap();
+        assert_eq!(LOCALE.format(amount), "1 234 567,5");
+        assert_eq!(LOCALE.format(Currency::new(123, 0).unwrap()), "123,0");
+    }
+
+    #[test]
+    fn parse_thousands_separated() {
+        assert_eq!(
+            LOCALE.parse::<u64, 4>("1 234 567,5"),
+            Currency::new(1234567, 5000)
+        );
+    }
+
+    #[test]
+    fn cannot_parse_misplaced_thousands_separator() {
+        assert_matches!(
+            LOCALE.parse::<u64, 4>("12 34,5"),
+            Err(CurrencyError::MisplacedThousandsSeparator(..))
+        );
+        assert_matches!(
+            LOCALE.parse::<u64, 4>(" 234,5"),
+            Err(CurrencyError::MisplacedThousandsSeparator(..))
+        );
+    }
+
+    #[test]
+    fn ambiguous_format_is_invalid() {
+        let format = Format {
+            thousands_separator: Some('.'),
+            ..Format::default()
+        };
+        assert_matches!(format.validate(), Err(CurrencyError::AmbiguousFormat));
+        assert!(LOCALE.validate().is_ok());
+    }
+
+    fn any_format() -> impl Strategy<Value = Format> {
+        (
+            prop_oneof![
+                Just(Scale::Fixed),
+                Just(Scale::Legacy),
+                Just(Scale::Trimmed)
+            ],
+            prop_oneof![
+                Just(None),
+                Just(Some(',')),
+                Just(Some(' ')),
+                Just(Some('_'))
+            ],
+            prop_oneof![Just('.'), Just(',')],
+        )
+            .prop_map(|(scale, thousands_separator, decimal_mark)| Format {
+                scale,
+                thousands_separator,
+                decimal_mark,
+            })
+            .prop_filter("ambiguous format", |format| format.validate().is_ok())
+    }
+
+    proptest! {
+        #[test]
+        fn round_trip_default_scales(value: u64) {
+            let amount = Currency::new(value / 10_000, value % 10_000).unwrap();
+            prop_assert_eq!(Currency::try_from(FIXED.format(amount).as_str()), Ok(amount));
+            prop_assert_eq!(Currency::try_from(TRIMMED.format(amount).as_str()), Ok(amount));
+            prop_assert_eq!(Currency::try_from(amount.to_string().as_str()), Ok(amount));
+        }
+
+        #[test]
+        fn round_trip_any_format(value: u64, format in any_format()) {
+            let amount = Currency::new(value / 10_000, value % 10_000).unwrap();
+            prop_assert_eq!(format.parse(&format.format(amount)), Ok(amount));
+        }
+
+        #[test]
+        fn round_trip_wide_backing(value: u128, format in any_format()) {
+            let base = 10_u128.pow(18);
+            let amount = Decimal::<u128, 18>::new(value / base, value % base).unwrap();
+            prop_assert_eq!(format.parse(&format.format(amount)), Ok(amount));
+        }
+    }
+}
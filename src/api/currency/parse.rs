@@ -0,0 +1,741 @@
+//! Reading amounts from text
+
+// Crate paths
+use crate::api::currency::backing::Backing;
+use crate::api::currency::error::CurrencyError;
+use crate::api::currency::rounding::RoundingMode;
+use crate::api::currency::Decimal;
+
+/// Max number of digits exponent may move decimal point by
+const MAX_EXPONENT: usize = 1000;
+
+/// Which extensions of plain `123.45` amount syntax are accepted
+///
+/// Positions in errors are byte offsets from the beginning of input.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Syntax {
+    /// Leading `+`, e.g. `+1.0`
+    pub plus_sign: bool,
+    /// Amount without decimal part, e.g. `.5`
+    pub bare_fraction: bool,
+    /// Underscores between digits, e.g. `1_000.00`
+    pub digit_separators: bool,
+    /// Scientific notation, e.g. `1e3` or `1.5E-2`
+    pub exponent: bool,
+    /// Trailing code of currency, e.g. `10.00 USD`
+    pub currency_code: bool,
+}
+
+impl Syntax {
+    /// Only digits with optional fractional part
+    pub const STRICT: Self = Self {
+        plus_sign: false,
+        bare_fraction: false,
+        digit_separators: false,
+        exponent: false,
+        currency_code: false,
+    };
+
+    /// Any supported extension
+    pub const LENIENT: Self = Self {
+        plus_sign: true,
+        bare_fraction: true,
+        digit_separators: true,
+        exponent: true,
+        currency_code: true,
+    };
+}
+
+impl Default for Syntax {
+    fn default() -> Self {
+        Self::STRICT
+    }
+}
+
+/// How to read amounts from text
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct ParseOptions {
+    /// Rounding of fractional digits beyond scale, `None` rejects such amounts
+    pub rounding: Option<RoundingMode>,
+    pub syntax: Syntax,
+    /// Max number of fractional digits, `None` or any above scale means scale
+    pub precision: Option<u32>,
+}
+
+/// Amount read from text together with notes how it was read
+#[derive(Debug, Clone, PartialEq)]
+pub struct Parsed<V> {
+    pub value: V,
+    /// Input had more fractional digits than scale and they were rounded
+    pub rounded: bool,
+    /// Code of currency given after amount
+    pub code: Option<String>,
+}
+
+impl<T: Backing, const SCALE: u32> Decimal<T, SCALE> {
+    pub fn parse_with(input: &str, options: &ParseOptions) -> Result<Parsed<Self>, CurrencyError> {
+        parse(input, options, false).map(|(_, parsed)| parsed)
+    }
+
+    /// Build value from plain digits of decimal and fractional part
+    fn from_digits(
+        decimal: &str,
+        fractional: &str,
+        options: &ParseOptions,
+    ) -> Result<(Self, bool), CurrencyError> {
+        let rounding = options.rounding;
+        let precision = options
+            .precision
+            .map_or(SCALE, |precision| precision.min(SCALE));
+        let scale = SCALE as usize;
+        let precision = precision as usize;
+
+        // Digits are already validated, so the only failure is out of range
+        let decimal = match decimal.trim_start_matches('0') {
+            "" => T::ZERO,
+            digits => T::parse(digits)
+                .map_err(|_| CurrencyError::DecimalOutOfRange(digits.to_string()))?,
+        };
+
+        // Split digits which fit into scale from excess ones
+        let (fractional, excess) = if fractional.len() > precision {
+            if rounding.is_none() {
+                return Err(CurrencyError::FractionalTooLong(fractional.to_string()));
+            }
+            fractional.split_at(precision)
+        } else {
+            (fractional, "")
+        };
+
+        let fractional = String::from(fractional) + &"0".repeat(scale - fractional.len());
+        let fractional = match fractional.trim_start_matches('0') {
+            "" => T::ZERO,
+            digits => T::parse(digits)
+                .map_err(|_| CurrencyError::DecimalOutOfRange(digits.to_string()))?,
+        };
+
+        let value = Self::new(decimal, fractional)?;
+
+        match rounding {
+            Some(rounding) if !excess.is_empty() => {
+                // Unit of last digit kept within precision
+                let unit = T::pow10((scale - precision) as u32);
+                Self::round(value, unit, excess, rounding).map(|value| (value, true))
+            }
+            _ => Ok((value, false)),
+        }
+    }
+
+    /// Round `value` according to `excess` digits which did not fit into scale
+    fn round(
+        value: Self,
+        unit: T,
+        excess: &str,
+        rounding: RoundingMode,
+    ) -> Result<Self, CurrencyError> {
+        let mut digits = excess.bytes();
+
+        // Whole excess is not needed to round, only relation to half of unit
+        // First excess digit doubled tells if it is below or above half
+        // Any further non zero digit tells it is not exact tie
+        let first = u64::from(digits.next().unwrap_or(b'0') - b'0');
+        let rest = u64::from(digits.any(|digit| digit != b'0'));
+        // Parity of value is the same as parity of its last digit
+        let last = if (value.0 / unit).is_odd() { 1 } else { 0 };
+
+        if !rounding.rounds_up(last, first * 2 + rest, 20) {
+            return Ok(value);
+        }
+
+        value
+            .0
+            .checked_add(unit)
+            .map(Self)
+            .ok_or_else(|| CurrencyError::RoundingOutOfRange(value.0.to_string()))
+    }
+}
+
+/// Read amount, optionally preceded by minus sign if `signed`
+///
+/// Gives whether amount is negative aside of its magnitude.
+pub(crate) fn parse<T: Backing, const SCALE: u32>(
+    input: &str,
+    options: &ParseOptions,
+    signed: bool,
+) -> Result<(bool, Parsed<Decimal<T, SCALE>>), CurrencyError> {
+    let lexed = Lexer::new(input, &options.syntax).lex(signed)?;
+    let (value, rounded) = Decimal::from_digits(&lexed.decimal, &lexed.fractional, options)?;
+
+    Ok((
+        lexed.negative,
+        Parsed {
+            value,
+            rounded,
+            code: lexed.code.map(String::from),
+        },
+    ))
+}
+
+/// Amount split into plain digits with exponent already applied
+struct Lexed<'a> {
+    negative: bool,
+    decimal: String,
+    fractional: String,
+    code: Option<&'a str>,
+}
+
+struct Lexer<'a> {
+    input: &'a str,
+    syntax: &'a Syntax,
+    position: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(input: &'a str, syntax: &'a Syntax) -> Self {
+        Self {
+            input,
+            syntax,
+            position: 0,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.position..].chars().next()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.input[self.position..].chars().nth(offset)
+    }
+
+    fn lex(mut self, signed: bool) -> Result<Lexed<'a>, CurrencyError> {
+        let mut negative = false;
+        match self.peek() {
+            Some('-') if signed => {
+                negative = true;
+                self.position += 1;
+            }
+            Some('+') if self.syntax.plus_sign => self.position += 1,
+            _ => {}
+        }
+
+        let mut decimal = self.digits();
+        let mut fractional = String::new();
+
+        let bare_fraction = self.syntax.bare_fraction && self.peek() == Some('.');
+        if decimal.is_empty() && !bare_fraction {
+            return Err(self.unexpected_in_decimal());
+        }
+
+        let mut in_fractional = false;
+        if self.peek() == Some('.') {
+            self.position += 1;
+            in_fractional = true;
+            fractional = self.digits();
+
+            // Lone dot is not an amount
+            if decimal.is_empty() && fractional.is_empty() {
+                return Err(self.unexpected_in_fractional());
+            }
+        }
+
+        let exponent = self.exponent()?;
+
+        let code = self.code();
+
+        if self.position < self.input.len() {
+            if exponent.is_some() || code.is_some() {
+                return Err(CurrencyError::UnexpectedCharacter {
+                    position: self.position,
+                    found: self.peek().unwrap_or_default(),
+                });
+            }
+            if in_fractional {
+                return Err(self.unexpected_in_fractional());
+            }
+            return Err(self.unexpected_in_decimal());
+        }
+
+        if let Some(exponent) = exponent {
+            shift(&mut decimal, &mut fractional, exponent);
+        }
+
+        Ok(Lexed {
+            negative,
+            decimal,
+            fractional,
+            code,
+        })
+    }
+
+    /// Read digits, skipping separators placed between them
+    fn digits(&mut self) -> String {
+        let mut digits = String::new();
+
+        while let Some(character) = self.peek() {
+            let separator = self.syntax.digit_separators
+                && character == '_'
+                && !digits.is_empty()
+                && self.peek_at(1).is_some_and(|next| next.is_ascii_digit());
+
+            if character.is_ascii_digit() {
+                digits.push(character);
+            } else if !separator {
+                break;
+            }
+            self.position += 1;
+        }
+
+        digits
+    }
+
+    /// Read exponent as signed number of digits to move decimal point by
+    fn exponent(&mut self) -> Result<Option<isize>, CurrencyError> {
+        if !self.syntax.exponent || !matches!(self.peek(), Some('e') | Some('E')) {
+            return Ok(None);
+        }
+
+        // Letter followed neither by sign nor digit could be code of currency
+        let next = self.peek_at(1);
+        let starts_exponent = next.is_some_and(|next| next.is_ascii_digit())
+            || (matches!(next, Some('+') | Some('-'))
+                && self.peek_at(2).is_some_and(|next| next.is_ascii_digit()));
+        if !starts_exponent && self.syntax.currency_code {
+            return Ok(None);
+        }
+
+        self.position += 1;
+        let negative = match self.peek() {
+            Some('-') => {
+                self.position += 1;
+                true
+            }
+            Some('+') => {
+                self.position += 1;
+                false
+            }
+            _ => false,
+        };
+
+        let start = self.position;
+        while self.peek().is_some_and(|next| next.is_ascii_digit()) {
+            self.position += 1;
+        }
+        let digits = &self.input[start..self.position];
+        if digits.is_empty() {
+            return Err(CurrencyError::CannotParseExponent {
+                position: self.position,
+                found: self.peek(),
+            });
+        }
+
+        let exponent = digits
+            .parse::<usize>()
+            .ok()
+            .filter(|exponent| *exponent <= MAX_EXPONENT)
+            .ok_or_else(|| CurrencyError::ExponentOutOfRange(digits.to_string()))?;
+
+        // Max exponent is small, so conversion never fails
+        let exponent = exponent as isize;
+        Ok(Some(if negative { -exponent } else { exponent }))
+    }
+
+    /// Read code of currency, optionally separated from amount by spaces
+    fn code(&mut self) -> Option<&'a str> {
+        if !self.syntax.currency_code {
+            return None;
+        }
+
+        let rest = &self.input[self.position..];
+        let code = rest.trim_start_matches(' ');
+        let valid = (2..=10).contains(&code.len())
+            && code.starts_with(|first: char| first.is_ascii_uppercase())
+            && code
+                .chars()
+                .all(|next| next.is_ascii_uppercase() || next.is_ascii_digit());
+        if !valid {
+            return None;
+        }
+
+        self.position = self.input.len();
+        Some(code)
+    }
+
+    fn unexpected_in_decimal(&self) -> CurrencyError {
+        CurrencyError::CannotParseDecimalPart {
+            position: self.position,
+            found: self.peek(),
+        }
+    }
+
+    fn unexpected_in_fractional(&self) -> CurrencyError {
+        CurrencyError::CannotParseFractionalPart {
+            position: self.position,
+            found: self.peek(),
+        }
+    }
+}
+
+/// Move decimal point by `exponent` digits, right if positive
+fn shift(decimal: &mut String, fractional: &mut String, exponent: isize) {
+    let moved = exponent.unsigned_abs();
+
+    if exponent > 0 {
+        let from_fractional = moved.min(fractional.len());
+        decimal.extend(fractional.drain(..from_fractional));
+        decimal.push_str(&"0".repeat(moved - from_fractional));
+    } else {
+        if decimal.len() < moved {
+            decimal.insert_str(0, &"0".repeat(moved - decimal.len()));
+        }
+        let split = decimal.len() - moved;
+        fractional.insert_str(0, &decimal[split..]);
+        decimal.truncate(split);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::api::currency::Currency;
+    use assert_matches::assert_matches;
+    use std::convert::TryFrom;
+
+    fn parse_rounded(input: &str, rounding: RoundingMode) -> Result<Currency, CurrencyError> {
+        let options = ParseOptions {
+            rounding: Some(rounding),
+            ..ParseOptions::default()
+        };
+        Currency::parse_with(input, &options).map(|parsed| parsed.value)
+    }
+
+    fn parse_lenient(input: &str) -> Result<Parsed<Currency>, CurrencyError> {
+        let options = ParseOptions {
+            syntax: Syntax::LENIENT,
+            ..ParseOptions::default()
+        };
+        Currency::parse_with(input, &options)
+    }
+
+    #[test]
+    fn cannot_parse_too_long_fractional_by_default() {
+        assert_matches!(
+            Currency::parse_with("0.12345", &ParseOptions::default()),
+            Err(CurrencyError::FractionalTooLong(..))
+        );
+    }
+
+    #[test]
+    fn not_rounded_when_fits_into_scale() {
+        let options = ParseOptions {
+            rounding: Some(RoundingMode::HalfUp),
+            ..ParseOptions::default()
+        };
+        assert_eq!(
+            Currency::parse_with("0.1234", &options),
+            Ok(Parsed {
+                value: Currency::new(0, 1234).unwrap(),
+                rounded: false,
+                code: None,
+            })
+        );
+    }
+
+    #[test]
+    fn round_truncate() {
+        assert_eq!(
+            parse_rounded("1.99999", RoundingMode::Truncate),
+            Currency::new(1, 9999)
+        );
+    }
+
+    #[test]
+    fn round_up() {
+        assert_eq!(
+            parse_rounded("1.00001", RoundingMode::Up),
+            Currency::new(1, 1)
+        );
+        assert_eq!(
+            parse_rounded("1.00000", RoundingMode::Up),
+            Currency::new(1, 0)
+        );
+    }
+
+    #[test]
+    fn round_half_up() {
+        assert_eq!(
+            parse_rounded("0.12345", RoundingMode::HalfUp),
+            Currency::new(0, 1235)
+        );
+        assert_eq!(
+            parse_rounded("0.123449", RoundingMode::HalfUp),
+            Currency::new(0, 1234)
+        );
+    }
+
+    #[test]
+    fn round_half_even() {
+        assert_eq!(
+            parse_rounded("0.12345", RoundingMode::HalfEven),
+            Currency::new(0, 1234)
+        );
+        assert_eq!(
+            parse_rounded("0.12355", RoundingMode::HalfEven),
+            Currency::new(0, 1236)
+        );
+        assert_eq!(
+            parse_rounded("0.123450001", RoundingMode::HalfEven),
+            Currency::new(0, 1235)
+        );
+    }
+
+    #[test]
+    fn round_carry_to_decimal() {
+        assert_eq!(
+            parse_rounded("0.99995", RoundingMode::HalfUp),
+            Currency::new(1, 0)
+        );
+    }
+
+    #[test]
+    fn cannot_round_above_max() {
+        assert_matches!(
+            parse_rounded("1844674407370955.16159", RoundingMode::Up),
+            Err(CurrencyError::RoundingOutOfRange(..))
+        );
+    }
+
+    #[test]
+    fn cannot_round_words() {
+        assert_matches!(
+            parse_rounded("0.1234NaN", RoundingMode::HalfUp),
+            Err(CurrencyError::CannotParseFractionalPart {
+                position: 6,
+                found: Some('N')
+            })
+        );
+    }
+
+    #[test]
+    fn ok_to_parse_without_scale() {
+        assert_eq!(
+            Decimal::<u64, 0>::parse_with("12", &ParseOptions::default()).map(|p| p.value),
+            Decimal::<u64, 0>::new(12, 0)
+        );
+    }
+
+    #[test]
+    fn cannot_parse_beyond_precision() {
+        let options = ParseOptions {
+            precision: Some(2),
+            ..ParseOptions::default()
+        };
+        assert_eq!(
+            Currency::parse_with("1.25", &options).map(|p| p.value),
+            Currency::new(1, 2500)
+        );
+        assert_matches!(
+            Currency::parse_with("1.255", &options),
+            Err(CurrencyError::FractionalTooLong(..))
+        );
+    }
+
+    #[test]
+    fn round_to_precision() {
+        let options = ParseOptions {
+            rounding: Some(RoundingMode::HalfEven),
+            precision: Some(2),
+            ..ParseOptions::default()
+        };
+        assert_eq!(
+            Currency::parse_with("1.125", &options).map(|p| p.value),
+            Currency::new(1, 1200)
+        );
+        assert_eq!(
+            Currency::parse_with("1.12501", &options).map(|p| p.value),
+            Currency::new(1, 1300)
+        );
+        let options = ParseOptions {
+            precision: Some(0),
+            ..options
+        };
+        assert_eq!(
+            Currency::parse_with("2.5", &options).map(|p| p.value),
+            Currency::new(2, 0)
+        );
+    }
+
+    #[test]
+    fn strict_rejects_extensions_with_position() {
+        assert_matches!(
+            Currency::try_from("+1.0"),
+            Err(CurrencyError::CannotParseDecimalPart {
+                position: 0,
+                found: Some('+')
+            })
+        );
+        assert_matches!(
+            Currency::try_from(".5"),
+            Err(CurrencyError::CannotParseDecimalPart {
+                position: 0,
+                found: Some('.')
+            })
+        );
+        assert_matches!(
+            Currency::try_from("1_000"),
+            Err(CurrencyError::CannotParseDecimalPart {
+                position: 1,
+                found: Some('_')
+            })
+        );
+        assert_matches!(
+            Currency::try_from("1e3"),
+            Err(CurrencyError::CannotParseDecimalPart {
+                position: 1,
+                found: Some('e')
+            })
+        );
+        assert_matches!(
+            Currency::try_from("1.0 USD"),
+            Err(CurrencyError::CannotParseFractionalPart {
+                position: 3,
+                found: Some(' ')
+            })
+        );
+    }
+
+    #[test]
+    fn strict_rejects_second_dot() {
+        assert_matches!(
+            Currency::try_from("1.2.3"),
+            Err(CurrencyError::CannotParseFractionalPart {
+                position: 3,
+                found: Some('.')
+            })
+        );
+    }
+
+    #[test]
+    fn strict_accepts_trailing_dot() {
+        assert_eq!(Currency::try_from("1."), Currency::new(1, 0));
+    }
+
+    #[test]
+    fn lenient_accepts_plus_sign() {
+        assert_eq!(parse_lenient("+1.0").map(|p| p.value), Currency::new(1, 0));
+    }
+
+    #[test]
+    fn lenient_accepts_bare_fraction() {
+        assert_eq!(parse_lenient(".5").map(|p| p.value), Currency::new(0, 5000));
+        assert_matches!(
+            parse_lenient("."),
+            Err(CurrencyError::CannotParseFractionalPart {
+                position: 1,
+                found: None
+            })
+        );
+    }
+
+    #[test]
+    fn lenient_accepts_digit_separators() {
+        assert_eq!(
+            parse_lenient("1_000.000_1").map(|p| p.value),
+            Currency::new(1000, 1)
+        );
+        assert_matches!(
+            parse_lenient("1__000"),
+            Err(CurrencyError::CannotParseDecimalPart {
+                position: 1,
+                found: Some('_')
+            })
+        );
+        assert_matches!(
+            parse_lenient("1000_"),
+            Err(CurrencyError::CannotParseDecimalPart {
+                position: 4,
+                found: Some('_')
+            })
+        );
+    }
+
+    #[test]
+    fn lenient_accepts_exponent() {
+        assert_eq!(
+            parse_lenient("1e3").map(|p| p.value),
+            Currency::new(1000, 0)
+        );
+        assert_eq!(
+            parse_lenient("1.5E-2").map(|p| p.value),
+            Currency::new(0, 150)
+        );
+        assert_eq!(
+            parse_lenient("12.34e+1").map(|p| p.value),
+            Currency::new(123, 4000)
+        );
+        assert_matches!(
+            parse_lenient("1e+"),
+            Err(CurrencyError::CannotParseDecimalPart {
+                position: 1,
+                found: Some('e')
+            })
+        );
+    }
+
+    #[test]
+    fn cannot_parse_huge_exponent() {
+        assert_matches!(
+            parse_lenient("1e99999"),
+            Err(CurrencyError::ExponentOutOfRange(..))
+        );
+        assert_matches!(
+            parse_lenient("1e100"),
+            Err(CurrencyError::DecimalOutOfRange(..))
+        );
+    }
+
+    #[test]
+    fn lenient_accepts_currency_code() {
+        assert_eq!(
+            parse_lenient("10.00 USD"),
+            Ok(Parsed {
+                value: Currency::new(10, 0).unwrap(),
+                rounded: false,
+                code: Some(String::from("USD")),
+            })
+        );
+        assert_eq!(
+            parse_lenient("1EUR").map(|p| p.code),
+            Ok(Some(String::from("EUR")))
+        );
+        assert_matches!(
+            parse_lenient("10.00 usd"),
+            Err(CurrencyError::CannotParseFractionalPart {
+                position: 5,
+                found: Some(' ')
+            })
+        );
+    }
+
+    #[test]
+    fn cannot_parse_after_code() {
+        assert_matches!(
+            parse_lenient("1e2x"),
+            Err(CurrencyError::UnexpectedCharacter {
+                position: 3,
+                found: 'x'
+            })
+        );
+    }
+
+    #[test]
+    fn cannot_parse_negative() {
+        assert_matches!(
+            parse_lenient("-1"),
+            Err(CurrencyError::CannotParseDecimalPart {
+                position: 0,
+                found: Some('-')
+            })
+        );
+    }
+}
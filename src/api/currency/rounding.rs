@@ -0,0 +1,70 @@
+//! Rounding of amounts which do not fit into precision
+
+// Crate paths
+use crate::api::currency::backing::Backing;
+
+/// How to treat digits which cannot be represented
+///
+/// Amounts are never negative, so rounding down is the same as truncating.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum RoundingMode {
+    /// Drop excess digits
+    Truncate,
+    /// Round away from zero when any non zero digit is dropped
+    Up,
+    /// Round to nearest, ties away from zero
+    HalfUp,
+    /// Round to nearest, ties to even, also known as banker's rounding
+    HalfEven,
+}
+
+impl RoundingMode {
+    /// Tell if already truncated `quotient` has to be increased by one
+    ///
+    /// `remainder` is what is left after division by `divisor`.
+    pub fn rounds_up<T: Backing>(self, quotient: T, remainder: T, divisor: T) -> bool {
+        if remainder == T::ZERO {
+            return false;
+        }
+
+        // Compare remainder with half of divisor without overflow
+        let rest = divisor.checked_sub(remainder).unwrap_or(T::ZERO);
+
+        match self {
+            Self::Truncate => false,
+            Self::Up => true,
+            Self::HalfUp => remainder >= rest,
+            Self::HalfEven => remainder > rest || (remainder == rest && quotient.is_odd()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn truncate_never_rounds_up() {
+        assert!(!RoundingMode::Truncate.rounds_up(1_u64, 9, 10));
+    }
+
+    #[test]
+    fn up_rounds_any_remainder() {
+        assert!(RoundingMode::Up.rounds_up(1_u64, 1, 10));
+        assert!(!RoundingMode::Up.rounds_up(1_u64, 0, 10));
+    }
+
+    #[test]
+    fn half_up_rounds_tie_up() {
+        assert!(RoundingMode::HalfUp.rounds_up(2_u64, 5, 10));
+        assert!(!RoundingMode::HalfUp.rounds_up(2_u64, 4, 10));
+    }
+
+    #[test]
+    fn half_even_rounds_tie_to_even() {
+        assert!(!RoundingMode::HalfEven.rounds_up(2_u64, 5, 10));
+        assert!(RoundingMode::HalfEven.rounds_up(3_u64, 5, 10));
+        assert!(RoundingMode::HalfEven.rounds_up(2_u64, 6, 10));
+    }
+}
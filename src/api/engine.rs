@@ -1,36 +1,139 @@
 // Standard paths
-use std::collections::hash_map::Entry;
+use std::collections::hash_map;
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
 use std::sync::Mutex;
+use std::sync::MutexGuard;
 use std::sync::RwLock;
+use std::sync::RwLockReadGuard;
 
 // Crate paths
+use self::account::Access;
 use self::account::Account;
+use self::account::AccountId;
+use self::account::Status;
+use self::admin::AdminAction;
+use self::admin::AdminRecord;
 use self::error::EngineError;
+use self::history::Entry;
+use self::history::Event;
+use self::history::History;
+use self::operation::Operation;
+use self::options::EngineOptions;
+use self::reconcile::Flow;
+use self::reconcile::Flows;
+use self::snapshot::Before;
+use self::snapshot::Change;
+use self::snapshot::Versions;
+use crate::api::asset::Asset;
+use crate::api::currency::balance::Balance;
+use crate::api::currency::rounding::RoundingMode;
 use crate::api::currency::Currency;
 
+// External paths
+use serde::Serialize;
+
 // Crate modules
 pub mod account;
+pub mod admin;
 pub mod error;
+pub mod fees;
+pub mod history;
+pub mod limits;
+pub mod operation;
+pub mod options;
+pub mod query;
+pub mod rates;
+pub mod reconcile;
+pub mod snapshot;
+
+/// Part of transaction which changes single account
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Leg {
+    pub account: AccountId,
+    pub amount: Currency,
+}
+
+/// Transaction kept to let dispute it later
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Recorded {
+    /// Deposit or withdrawal, account is given by client of dispute
+    Single {
+        asset: Asset,
+        amount: Currency,
+        /// Fee given to house account
+        fee: Option<Leg>,
+    },
+    /// Funds taken from one account and given to another, e.g. exchange or transfer
+    Movement { from: Leg, to: Leg },
+    /// Administrative operation, it only takes tx id
+    Admin(AdminRecord),
+}
 
 pub struct Engine {
-    accounts: RwLock<HashMap<u16, Mutex<Account>>>,
+    options: EngineOptions,
+    accounts: RwLock<HashMap<AccountId, Mutex<Account>>>,
     // Should it track client id also and verify later that disputed transactions are valid?
-    transactions: RwLock<HashMap<u32, Currency>>,
+    transactions: RwLock<HashMap<u32, Recorded>>,
     transactions_disputed: RwLock<HashSet<u32>>,
+    /// Kept only if enabled in options
+    history: Option<History>,
+    /// Shared by operations, taken exclusively to reconcile between operations
+    snapshot_gate: RwLock<()>,
+    /// Number of finished operations, rejected ones are counted too
+    version: AtomicU64,
+    /// Numbers changes of accounts, so snapshot can skip ones applied while it copies
+    versions: Versions,
+    /// Funds which entered or left each asset, used to reconcile
+    flows: Mutex<HashMap<Asset, Flows>>,
+}
+
+/// Operation in progress, reconcile waits for it to finish
+struct OperationGuard<'a> {
+    version: &'a AtomicU64,
+    _gate: RwLockReadGuard<'a, ()>,
+}
+
+impl Drop for OperationGuard<'_> {
+    fn drop(&mut self) {
+        // Counted while gate is still held, so reconcile sees version matching accounts
+        self.version.fetch_add(1, Ordering::Relaxed);
+    }
 }
 
 impl Engine {
     pub fn new() -> Self {
+        Self::with_options(EngineOptions::default())
+    }
+
+    pub fn with_options(options: EngineOptions) -> Self {
+        let history = options.keep_history.then(History::default);
         Engine {
+            options,
+            history,
+            snapshot_gate: RwLock::new(()),
+            version: AtomicU64::new(0),
+            versions: Versions::default(),
+            flows: Mutex::new(HashMap::new()),
             accounts: RwLock::new(HashMap::new()),
             transactions: RwLock::new(HashMap::new()),
             transactions_disputed: RwLock::new(HashSet::new()),
         }
     }
 
-    fn record_transaction(&mut self, tx: u32, amount: Currency) -> Result<(), EngineError> {
+    /// Let reconcile wait until operation finishes, operations do not wait for each other
+    fn begin_operation(&self) -> OperationGuard<'_> {
+        OperationGuard {
+            version: &self.version,
+            // Panic if lock is poisoned
+            _gate: self.snapshot_gate.read().unwrap(),
+        }
+    }
+
+    fn record_transaction(&self, tx: u32, recorded: Recorded) -> Result<(), EngineError> {
         // Limit lock time
         {
             // Panic if lock is poisoned
@@ -45,16 +148,84 @@ impl Engine {
             // If further deposit fails, then transaction is going to be be stored anyway
             // Then repating same transaction with same tx id will fail
             // Always should be used another unique tx id with each transaction
-            if transactions_lock_write.insert(tx, amount).is_some() {
-                return Err(EngineError::TransactionNotUnique(tx));
+
+            // Keep the first one, e.g. admin operation stays undisputable
+            match transactions_lock_write.entry(tx) {
+                hash_map::Entry::Occupied(_) => return Err(EngineError::TransactionNotUnique(tx)),
+                hash_map::Entry::Vacant(entry) => {
+                    entry.insert(recorded);
+                }
             }
         }
 
         Ok(())
     }
 
-    pub fn deposit(&mut self, client: u16, tx: u32, amount: Currency) -> Result<(), EngineError> {
-        self.record_transaction(tx, amount)?;
+    /// Deposit into account of default asset
+    pub fn deposit(&self, client: u16, tx: u32, amount: Currency) -> Result<(), EngineError> {
+        self.deposit_asset(client, &Asset::default(), tx, amount)
+    }
+
+    pub fn deposit_asset(
+        &self,
+        client: u16,
+        asset: &Asset,
+        tx: u32,
+        amount: Currency,
+    ) -> Result<(), EngineError> {
+        let _operation = self.begin_operation();
+        self.apply_deposit(client, asset, tx, amount)?;
+        self.add_flow(asset, Flow::Deposited, amount);
+
+        Ok(())
+    }
+
+    fn apply_deposit(
+        &self,
+        client: u16,
+        asset: &Asset,
+        tx: u32,
+        amount: Currency,
+    ) -> Result<(), EngineError> {
+        let fee = self.fee(Operation::Deposit, client, asset, tx, amount)?;
+        if let Some(fee) = &fee {
+            if fee.amount > amount {
+                return Err(EngineError::FeeExceedsAmount {
+                    tx,
+                    amount,
+                    fee: fee.amount,
+                });
+            }
+        }
+
+        self.record_transaction(
+            tx,
+            Recorded::Single {
+                asset: asset.clone(),
+                amount,
+                fee: fee.clone(),
+            },
+        )?;
+        let id = AccountId::new(client, asset);
+
+        if let Some(fee) = fee {
+            // Client gets amount without fee
+            // Checked above that fee is not bigger than amount
+            let net = amount
+                .checked_sub(fee.amount)
+                .unwrap_or_else(Currency::zero);
+            let events = (Event::Deposit, Event::Fee);
+            self.update_pair(&id, &fee.account, tx, events, |account, house| {
+                // Create temporarly value to not update client account if fee cannot be given
+                let mut updated = account.clone();
+                credit(&mut updated, client, tx, net)?;
+                credit(house, fee.account.client, tx, fee.amount)?;
+                *account = updated;
+                Ok(())
+            })?;
+            self.add_flow(asset, Flow::FeeCharged, fee.amount);
+            return Ok(());
+        }
 
         // Try to deposit assuming that account already exist
 
@@ -63,15 +234,15 @@ impl Engine {
             // Panic if lock is poisoned
             let accounts_lock_read = self.accounts.read().unwrap();
 
-            if let Some(mutex) = accounts_lock_read.get(&client) {
+            if let Some(mutex) = accounts_lock_read.get(&id) {
                 let mut account = mutex
                     .lock()
                     // Panic if mutex is poisoned
                     .unwrap();
 
-                if account.locked {
-                    return Err(EngineError::AccountLocked(client));
-                }
+                account.ensure_allows(Access::Receive, client)?;
+                let change = self.begin_change();
+                let before = self.before(&change, &account);
 
                 // Create temporarly value to not update target account if any error
                 let mut available = account.available;
@@ -103,6 +274,7 @@ impl Engine {
 
                 // Update target account as all fine
                 account.available = available;
+                self.record_change(&change, &id, tx, Event::Deposit, before, &account);
 
                 return Ok(());
             }
@@ -129,15 +301,18 @@ impl Engine {
             // Panic if lock is poisoned
             let mut accounts_lock_write = self.accounts.write().unwrap();
 
-            match accounts_lock_write.entry(client) {
-                Entry::Occupied(_) => {
+            match accounts_lock_write.entry(id) {
+                hash_map::Entry::Occupied(_) => {
                     // Between getting read of read lock and before getting write lock
                     // Another thread may be lucky enough to deposit to same account
                     // Then don't overwrite already existing account
                     // Instead try deposit again
                     return Err(EngineError::DepositTryAgain(tx));
                 }
-                Entry::Vacant(entry) => {
+                hash_map::Entry::Vacant(entry) => {
+                    let change = self.begin_change();
+                    let (id, event) = (entry.key(), Event::Deposit);
+                    self.record_change(&change, id, tx, event, Before::Created, &account);
                     entry.insert(Mutex::new(account));
                 }
             };
@@ -146,29 +321,80 @@ impl Engine {
         Ok(())
     }
 
-    pub fn withdrawal(
-        &mut self,
+    /// Withdrawal from account of default asset
+    pub fn withdrawal(&self, client: u16, tx: u32, amount: Currency) -> Result<(), EngineError> {
+        self.withdrawal_asset(client, &Asset::default(), tx, amount)
+    }
+
+    pub fn withdrawal_asset(
+        &self,
         client: u16,
+        asset: &Asset,
         tx: u32,
         amount: Currency,
     ) -> Result<(), EngineError> {
-        self.record_transaction(tx, amount)?;
+        let _operation = self.begin_operation();
+        self.apply_withdrawal(client, asset, tx, amount)?;
+        // Fee is not counted as it stays on house account
+        self.add_flow(asset, Flow::Withdrawn, amount);
+
+        Ok(())
+    }
+
+    fn apply_withdrawal(
+        &self,
+        client: u16,
+        asset: &Asset,
+        tx: u32,
+        amount: Currency,
+    ) -> Result<(), EngineError> {
+        let fee = self.fee(Operation::Withdrawal, client, asset, tx, amount)?;
+
+        self.record_transaction(
+            tx,
+            Recorded::Single {
+                asset: asset.clone(),
+                amount,
+                fee: fee.clone(),
+            },
+        )?;
+        let id = AccountId::new(client, asset);
+
+        if let Some(fee) = fee {
+            self.ensure_account_exists(&id)?;
+
+            // Client pays amount together with fee
+            let gross = amount
+                .checked_add(fee.amount)
+                .ok_or(EngineError::FeeOutOfRange { tx, amount })?;
+            let events = (Event::Withdrawal, Event::Fee);
+            self.update_pair(&id, &fee.account, tx, events, |account, house| {
+                // Create temporarly value to not update client account if fee cannot be given
+                let mut updated = account.clone();
+                debit(&mut updated, client, tx, gross)?;
+                credit(house, fee.account.client, tx, fee.amount)?;
+                *account = updated;
+                Ok(())
+            })?;
+            self.add_flow(asset, Flow::FeeCharged, fee.amount);
+            return Ok(());
+        }
 
         // Section with accounts locks
         {
             // Panic if lock is poisoned
             let accounts_lock_read = self.accounts.read().unwrap();
 
-            match accounts_lock_read.get(&client) {
+            match accounts_lock_read.get(&id) {
                 Some(mutex) => {
                     let mut account = mutex
                         .lock()
                         // Panic if mutex is poisoned
                         .unwrap();
 
-                    if account.locked {
-                        return Err(EngineError::AccountLocked(client));
-                    }
+                    account.ensure_allows(Access::Send, client)?;
+                    let change = self.begin_change();
+                    let before = self.before(&change, &account);
 
                     account.available.substract(amount).map_err(|source| {
                         EngineError::CannotWithdrawal {
@@ -177,7 +403,10 @@ impl Engine {
                             amount,
                             source,
                         }
-                    })
+                    })?;
+                    self.record_change(&change, &id, tx, Event::Withdrawal, before, &account);
+
+                    Ok(())
                 }
                 None => Err(EngineError::AccountDoesNotExist(client)),
             }?;
@@ -186,152 +415,844 @@ impl Engine {
         Ok(())
     }
 
-    fn get_transaction_amount(&self, tx: u32) -> Result<Currency, EngineError> {
-        let amount;
+    fn find_transaction(&self, tx: u32) -> Result<Recorded, EngineError> {
+        let recorded;
         // Limit lock time
         {
             // Panic if lock is poisoned
             let transactions_lock_read = self.transactions.read().unwrap();
 
-            let amount_ref = transactions_lock_read
+            recorded = transactions_lock_read
                 .get(&tx)
-                .ok_or(EngineError::CannotFindTransaction(tx))?;
+                .ok_or(EngineError::CannotFindTransaction(tx))?
+                .clone();
+        }
+
+        Ok(recorded)
+    }
+
+    /// Exchange funds of client from one asset to another using rate from options
+    ///
+    /// Both legs are applied or none of them.
+    pub fn exchange(
+        &self,
+        client: u16,
+        tx: u32,
+        from: &Asset,
+        to: &Asset,
+        amount: Currency,
+    ) -> Result<(), EngineError> {
+        let _operation = self.begin_operation();
+        if from == to {
+            return Err(EngineError::ExchangeSameAsset(tx));
+        }
+
+        let rate = self
+            .options
+            .rates
+            .get(from, to)
+            .ok_or_else(|| EngineError::CannotFindRate {
+                from: from.clone(),
+                to: to.clone(),
+            })?;
+
+        // Exchanged amount is rounded to nearest, ties to even to not favour any side
+        let converted = amount
+            .checked_mul_decimal(rate, RoundingMode::HalfEven)
+            .ok_or(EngineError::ExchangeOutOfRange { tx, amount })?;
+        // Client would give funds for nothing
+        if converted == Currency::zero() {
+            return Err(EngineError::ExchangeToZero { tx, amount });
+        }
+
+        let from = Leg {
+            account: AccountId::new(client, from),
+            amount,
+        };
+        let to = Leg {
+            account: AccountId::new(client, to),
+            amount: converted,
+        };
+
+        self.record_transaction(
+            tx,
+            Recorded::Movement {
+                from: from.clone(),
+                to: to.clone(),
+            },
+        )?;
+
+        self.move_funds(tx, Event::Exchange, &from, &to)?;
+        self.add_flow(&from.account.asset, Flow::ExchangedOut, from.amount);
+        self.add_flow(&to.account.asset, Flow::ExchangedIn, to.amount);
+
+        Ok(())
+    }
+
+    /// Move funds of given asset from account of `client` into account of `to_client`
+    ///
+    /// Both legs are applied or none of them.
+    pub fn transfer(
+        &self,
+        client: u16,
+        to_client: u16,
+        asset: &Asset,
+        tx: u32,
+        amount: Currency,
+    ) -> Result<(), EngineError> {
+        let _operation = self.begin_operation();
+        if client == to_client {
+            return Err(EngineError::TransferToItself(tx));
+        }
+
+        let from = Leg {
+            account: AccountId::new(client, asset),
+            amount,
+        };
+        let to = Leg {
+            account: AccountId::new(to_client, asset),
+            amount,
+        };
+
+        self.record_transaction(
+            tx,
+            Recorded::Movement {
+                from: from.clone(),
+                to: to.clone(),
+            },
+        )?;
+
+        self.move_funds(tx, Event::Transfer, &from, &to)
+    }
 
-            amount = *amount_ref;
+    /// Take funds of `from` leg and give funds of `to` leg in one step
+    fn move_funds(&self, tx: u32, event: Event, from: &Leg, to: &Leg) -> Result<(), EngineError> {
+        self.ensure_account_exists(&from.account)?;
+
+        let events = (event, event);
+        self.update_pair(&from.account, &to.account, tx, events, |source, target| {
+            apply_movement(source, target, tx, from, to)
+        })
+    }
+
+    fn ensure_account_exists(&self, id: &AccountId) -> Result<(), EngineError> {
+        // Panic if lock is poisoned
+        let accounts_lock_read = self.accounts.read().unwrap();
+
+        if !accounts_lock_read.contains_key(id) {
+            return Err(EngineError::AccountDoesNotExist(id.client));
         }
 
-        Ok(amount)
+        Ok(())
     }
 
-    pub fn dispute(&mut self, client: u16, tx: u32) -> Result<(), EngineError> {
+    /// Update existing account, it is not changed if `update` fails
+    fn update_account<F>(
+        &self,
+        id: &AccountId,
+        tx: u32,
+        event: Event,
+        update: F,
+    ) -> Result<(), EngineError>
+    where
+        F: FnOnce(&mut Account) -> Result<(), EngineError>,
+    {
+        // Panic if lock is poisoned
+        let accounts_lock_read = self.accounts.read().unwrap();
+        let mutex = accounts_lock_read
+            .get(id)
+            .ok_or(EngineError::CannotFindAccount(id.client))?;
+
+        // Panic if lock is poisoned
+        let mut account = mutex.lock().unwrap();
+
+        // Create temporarly value to not update target account if any error
+        let mut updated = account.clone();
+        update(&mut updated)?;
+        let change = self.begin_change();
+        let before = self.before(&change, &account);
+        self.record_change(&change, id, tx, event, before, &updated);
+        *account = updated;
+
+        Ok(())
+    }
+
+    /// Update two different accounts at once, missing ones are created
+    ///
+    /// Newly created accounts are kept only if `update` succeeds. It has to
+    /// leave accounts untouched if it fails.
+    fn update_pair<F>(
+        &self,
+        first: &AccountId,
+        second: &AccountId,
+        tx: u32,
+        events: (Event, Event),
+        update: F,
+    ) -> Result<(), EngineError>
+    where
+        F: FnOnce(&mut Account, &mut Account) -> Result<(), EngineError>,
+    {
+        // Try to update assuming that both accounts already exist
+
         // Limit lock time
         {
             // Panic if lock is poisoned
-            let transactions_disputed_lock_read = self.transactions_disputed.read().unwrap();
+            let accounts_lock_read = self.accounts.read().unwrap();
 
-            if transactions_disputed_lock_read.contains(&tx) {
-                return Err(EngineError::DisputeAlreadyDisputed(tx));
+            let locked = lock_pair(&accounts_lock_read, first, second);
+            if let Some((mut first_account, mut second_account)) = locked {
+                let change = self.begin_change();
+                let first_before = self.before(&change, &first_account);
+                let second_before = self.before(&change, &second_account);
+                update(&mut first_account, &mut second_account)?;
+                self.record_change(&change, first, tx, events.0, first_before, &first_account);
+                self.record_change(
+                    &change,
+                    second,
+                    tx,
+                    events.1,
+                    second_before,
+                    &second_account,
+                );
+                return Ok(());
             }
         }
 
-        let amount = self.get_transaction_amount(tx)?;
+        // If it comes here, then some account does not exist yet
+        // Inserting new account into accounts requires big lock for write
 
         // Limit lock time
         {
             // Panic if lock is poisoned
-            let accounts_lock_read = self.accounts.read().unwrap();
-            let mutex = accounts_lock_read
-                .get(&client)
-                .ok_or(EngineError::CannotFindAccount(client))?;
+            let mut accounts_lock_write = self.accounts.write().unwrap();
 
-            // Panic if lock is poisoned
-            let mut account = mutex.lock().unwrap();
+            // Nobody else can reach accounts while write lock is held
+            // Panic if mutex is poisoned
+            let mut take = |id| {
+                accounts_lock_write
+                    .remove(id)
+                    .map(|mutex: Mutex<Account>| mutex.into_inner().unwrap())
+            };
+            let first_existing = take(first);
+            let second_existing = take(second);
+
+            let first_created = first_existing.is_none();
+            let second_created = second_existing.is_none();
+            let mut first_account = first_existing.unwrap_or_default();
+            let mut second_account = second_existing.unwrap_or_default();
+            let change = self.begin_change();
+            let before = |created, account| {
+                if created {
+                    Before::Created
+                } else {
+                    self.before(&change, account)
+                }
+            };
+            let first_before = before(first_created, &first_account);
+            let second_before = before(second_created, &second_account);
+
+            let result = update(&mut first_account, &mut second_account);
+            if result.is_ok() {
+                self.record_change(&change, first, tx, events.0, first_before, &first_account);
+                self.record_change(
+                    &change,
+                    second,
+                    tx,
+                    events.1,
+                    second_before,
+                    &second_account,
+                );
+            }
 
-            account
-                .available
-                .substract(amount)
-                .map_err(|source| EngineError::DisputeCannotSubstractAvailable { source })?;
-            account
-                .held
-                .add(amount)
-                .map_err(|source| EngineError::DisputeCannotAddHeld { source })?;
+            if result.is_ok() || !first_created {
+                accounts_lock_write.insert(first.clone(), Mutex::new(first_account));
+            }
+            if result.is_ok() || !second_created {
+                accounts_lock_write.insert(second.clone(), Mutex::new(second_account));
+            }
+
+            result
         }
+    }
 
-        // Limit lock time
-        {
-            // Panic if lock is poisoned
-            let mut transactions_disputed_lock_write = self.transactions_disputed.write().unwrap();
-            transactions_disputed_lock_write.insert(tx);
+    /// Fee of operation as leg to house account, `None` if nothing to pay
+    fn fee(
+        &self,
+        operation: Operation,
+        client: u16,
+        asset: &Asset,
+        tx: u32,
+        amount: Currency,
+    ) -> Result<Option<Leg>, EngineError> {
+        let schedule = match &self.options.fees {
+            Some(schedule) => schedule,
+            None => return Ok(None),
+        };
+
+        let fee = schedule
+            .fee(operation, client, amount)
+            .ok_or(EngineError::FeeOutOfRange { tx, amount })?;
+        if fee == Currency::zero() {
+            return Ok(None);
         }
 
-        Ok(())
+        Ok(Some(Leg {
+            account: AccountId::new(schedule.house, asset),
+            amount: fee,
+        }))
     }
 
-    fn transaction_remove_from_disputed_list(&mut self, tx: u32) {
-        // Limit lock time
-        {
-            // Panic if lock is poisoned
-            let mut transactions_disputed_lock_write = self.transactions_disputed.write().unwrap();
-            transactions_disputed_lock_write.remove(&tx);
+    /// Account and amount which dispute of transaction applies to
+    ///
+    /// Gives also fee of transaction which is refunded for time of dispute.
+    fn disputed_leg(
+        &self,
+        client: u16,
+        tx: u32,
+        recorded: Recorded,
+    ) -> Result<(Leg, Option<Leg>), EngineError> {
+        match recorded {
+            Recorded::Single { asset, amount, fee } => Ok((
+                Leg {
+                    account: AccountId::new(client, &asset),
+                    amount,
+                },
+                fee,
+            )),
+            // Funds given to receiving account are the ones held
+            Recorded::Movement { from, to } => {
+                if from.account.client != client && to.account.client != client {
+                    return Err(EngineError::NotPartyOfTransaction { client, tx });
+                }
+                Ok((to, None))
+            }
+            Recorded::Admin(_) => Err(EngineError::TransactionNotDisputable(tx)),
         }
     }
 
-    fn ensure_transaction_is_disputed(&self, tx: u32) -> Result<(), EngineError> {
-        // Limit lock time
-        {
-            // Panic if lock is poisoned
-            let transactions_disputed_lock_read = self.transactions_disputed.read().unwrap();
+    pub fn dispute(&self, client: u16, tx: u32) -> Result<(), EngineError> {
+        let _operation = self.begin_operation();
+        // Mark it first, so only one of parallel disputes of the same transaction holds funds
+        if !self.transaction_add_to_disputed_list(tx) {
+            return Err(EngineError::DisputeAlreadyDisputed(tx));
+        }
 
-            if !transactions_disputed_lock_read.contains(&tx) {
-                return Err(EngineError::TransactionNotDisputed(tx));
-            }
+        let result = self.hold_disputed(client, tx);
+        if result.is_err() {
+            self.transaction_remove_from_disputed_list(tx);
+        }
+
+        result
+    }
+
+    fn hold_disputed(&self, client: u16, tx: u32) -> Result<(), EngineError> {
+        let recorded = self.find_transaction(tx)?;
+        let (
+            Leg {
+                account: id,
+                amount,
+            },
+            fee,
+        ) = self.disputed_leg(client, tx, recorded)?;
+
+        let allow_negative = self.options.allow_negative_available;
+        let events = (Event::Dispute, Event::Fee);
+        match fee {
+            Some(fee) => self
+                .update_pair(&id, &fee.account, tx, events, |account, house| {
+                    // Create temporarly values to not update any account if any error
+                    let mut updated = account.clone();
+                    let mut updated_house = house.clone();
+                    refund_fee(&mut updated, &mut updated_house, fee.amount)?;
+                    hold(&mut updated, id.client, amount, allow_negative)?;
+                    *account = updated;
+                    *house = updated_house;
+                    Ok(())
+                })
+                .map(|_| self.add_flow(&id.asset, Flow::FeeRefunded, fee.amount))?,
+            None => self.update_account(&id, tx, Event::Dispute, |account| {
+                hold(account, id.client, amount, allow_negative)
+            })?,
         }
 
         Ok(())
     }
 
-    pub fn resolve(&mut self, client: u16, tx: u32) -> Result<(), EngineError> {
-        let amount = self.get_transaction_amount(tx)?;
+    /// False if transaction is disputed already
+    fn transaction_add_to_disputed_list(&self, tx: u32) -> bool {
+        // Panic if lock is poisoned
+        self.transactions_disputed.write().unwrap().insert(tx)
+    }
 
-        self.ensure_transaction_is_disputed(tx)?;
+    /// False if transaction was not disputed
+    fn transaction_remove_from_disputed_list(&self, tx: u32) -> bool {
+        // Panic if lock is poisoned
+        self.transactions_disputed.write().unwrap().remove(&tx)
+    }
 
-        // Limit lock time
-        {
+    /// Finish dispute with `apply`, transaction stays disputed if it fails
+    ///
+    /// Dispute is taken first, so only one of parallel resolves or chargebacks
+    /// of the same transaction applies.
+    fn settle_dispute<F>(&self, tx: u32, apply: F) -> Result<(), EngineError>
+    where
+        F: FnOnce() -> Result<(), EngineError>,
+    {
+        if !self.transaction_remove_from_disputed_list(tx) {
+            return Err(EngineError::TransactionNotDisputed(tx));
+        }
+
+        let result = apply();
+        if result.is_err() {
+            self.transaction_add_to_disputed_list(tx);
+        }
+
+        result
+    }
+
+    pub fn resolve(&self, client: u16, tx: u32) -> Result<(), EngineError> {
+        let _operation = self.begin_operation();
+        let recorded = self.find_transaction(tx)?;
+        let (
+            Leg {
+                account: id,
+                amount,
+            },
+            fee,
+        ) = self.disputed_leg(client, tx, recorded)?;
+
+        let events = (Event::Resolve, Event::Fee);
+        self.settle_dispute(tx, || match fee {
+            Some(fee) => self
+                .update_pair(&id, &fee.account, tx, events, |account, house| {
+                    // Create temporarly values to not update any account if any error
+                    let mut updated = account.clone();
+                    let mut updated_house = house.clone();
+                    release(&mut updated, id.client, amount)?;
+                    charge_fee(&mut updated, &mut updated_house, fee.amount)?;
+                    *account = updated;
+                    *house = updated_house;
+                    Ok(())
+                })
+                .map(|_| self.add_flow(&id.asset, Flow::FeeCharged, fee.amount)),
+            None => self.update_account(&id, tx, Event::Resolve, |account| {
+                release(account, id.client, amount)
+            }),
+        })
+    }
+
+    pub fn chargeback(&self, client: u16, tx: u32) -> Result<(), EngineError> {
+        let _operation = self.begin_operation();
+        let recorded = self.find_transaction(tx)?;
+        // Funds of movement are returned to sending account
+        let returned = match &recorded {
+            Recorded::Movement { from, .. } => Some(from.clone()),
+            Recorded::Single { .. } | Recorded::Admin(_) => None,
+        };
+        // Fee stays refunded as charged back transaction is void
+        let (
+            Leg {
+                account: id,
+                amount,
+            },
+            _,
+        ) = self.disputed_leg(client, tx, recorded)?;
+
+        self.settle_dispute(tx, || {
             // Panic if lock is poisoned
             let accounts_lock_read = self.accounts.read().unwrap();
-            let mutex = accounts_lock_read
-                .get(&client)
-                .ok_or(EngineError::CannotFindAccount(client))?;
 
-            // Panic if lock is poisoned
-            let mut account = mutex.lock().unwrap();
+            match returned {
+                None => {
+                    let mutex = accounts_lock_read
+                        .get(&id)
+                        .ok_or(EngineError::CannotFindAccount(id.client))?;
 
-            account
-                .available
-                .add(amount)
-                .map_err(|source| EngineError::ResolveCannotAddAvailable { source })?;
-            account
-                .held
-                .substract(amount)
-                .map_err(|source| EngineError::ResolveCannotSubstractHeld { source })?;
-        }
+                    let mut account = mutex.lock().unwrap();
+                    let change = self.begin_change();
+                    let before = self.before(&change, &account);
 
-        self.transaction_remove_from_disputed_list(tx);
+                    account
+                        .held
+                        .substract(amount)
+                        .map_err(|source| EngineError::ChargebackCannotSubstractHeld { source })?;
 
-        Ok(())
-    }
+                    account.status = Status::Locked {
+                        reason: String::from("chargeback"),
+                        tx,
+                    };
+                    self.record_change(&change, &id, tx, Event::Chargeback, before, &account);
+                    self.add_flow(&id.asset, Flow::ChargedBack, amount);
+                }
+                Some(from) => {
+                    let (mut target, mut source) =
+                        lock_pair(&accounts_lock_read, &id, &from.account)
+                            .ok_or(EngineError::CannotFindAccount(id.client))?;
+
+                    let change = self.begin_change();
+                    let target_before = self.before(&change, &target);
+                    let source_before = self.before(&change, &source);
+
+                    // Funds may return to locked account as dispute does, but never to closed one
+                    source.ensure_allows(Access::Dispute, from.account.client)?;
+
+                    // Create temporarly values to not update any account if any error
+                    let mut held = target.held;
+                    held.substract(amount)
+                        .map_err(|source| EngineError::ChargebackCannotSubstractHeld { source })?;
+                    let mut available = source.available;
+                    available
+                        .add(from.amount)
+                        .map_err(|source| EngineError::ChargebackCannotAddAvailable { source })?;
+                    // Returned funds may not make total of source out of range
+                    let mut total = available;
+                    total
+                        .add(source.held)
+                        .map_err(|source| EngineError::ChargebackCannotAddAvailable { source })?;
+
+                    target.held = held;
+                    target.status = Status::Locked {
+                        reason: String::from("chargeback"),
+                        tx,
+                    };
+                    source.available = available;
+                    let event = Event::Chargeback;
+                    self.record_change(&change, &id, tx, event, target_before, &target);
+                    let from_id = &from.account;
+                    self.record_change(&change, from_id, tx, event, source_before, &source);
+                    self.add_flow(&id.asset, Flow::ChargedBack, amount);
+                    self.add_flow(&from.account.asset, Flow::Returned, from.amount);
+                }
+            }
 
-    pub fn chargeback(&mut self, client: u16, tx: u32) -> Result<(), EngineError> {
-        let amount = self.get_transaction_amount(tx)?;
+            Ok(())
+        })
+    }
 
-        self.ensure_transaction_is_disputed(tx)?;
+    /// Apply administrative operation and keep it in history
+    ///
+    /// All affected accounts are changed or none of them. Its tx id has to be
+    /// unique as of any other transaction.
+    pub fn admin(&self, record: AdminRecord) -> Result<(), EngineError> {
+        let _operation = self.begin_operation();
+        let client = record.client;
+        let tx = record.tx;
+        self.record_transaction(tx, Recorded::Admin(record.clone()))?;
 
         // Limit lock time
         {
             // Panic if lock is poisoned
             let accounts_lock_read = self.accounts.read().unwrap();
-            let mutex = accounts_lock_read
-                .get(&client)
-                .ok_or(EngineError::CannotFindAccount(client))?;
 
-            let mut account = mutex.lock().unwrap();
+            let mut ids: Vec<&AccountId> = accounts_lock_read
+                .keys()
+                .filter(|id| {
+                    id.client == client
+                        && record.asset.as_ref().is_none_or(|asset| &id.asset == asset)
+                })
+                .collect();
+            if ids.is_empty() {
+                return Err(EngineError::AccountDoesNotExist(client));
+            }
 
-            account
-                .held
-                .substract(amount)
-                .map_err(|source| EngineError::ChargebackCannotSubstractHeld { source })?;
+            // Always lock in order of ids as other operations on many accounts do
+            ids.sort_unstable();
+            // Panic if mutex is poisoned
+            let mut accounts: Vec<_> = ids
+                .iter()
+                .map(|id| accounts_lock_read[*id].lock().unwrap())
+                .collect();
+
+            // Create temporarly values to not update any account if any error
+            let mut updated = Vec::with_capacity(accounts.len());
+            for account in &accounts {
+                let mut account = Account::clone(account);
+                apply_admin(&mut account, &record)?;
+                updated.push(account);
+            }
 
-            account.locked = true;
+            let event = Event::from(record.action);
+            let change = self.begin_change();
+            for ((id, account), updated) in ids.iter().zip(accounts.iter_mut()).zip(updated) {
+                let before = self.before(&change, account);
+                **account = updated;
+                self.record_change(&change, id, tx, event, before, account);
+            }
         }
 
-        self.transaction_remove_from_disputed_list(tx);
-
         Ok(())
     }
 
-    pub fn accounts(&self) -> &RwLock<HashMap<u16, Mutex<Account>>> {
-        &self.accounts
+    /// Applied operations on all accounts of client with running balances
+    ///
+    /// It is empty if history is not kept, see `EngineOptions::keep_history`.
+    pub fn statement(&self, client: u16) -> Vec<Entry> {
+        self.history
+            .as_ref()
+            .map(|history| history.statement(client))
+            .unwrap_or_default()
     }
+
+    /// Copy state of account before change only if history or snapshot needs it
+    fn before(&self, change: &Change, account: &Account) -> Before {
+        if self.history.is_some() || change.keeps() {
+            Before::Existing(account.clone())
+        } else {
+            Before::Skipped
+        }
+    }
+
+    /// Append change of account into history and keep state before it for
+    /// snapshots, it has to be called while account is locked
+    ///
+    /// Invariants of changed account are checked in debug builds as well.
+    fn record_change(
+        &self,
+        change: &Change,
+        id: &AccountId,
+        tx: u32,
+        event: Event,
+        before: Before,
+        after: &Account,
+    ) {
+        // Refund of fee may overdraw house account
+        let house = self.options.fees.as_ref().map(|fees| fees.house);
+        debug_assert!(
+            after
+                .invariants_hold(self.options.allow_negative_available || house == Some(id.client)),
+            "invariants of account {} broken after tx {}: {:?}",
+            id,
+            tx,
+            after
+        );
+
+        if let Some(history) = &self.history {
+            match &before {
+                Before::Existing(before) => {
+                    history.record(Entry::new(id, tx, event, before, after))
+                }
+                Before::Created => {
+                    history.record(Entry::new(id, tx, event, &Account::default(), after))
+                }
+                Before::Skipped => {}
+            }
+        }
+        self.keep_before(change, id, before);
+    }
+
+    /// Accounts with available funds below zero
+    pub fn overdrawn_accounts(&self) -> Vec<AccountId> {
+        // Panic if lock is poisoned
+        let accounts_lock_read = self.accounts.read().unwrap();
+
+        let mut ids: Vec<AccountId> = accounts_lock_read
+            .iter()
+            .filter(|(_, mutex)| mutex.lock().unwrap().is_overdrawn())
+            .map(|(id, _)| id.clone())
+            .collect();
+        ids.sort_unstable();
+
+        ids
+    }
+}
+
+/// Lock two different accounts, `None` if any does not exist
+///
+/// Locks are always taken in order of account ids, so two threads locking
+/// the same pair cannot wait for each other.
+fn lock_pair<'a>(
+    accounts: &'a HashMap<AccountId, Mutex<Account>>,
+    first: &AccountId,
+    second: &AccountId,
+) -> Option<(MutexGuard<'a, Account>, MutexGuard<'a, Account>)> {
+    let first_mutex = accounts.get(first)?;
+    let second_mutex = accounts.get(second)?;
+
+    // Panic if mutex is poisoned
+    if first < second {
+        let first_guard = first_mutex.lock().unwrap();
+        let second_guard = second_mutex.lock().unwrap();
+        Some((first_guard, second_guard))
+    } else {
+        let second_guard = second_mutex.lock().unwrap();
+        let first_guard = first_mutex.lock().unwrap();
+        Some((first_guard, second_guard))
+    }
+}
+
+/// Take `from` leg of `source` account and give `to` leg into `target` account
+///
+/// None of accounts is changed if any leg fails.
+fn apply_movement(
+    source: &mut Account,
+    target: &mut Account,
+    tx: u32,
+    from: &Leg,
+    to: &Leg,
+) -> Result<(), EngineError> {
+    // Create temporarly value to not update source account if target fails
+    let mut updated = source.clone();
+    debit(&mut updated, from.account.client, tx, from.amount)?;
+    credit(target, to.account.client, tx, to.amount)?;
+    *source = updated;
+
+    Ok(())
+}
+
+/// Give funds into available ones as deposit does, account is not changed if any error
+fn credit(
+    account: &mut Account,
+    client: u16,
+    tx: u32,
+    amount: Currency,
+) -> Result<(), EngineError> {
+    account.ensure_allows(Access::Receive, client)?;
+
+    let mut available = account.available;
+    available
+        .add(amount)
+        .map_err(|source| EngineError::CannotDeposit {
+            client,
+            tx,
+            amount,
+            source,
+        })?;
+
+    // Ensure total will not exceed max limit as in deposit
+    let held = account.held;
+    let mut total = available;
+    total
+        .add(held)
+        .map_err(|source| EngineError::CannotDepositTotalExceededMaxLimit {
+            client,
+            tx,
+            amount,
+            available,
+            held,
+            source,
+        })?;
+
+    account.available = available;
+
+    Ok(())
+}
+
+/// Take funds from available ones as withdrawal does, account is not changed if any error
+fn debit(account: &mut Account, client: u16, tx: u32, amount: Currency) -> Result<(), EngineError> {
+    account.ensure_allows(Access::Send, client)?;
+
+    account
+        .available
+        .substract(amount)
+        .map_err(|source| EngineError::CannotWithdrawal {
+            client,
+            tx,
+            amount,
+            source,
+        })
+}
+
+/// Move disputed funds from available to held ones
+fn hold(
+    account: &mut Account,
+    client: u16,
+    amount: Currency,
+    allow_negative: bool,
+) -> Result<(), EngineError> {
+    account.ensure_allows(Access::Dispute, client)?;
+
+    if allow_negative {
+        account.available.substract_allow_negative(amount)
+    } else {
+        account.available.substract(amount)
+    }
+    .map_err(|source| EngineError::DisputeCannotSubstractAvailable { source })?;
+
+    account
+        .held
+        .add(amount)
+        .map_err(|source| EngineError::DisputeCannotAddHeld { source })
+}
+
+/// Move resolved funds from held back to available ones
+fn release(account: &mut Account, client: u16, amount: Currency) -> Result<(), EngineError> {
+    account.ensure_allows(Access::Dispute, client)?;
+
+    account
+        .available
+        .add(amount)
+        .map_err(|source| EngineError::ResolveCannotAddAvailable { source })?;
+
+    account
+        .held
+        .substract(amount)
+        .map_err(|source| EngineError::ResolveCannotSubstractHeld { source })
+}
+
+fn apply_admin(account: &mut Account, record: &AdminRecord) -> Result<(), EngineError> {
+    let client = record.client;
+    let reason = record.reason.clone();
+
+    account.status = match (&account.status, record.action) {
+        (Status::Closed { .. }, _) => return Err(EngineError::AccountClosed(client)),
+        (_, AdminAction::Lock) => Status::Locked {
+            reason: reason.unwrap_or_else(|| String::from("administrator")),
+            tx: record.tx,
+        },
+        // Freeze would let locked account to receive funds again
+        (Status::Locked { .. }, AdminAction::Freeze) => {
+            return Err(EngineError::AccountLocked(client))
+        }
+        (_, AdminAction::Freeze) => Status::Frozen { reason },
+        (_, AdminAction::Unlock) => Status::Active,
+        (_, AdminAction::Close) => {
+            if account.available != Balance::zero() || account.held != Currency::zero() {
+                return Err(EngineError::CannotCloseAccountWithFunds(client));
+            }
+            Status::Closed { reason }
+        }
+    };
+
+    Ok(())
+}
+
+/// Give fee back from house account to client
+///
+/// House account may be overdrawn as fee could be spent already, client may
+/// dispute transaction anyway.
+fn refund_fee(
+    account: &mut Account,
+    house: &mut Account,
+    fee: Currency,
+) -> Result<(), EngineError> {
+    house
+        .available
+        .substract_allow_negative(fee)
+        .map_err(|source| EngineError::CannotRefundFee { source })?;
+
+    account
+        .available
+        .add(fee)
+        .and_then(|_| account.ensure_total_fits())
+        .map_err(|source| EngineError::CannotRefundFee { source })
+}
+
+/// Take fee from client into house account again
+fn charge_fee(
+    account: &mut Account,
+    house: &mut Account,
+    fee: Currency,
+) -> Result<(), EngineError> {
+    account
+        .available
+        .substract(fee)
+        .map_err(|source| EngineError::CannotChargeFee { source })?;
+
+    house
+        .available
+        .add(fee)
+        .and_then(|_| house.ensure_total_fits())
+        .map_err(|source| EngineError::CannotChargeFee { source })
 }
 
 impl Default for Engine {
@@ -344,19 +1265,26 @@ impl Default for Engine {
 mod tests {
 
     use super::*;
+    use crate::api::currency::balance::Balance;
     use crate::api::currency::error::CurrencyError;
+    use crate::api::engine::fees::FeeRule;
+    use crate::api::engine::fees::FeeSchedule;
+    use crate::api::engine::fees::Percent;
+    use crate::api::engine::rates::Rate;
+    use crate::api::engine::rates::RateTable;
     use assert_matches::assert_matches;
+    use std::convert::TryFrom;
 
     #[test]
     fn correct_deposit() {
-        let mut engine = Engine::new();
+        let engine = Engine::new();
         let amount = Currency::new(1, 1).unwrap();
         assert!(engine.deposit(1, 1, amount).is_ok());
     }
 
     #[test]
     fn correct_2_deposits_for_one_account() {
-        let mut engine = Engine::new();
+        let engine = Engine::new();
         let amount = Currency::new(1, 1).unwrap();
         assert!(engine.deposit(1, 1, amount).is_ok());
         assert!(engine.deposit(1, 2, amount).is_ok());
@@ -364,7 +1292,7 @@ mod tests {
 
     #[test]
     fn incorrect_2_deposits_for_one_account_out_of_range() {
-        let mut engine = Engine::new();
+        let engine = Engine::new();
         let amount = Currency::max();
         assert!(engine.deposit(1, 1, amount).is_ok());
         assert_matches!(
@@ -380,7 +1308,7 @@ mod tests {
 
     #[test]
     fn incorrect_2_deposits_with_same_tx() {
-        let mut engine = Engine::new();
+        let engine = Engine::new();
         let amount = Currency::new(1, 1).unwrap();
         assert!(engine.deposit(1, 1, amount).is_ok());
         assert_matches!(
@@ -391,7 +1319,7 @@ mod tests {
 
     #[test]
     fn incorrect_2_withdrawals_with_same_tx() {
-        let mut engine = Engine::new();
+        let engine = Engine::new();
         let amount = Currency::new(1, 1).unwrap();
         assert!(engine.deposit(1, 1, amount).is_ok());
         assert_matches!(
@@ -402,7 +1330,7 @@ mod tests {
 
     #[test]
     fn incorrect_withdrawal_from_unexisting_account() {
-        let mut engine = Engine::new();
+        let engine = Engine::new();
         let amount = Currency::new(1, 1).unwrap();
         assert_matches!(
             engine.withdrawal(1, 1, amount),
@@ -412,7 +1340,7 @@ mod tests {
 
     #[test]
     fn correct_withdrawal_from_deposited_account() {
-        let mut engine = Engine::new();
+        let engine = Engine::new();
         let amount = Currency::new(1, 1).unwrap();
         assert!(engine.deposit(1, 1, amount).is_ok());
         assert!(engine.withdrawal(1, 2, amount).is_ok());
@@ -420,7 +1348,7 @@ mod tests {
 
     #[test]
     fn correct_withdrawal_less_then_deposited() {
-        let mut engine = Engine::new();
+        let engine = Engine::new();
         let amount_more = Currency::new(2, 2).unwrap();
         let amount_less = Currency::new(1, 1).unwrap();
         assert!(engine.deposit(1, 1, amount_more).is_ok());
@@ -429,7 +1357,7 @@ mod tests {
 
     #[test]
     fn incorrect_withdrawal_more_then_deposited() {
-        let mut engine = Engine::new();
+        let engine = Engine::new();
         let amount_less = Currency::new(1, 1).unwrap();
         let amount_more = Currency::new(2, 2).unwrap();
         assert!(engine.deposit(1, 1, amount_less).is_ok());
@@ -446,7 +1374,7 @@ mod tests {
 
     #[test]
     fn correct_dispute() {
-        let mut engine = Engine::new();
+        let engine = Engine::new();
         let amount = Currency::new(1, 1).unwrap();
         assert!(engine.deposit(1, 1, amount).is_ok());
         assert!(engine.dispute(1, 1).is_ok());
@@ -454,7 +1382,7 @@ mod tests {
 
     #[test]
     fn incorrect_dispute_twice_some_tx() {
-        let mut engine = Engine::new();
+        let engine = Engine::new();
         let amount = Currency::new(1, 1).unwrap();
         assert!(engine.deposit(1, 1, amount).is_ok());
         assert!(engine.dispute(1, 1).is_ok());
@@ -466,7 +1394,7 @@ mod tests {
 
     #[test]
     fn incorrect_deposit_which_exceed_total_limit() {
-        let mut engine = Engine::new();
+        let engine = Engine::new();
         let amount = Currency::max();
         assert!(engine.deposit(1, 1, amount).is_ok());
         assert!(engine.dispute(1, 1).is_ok());
@@ -478,7 +1406,7 @@ mod tests {
 
     #[test]
     fn correct_resolve() {
-        let mut engine = Engine::new();
+        let engine = Engine::new();
         let amount = Currency::new(1, 1).unwrap();
         assert!(engine.deposit(1, 1, amount).is_ok());
         assert!(engine.dispute(1, 1).is_ok());
@@ -487,7 +1415,7 @@ mod tests {
 
     #[test]
     fn incorrect_resolve_unexisting_tx() {
-        let mut engine = Engine::new();
+        let engine = Engine::new();
         assert_matches!(
             engine.resolve(1, 1),
             Err(EngineError::CannotFindTransaction(..))
@@ -496,7 +1424,7 @@ mod tests {
 
     #[test]
     fn incorrect_resolve_not_disputed_tx() {
-        let mut engine = Engine::new();
+        let engine = Engine::new();
         let amount = Currency::new(1, 1).unwrap();
         assert!(engine.deposit(1, 1, amount).is_ok());
         assert_matches!(
@@ -507,7 +1435,7 @@ mod tests {
 
     #[test]
     fn correct_chargeback() {
-        let mut engine = Engine::new();
+        let engine = Engine::new();
         let amount = Currency::new(1, 1).unwrap();
         assert!(engine.deposit(1, 1, amount).is_ok());
         assert!(engine.dispute(1, 1).is_ok());
@@ -516,7 +1444,7 @@ mod tests {
 
     #[test]
     fn incorrect_chargeback_unexisting_tx() {
-        let mut engine = Engine::new();
+        let engine = Engine::new();
         assert_matches!(
             engine.chargeback(1, 1),
             Err(EngineError::CannotFindTransaction(..))
@@ -525,7 +1453,7 @@ mod tests {
 
     #[test]
     fn incorrect_chargeback_not_disputed_tx() {
-        let mut engine = Engine::new();
+        let engine = Engine::new();
         let amount = Currency::new(1, 1).unwrap();
         assert!(engine.deposit(1, 1, amount).is_ok());
         assert_matches!(
@@ -536,7 +1464,7 @@ mod tests {
 
     #[test]
     fn incorrect_deposit_on_locked_account_tx() {
-        let mut engine = Engine::new();
+        let engine = Engine::new();
         let amount = Currency::new(1, 1).unwrap();
         assert!(engine.deposit(1, 1, amount).is_ok());
         assert!(engine.dispute(1, 1).is_ok());
@@ -546,4 +1474,747 @@ mod tests {
             Err(EngineError::AccountLocked(..))
         );
     }
+
+    #[test]
+    fn incorrect_dispute_withdrawn_funds_by_default() {
+        let engine = Engine::new();
+        assert!(engine.deposit(1, 1, Currency::new(1, 0).unwrap()).is_ok());
+        assert!(engine
+            .withdrawal(1, 2, Currency::new(1, 0).unwrap())
+            .is_ok());
+        assert_matches!(
+            engine.dispute(1, 1),
+            Err(EngineError::DisputeCannotSubstractAvailable { .. })
+        );
+        assert!(engine.overdrawn_accounts().is_empty());
+    }
+
+    #[test]
+    fn correct_dispute_withdrawn_funds_when_negative_allowed() {
+        let engine = Engine::with_options(EngineOptions {
+            allow_negative_available: true,
+            ..EngineOptions::default()
+        });
+        assert!(engine.deposit(1, 1, Currency::new(1, 0).unwrap()).is_ok());
+        assert!(engine
+            .withdrawal(1, 2, Currency::new(1, 0).unwrap())
+            .is_ok());
+        assert!(engine.dispute(1, 1).is_ok());
+        assert!(engine.chargeback(1, 1).is_ok());
+        assert_eq!(
+            engine.overdrawn_accounts(),
+            vec![AccountId::new(1, &Asset::default())]
+        );
+    }
+
+    #[test]
+    fn incorrect_withdrawal_from_overdrawn_account() {
+        let engine = Engine::with_options(EngineOptions {
+            allow_negative_available: true,
+            ..EngineOptions::default()
+        });
+        assert!(engine.deposit(1, 1, Currency::new(1, 0).unwrap()).is_ok());
+        assert!(engine
+            .withdrawal(1, 2, Currency::new(1, 0).unwrap())
+            .is_ok());
+        assert!(engine.deposit(1, 3, Currency::new(1, 0).unwrap()).is_ok());
+        assert!(engine.dispute(1, 1).is_ok());
+        assert!(engine.dispute(1, 3).is_ok());
+        assert_matches!(
+            engine.withdrawal(1, 4, Currency::new(0, 1).unwrap()),
+            Err(EngineError::CannotWithdrawal {
+                client: _,
+                tx: _,
+                amount: _,
+                source: CurrencyError::SubstractingOtherNegative
+            })
+        );
+    }
+
+    #[test]
+    fn separate_accounts_for_assets() {
+        let engine = Engine::new();
+        let usd = Asset::new("USD");
+        let eur = Asset::new("EUR");
+        let amount = Currency::new(1, 0).unwrap();
+        assert!(engine.deposit_asset(1, &usd, 1, amount).is_ok());
+        assert_matches!(
+            engine.withdrawal_asset(1, &eur, 2, amount),
+            Err(EngineError::AccountDoesNotExist(1))
+        );
+        assert!(engine.deposit_asset(1, &eur, 3, amount).is_ok());
+        assert!(engine.dispute(1, 3).is_ok());
+        assert_matches!(
+            engine.withdrawal_asset(1, &eur, 4, amount),
+            Err(EngineError::CannotWithdrawal { .. })
+        );
+        assert!(engine.withdrawal_asset(1, &usd, 5, amount).is_ok());
+    }
+
+    fn engine_with_rate(from: &Asset, to: &Asset, rate: &str) -> Engine {
+        let mut rates = RateTable::new();
+        rates.insert(from.clone(), to.clone(), Rate::try_from(rate).unwrap());
+        Engine::with_options(EngineOptions {
+            rates,
+            ..EngineOptions::default()
+        })
+    }
+
+    fn available(engine: &Engine, client: u16, asset: &Asset) -> Option<Balance> {
+        engine
+            .get_account_asset(client, asset)
+            .map(|account| account.available)
+    }
+
+    #[test]
+    fn correct_exchange() {
+        let usd = Asset::new("USD");
+        let eur = Asset::new("EUR");
+        let engine = engine_with_rate(&usd, &eur, "0.9");
+        assert!(engine
+            .deposit_asset(1, &usd, 1, Currency::new(10, 0).unwrap())
+            .is_ok());
+        assert!(engine
+            .exchange(1, 2, &usd, &eur, Currency::new(2, 5).unwrap())
+            .is_ok());
+        assert_eq!(
+            available(&engine, 1, &usd),
+            Some(Balance::from(Currency::new(7, 9995).unwrap()))
+        );
+        assert_eq!(
+            available(&engine, 1, &eur),
+            Some(Balance::from(Currency::new(1, 8004).unwrap()))
+        );
+    }
+
+    #[test]
+    fn incorrect_exchange_without_rate() {
+        let usd = Asset::new("USD");
+        let eur = Asset::new("EUR");
+        let engine = engine_with_rate(&usd, &eur, "0.9");
+        assert!(engine
+            .deposit_asset(1, &eur, 1, Currency::new(10, 0).unwrap())
+            .is_ok());
+        assert_matches!(
+            engine.exchange(1, 2, &eur, &usd, Currency::new(1, 0).unwrap()),
+            Err(EngineError::CannotFindRate { .. })
+        );
+    }
+
+    #[test]
+    fn incorrect_exchange_applies_no_leg() {
+        let usd = Asset::new("USD");
+        let eur = Asset::new("EUR");
+        let engine = engine_with_rate(&usd, &eur, "0.9");
+        let amount = Currency::new(1, 0).unwrap();
+        assert!(engine.deposit_asset(1, &usd, 1, amount).is_ok());
+        assert!(engine.deposit_asset(1, &eur, 2, Currency::max()).is_ok());
+        assert_matches!(
+            engine.exchange(1, 3, &usd, &eur, amount),
+            Err(EngineError::CannotDeposit { .. })
+        );
+        assert_eq!(available(&engine, 1, &usd), Some(Balance::from(amount)));
+        assert_matches!(
+            engine.exchange(1, 4, &usd, &eur, Currency::new(2, 0).unwrap()),
+            Err(EngineError::CannotWithdrawal { .. })
+        );
+        assert_eq!(available(&engine, 1, &usd), Some(Balance::from(amount)));
+    }
+
+    #[test]
+    fn incorrect_exchange_rounded_to_zero() {
+        let usd = Asset::new("USD");
+        let eur = Asset::new("EUR");
+        let engine = engine_with_rate(&usd, &eur, "0.1");
+        let amount = Currency::new(1, 0).unwrap();
+        assert!(engine.deposit_asset(1, &usd, 1, amount).is_ok());
+        assert_matches!(
+            engine.exchange(1, 2, &usd, &eur, Currency::new(0, 4).unwrap()),
+            Err(EngineError::ExchangeToZero { .. })
+        );
+        assert_eq!(available(&engine, 1, &usd), Some(Balance::from(amount)));
+        assert_eq!(available(&engine, 1, &eur), None);
+    }
+
+    #[test]
+    fn incorrect_exchange_without_account_creates_none() {
+        let usd = Asset::new("USD");
+        let eur = Asset::new("EUR");
+        let engine = engine_with_rate(&usd, &eur, "0.9");
+        let amount = Currency::new(1, 0).unwrap();
+        assert!(engine.deposit_asset(1, &usd, 1, amount).is_ok());
+        assert_matches!(
+            engine.exchange(1, 2, &usd, &eur, Currency::new(2, 0).unwrap()),
+            Err(EngineError::CannotWithdrawal { .. })
+        );
+        assert_eq!(available(&engine, 1, &eur), None);
+    }
+
+    #[test]
+    fn chargeback_exchange_returns_source() {
+        let usd = Asset::new("USD");
+        let eur = Asset::new("EUR");
+        let engine = engine_with_rate(&usd, &eur, "0.5");
+        let amount = Currency::new(4, 0).unwrap();
+        assert!(engine.deposit_asset(1, &usd, 1, amount).is_ok());
+        assert!(engine.exchange(1, 2, &usd, &eur, amount).is_ok());
+        assert_matches!(
+            engine.dispute(2, 2),
+            Err(EngineError::NotPartyOfTransaction { .. })
+        );
+        assert!(engine.dispute(1, 2).is_ok());
+        assert_eq!(available(&engine, 1, &eur), Some(Balance::zero()));
+        assert!(engine.chargeback(1, 2).is_ok());
+        assert_eq!(available(&engine, 1, &usd), Some(Balance::from(amount)));
+        assert_matches!(
+            engine.deposit_asset(1, &eur, 3, amount),
+            Err(EngineError::AccountLocked(1))
+        );
+    }
+
+    #[test]
+    fn correct_transfer() {
+        let engine = Engine::new();
+        let asset = Asset::default();
+        assert!(engine.deposit(1, 1, Currency::new(3, 0).unwrap()).is_ok());
+        assert!(engine
+            .transfer(1, 2, &asset, 2, Currency::new(1, 0).unwrap())
+            .is_ok());
+        assert_eq!(
+            available(&engine, 1, &asset),
+            Some(Balance::from(Currency::new(2, 0).unwrap()))
+        );
+        assert_eq!(
+            available(&engine, 2, &asset),
+            Some(Balance::from(Currency::new(1, 0).unwrap()))
+        );
+    }
+
+    #[test]
+    fn incorrect_transfer_to_itself() {
+        let engine = Engine::new();
+        let amount = Currency::new(1, 0).unwrap();
+        assert!(engine.deposit(1, 1, amount).is_ok());
+        assert_matches!(
+            engine.transfer(1, 1, &Asset::default(), 2, amount),
+            Err(EngineError::TransferToItself(2))
+        );
+    }
+
+    #[test]
+    fn incorrect_transfer_to_locked_account() {
+        let engine = Engine::new();
+        let amount = Currency::new(1, 0).unwrap();
+        assert!(engine.deposit(1, 1, amount).is_ok());
+        assert!(engine.deposit(2, 2, amount).is_ok());
+        assert!(engine.dispute(2, 2).is_ok());
+        assert!(engine.chargeback(2, 2).is_ok());
+        assert_matches!(
+            engine.transfer(1, 2, &Asset::default(), 3, amount),
+            Err(EngineError::AccountLocked(2))
+        );
+        assert_eq!(
+            available(&engine, 1, &Asset::default()),
+            Some(Balance::from(amount))
+        );
+    }
+
+    #[test]
+    fn dispute_transfer_by_any_party() {
+        let engine = Engine::new();
+        let asset = Asset::default();
+        let amount = Currency::new(1, 0).unwrap();
+        assert!(engine.deposit(1, 1, amount).is_ok());
+        assert!(engine.transfer(1, 2, &asset, 2, amount).is_ok());
+        assert_matches!(
+            engine.dispute(3, 2),
+            Err(EngineError::NotPartyOfTransaction { .. })
+        );
+        assert!(engine.dispute(1, 2).is_ok());
+        assert!(engine.resolve(2, 2).is_ok());
+        assert!(engine.dispute(2, 2).is_ok());
+        assert!(engine.chargeback(1, 2).is_ok());
+        assert_eq!(available(&engine, 1, &asset), Some(Balance::from(amount)));
+        assert_eq!(available(&engine, 2, &asset), Some(Balance::zero()));
+    }
+
+    #[test]
+    fn concurrent_opposite_transfers() {
+        let engine = Engine::new();
+        let asset = Asset::default();
+        let amount = Currency::new(1, 0).unwrap();
+        assert!(engine
+            .deposit(1, 1, Currency::new(1000, 0).unwrap())
+            .is_ok());
+        assert!(engine
+            .deposit(2, 2, Currency::new(1000, 0).unwrap())
+            .is_ok());
+
+        // Opposite directions would deadlock if locks were taken by direction
+        std::thread::scope(|scope| {
+            for (client, to_client, first_tx) in [(1, 2, 1000), (2, 1, 2000)] {
+                let engine = &engine;
+                let asset = &asset;
+                scope.spawn(move || {
+                    for tx in first_tx..first_tx + 500 {
+                        engine
+                            .transfer(client, to_client, asset, tx, amount)
+                            .unwrap();
+                    }
+                });
+            }
+        });
+
+        assert_eq!(
+            available(&engine, 1, &asset),
+            Some(Balance::from(Currency::new(1000, 0).unwrap()))
+        );
+    }
+
+    #[test]
+    fn concurrent_disputes_hold_once() {
+        let engine = Engine::new();
+        let asset = Asset::default();
+        let amount = Currency::new(1, 0).unwrap();
+        for tx in 1..=100 {
+            assert!(engine.deposit(1, tx, amount).is_ok());
+        }
+        let held = || engine.get_account_asset(1, &asset).unwrap().held;
+
+        // Every transaction is disputed, resolved and charged back by many threads at once
+        let in_parallel = |operation: fn(&Engine, u16, u32) -> Result<(), EngineError>| {
+            std::thread::scope(|scope| {
+                for _ in 0..4 {
+                    let engine = &engine;
+                    scope.spawn(move || {
+                        for tx in 1..=100 {
+                            let _ = operation(engine, 1, tx);
+                        }
+                    });
+                }
+            });
+        };
+
+        in_parallel(Engine::dispute);
+        assert_eq!(held(), Currency::new(100, 0).unwrap());
+        in_parallel(Engine::resolve);
+        assert_eq!(held(), Currency::zero());
+        assert_eq!(
+            available(&engine, 1, &asset),
+            Some(Balance::from(Currency::new(100, 0).unwrap()))
+        );
+        in_parallel(Engine::dispute);
+        in_parallel(Engine::chargeback);
+        assert_eq!(held(), Currency::zero());
+        assert_eq!(available(&engine, 1, &asset), Some(Balance::zero()));
+    }
+
+    fn engine_with_fees() -> Engine {
+        let rule = |operation, flat: &str, percent: &str| FeeRule {
+            operation,
+            client: None,
+            from: Currency::zero(),
+            flat: Currency::try_from(flat).unwrap(),
+            percent: Percent::try_from(percent).unwrap(),
+        };
+        Engine::with_options(EngineOptions {
+            fees: Some(FeeSchedule {
+                house: 0,
+                rounding: RoundingMode::HalfUp,
+                rules: vec![
+                    rule(Operation::Deposit, "0", "1"),
+                    rule(Operation::Withdrawal, "0.5", "0"),
+                ],
+            }),
+            ..EngineOptions::default()
+        })
+    }
+
+    fn balance(value: &str) -> Option<Balance> {
+        Some(Balance::try_from(value).unwrap())
+    }
+
+    fn held(engine: &Engine, client: u16) -> Option<Currency> {
+        engine.get_account(client).map(|account| account.held)
+    }
+
+    #[test]
+    fn correct_deposit_and_withdrawal_with_fee() {
+        let engine = engine_with_fees();
+        let asset = Asset::default();
+        assert!(engine.deposit(1, 1, Currency::new(100, 0).unwrap()).is_ok());
+        assert_eq!(available(&engine, 1, &asset), balance("99"));
+        assert_eq!(available(&engine, 0, &asset), balance("1"));
+        assert!(engine
+            .withdrawal(1, 2, Currency::new(10, 0).unwrap())
+            .is_ok());
+        assert_eq!(available(&engine, 1, &asset), balance("88.5"));
+        assert_eq!(available(&engine, 0, &asset), balance("1.5"));
+    }
+
+    #[test]
+    fn incorrect_withdrawal_with_fee_applies_nothing() {
+        let engine = engine_with_fees();
+        let asset = Asset::default();
+        assert!(engine.deposit(1, 1, Currency::new(100, 0).unwrap()).is_ok());
+        assert_matches!(
+            engine.withdrawal(1, 2, Currency::new(99, 0).unwrap()),
+            Err(EngineError::CannotWithdrawal { .. })
+        );
+        assert_eq!(available(&engine, 1, &asset), balance("99"));
+        assert_eq!(available(&engine, 0, &asset), balance("1"));
+    }
+
+    #[test]
+    fn incorrect_deposit_smaller_than_fee() {
+        let engine = engine_with_fees();
+        let mut schedule = engine.options.fees.clone().unwrap();
+        schedule.rules[0].flat = Currency::new(1, 0).unwrap();
+        let engine = Engine::with_options(EngineOptions {
+            fees: Some(schedule),
+            ..EngineOptions::default()
+        });
+        assert_matches!(
+            engine.deposit(1, 1, Currency::new(0, 5000).unwrap()),
+            Err(EngineError::FeeExceedsAmount { .. })
+        );
+    }
+
+    #[test]
+    fn dispute_refunds_fee_and_resolve_charges_again() {
+        let engine = engine_with_fees();
+        let asset = Asset::default();
+        assert!(engine.deposit(1, 1, Currency::new(100, 0).unwrap()).is_ok());
+        assert!(engine.dispute(1, 1).is_ok());
+        assert_eq!(available(&engine, 1, &asset), balance("0"));
+        assert_eq!(held(&engine, 1), Currency::new(100, 0).ok());
+        assert_eq!(available(&engine, 0, &asset), balance("0"));
+        assert!(engine.resolve(1, 1).is_ok());
+        assert_eq!(available(&engine, 1, &asset), balance("99"));
+        assert_eq!(available(&engine, 0, &asset), balance("1"));
+    }
+
+    #[test]
+    fn chargeback_keeps_fee_refunded() {
+        let engine = engine_with_fees();
+        let asset = Asset::default();
+        assert!(engine.deposit(1, 1, Currency::new(100, 0).unwrap()).is_ok());
+        assert!(engine.dispute(1, 1).is_ok());
+        assert!(engine.chargeback(1, 1).is_ok());
+        assert_eq!(available(&engine, 1, &asset), balance("0"));
+        assert_eq!(held(&engine, 1), Some(Currency::zero()));
+        assert_eq!(available(&engine, 0, &asset), balance("0"));
+    }
+
+    #[test]
+    fn dispute_refunds_fee_already_spent_by_house() {
+        let engine = engine_with_fees();
+        let asset = Asset::default();
+        assert!(engine.deposit(1, 1, Currency::new(100, 0).unwrap()).is_ok());
+        assert!(engine
+            .withdrawal(0, 2, Currency::new(1, 0).unwrap())
+            .is_ok());
+        assert!(engine.dispute(1, 1).is_ok());
+        assert_eq!(held(&engine, 1), Currency::new(100, 0).ok());
+        assert_eq!(available(&engine, 0, &asset), balance("-1"));
+        assert!(engine.resolve(1, 1).is_ok());
+        assert_eq!(available(&engine, 0, &asset), balance("0"));
+    }
+
+    #[test]
+    fn chargeback_of_transfer_from_closed_account() {
+        let engine = Engine::new();
+        let asset = Asset::default();
+        let amount = Currency::new(10, 0).unwrap();
+        assert!(engine.deposit(1, 1, amount).is_ok());
+        assert!(engine.transfer(1, 2, &asset, 2, amount).is_ok());
+        assert!(admin(&engine, 1, 3, AdminAction::Close).is_ok());
+        assert!(engine.dispute(2, 2).is_ok());
+        assert_eq!(engine.chargeback(2, 2), Err(EngineError::AccountClosed(1)));
+        assert_eq!(available(&engine, 1, &asset), balance("0"));
+        assert_eq!(held(&engine, 2), Some(amount));
+        assert!(engine.get_transaction(2).unwrap().disputed);
+    }
+
+    #[test]
+    fn deposit_with_fee_into_locked_house_account() {
+        let engine = engine_with_fees();
+        let asset = Asset::default();
+        assert!(engine.deposit(1, 1, Currency::new(10, 0).unwrap()).is_ok());
+        assert!(admin(&engine, 0, 2, AdminAction::Lock).is_ok());
+        assert_eq!(
+            engine.deposit(1, 3, Currency::new(10, 0).unwrap()),
+            Err(EngineError::AccountLocked(0))
+        );
+        assert_eq!(available(&engine, 1, &asset), balance("9.9"));
+        assert_eq!(available(&engine, 0, &asset), balance("0.1"));
+        assert!(engine.reconcile().is_balanced());
+    }
+
+    #[test]
+    fn fees_are_counted_in_reconciliation() {
+        let engine = engine_with_fees();
+        assert!(engine.deposit(1, 1, Currency::new(100, 0).unwrap()).is_ok());
+        assert!(engine.deposit(1, 2, Currency::new(10, 0).unwrap()).is_ok());
+        assert!(engine.dispute(1, 2).is_ok());
+        let reconciliation = engine.reconcile();
+        assert_eq!(
+            reconciliation.assets[0].flows.fees(),
+            Some(Currency::new(1, 0).unwrap().into())
+        );
+        assert!(reconciliation.is_balanced());
+    }
+
+    fn admin(
+        engine: &Engine,
+        client: u16,
+        tx: u32,
+        action: AdminAction,
+    ) -> Result<(), EngineError> {
+        engine.admin(AdminRecord {
+            client,
+            asset: None,
+            tx,
+            action,
+            reason: Some(String::from("audit")),
+        })
+    }
+
+    #[test]
+    fn admin_without_account() {
+        let engine = Engine::new();
+        assert_matches!(
+            admin(&engine, 1, 101, AdminAction::Lock),
+            Err(EngineError::AccountDoesNotExist(1))
+        );
+    }
+
+    #[test]
+    fn admin_lock_and_unlock() {
+        let engine = Engine::new();
+        assert!(engine.deposit(1, 1, Currency::new(10, 0).unwrap()).is_ok());
+        assert!(admin(&engine, 1, 102, AdminAction::Lock).is_ok());
+        assert_matches!(
+            engine.deposit(1, 2, Currency::new(1, 0).unwrap()),
+            Err(EngineError::AccountLocked(1))
+        );
+        assert!(admin(&engine, 1, 103, AdminAction::Unlock).is_ok());
+        assert!(engine.deposit(1, 3, Currency::new(1, 0).unwrap()).is_ok());
+    }
+
+    #[test]
+    fn admin_needs_unique_tx() {
+        let engine = Engine::new();
+        assert!(engine.deposit(1, 1, Currency::new(10, 0).unwrap()).is_ok());
+        assert_matches!(
+            admin(&engine, 1, 1, AdminAction::Lock),
+            Err(EngineError::TransactionNotUnique(1))
+        );
+        assert!(admin(&engine, 1, 2, AdminAction::Lock).is_ok());
+        assert_matches!(
+            admin(&engine, 1, 2, AdminAction::Unlock),
+            Err(EngineError::TransactionNotUnique(2))
+        );
+        assert_matches!(
+            engine.deposit(1, 2, Currency::new(1, 0).unwrap()),
+            Err(EngineError::TransactionNotUnique(2))
+        );
+        assert_matches!(
+            engine.dispute(1, 2),
+            Err(EngineError::TransactionNotDisputable(2))
+        );
+    }
+
+    #[test]
+    fn admin_freeze_lets_receive_only() {
+        let engine = Engine::new();
+        assert!(engine.deposit(1, 1, Currency::new(10, 0).unwrap()).is_ok());
+        assert!(admin(&engine, 1, 104, AdminAction::Freeze).is_ok());
+        assert!(engine.deposit(1, 2, Currency::new(1, 0).unwrap()).is_ok());
+        assert_matches!(
+            engine.withdrawal(1, 3, Currency::new(1, 0).unwrap()),
+            Err(EngineError::AccountFrozen(1))
+        );
+    }
+
+    #[test]
+    fn admin_close_needs_empty_account() {
+        let engine = Engine::new();
+        assert!(engine.deposit(1, 1, Currency::new(10, 0).unwrap()).is_ok());
+        assert_matches!(
+            admin(&engine, 1, 105, AdminAction::Close),
+            Err(EngineError::CannotCloseAccountWithFunds(1))
+        );
+        assert!(engine
+            .withdrawal(1, 2, Currency::new(10, 0).unwrap())
+            .is_ok());
+        assert!(admin(&engine, 1, 106, AdminAction::Close).is_ok());
+        assert_matches!(
+            engine.deposit(1, 3, Currency::new(1, 0).unwrap()),
+            Err(EngineError::AccountClosed(1))
+        );
+        assert_matches!(
+            admin(&engine, 1, 107, AdminAction::Unlock),
+            Err(EngineError::AccountClosed(1))
+        );
+    }
+
+    #[test]
+    fn admin_applies_to_all_accounts_or_none() {
+        let engine = Engine::new();
+        let usd = Asset::new("USD");
+        assert!(engine.deposit(1, 1, Currency::new(10, 0).unwrap()).is_ok());
+        assert!(engine
+            .deposit_asset(1, &usd, 2, Currency::new(10, 0).unwrap())
+            .is_ok());
+        assert!(engine
+            .withdrawal_asset(1, &usd, 3, Currency::new(10, 0).unwrap())
+            .is_ok());
+        assert_matches!(
+            admin(&engine, 1, 108, AdminAction::Close),
+            Err(EngineError::CannotCloseAccountWithFunds(1))
+        );
+        assert!(engine
+            .deposit_asset(1, &usd, 4, Currency::new(1, 0).unwrap())
+            .is_ok());
+    }
+
+    #[test]
+    fn dispute_on_account_locked_by_chargeback() {
+        let engine = Engine::new();
+        assert!(engine.deposit(1, 1, Currency::new(10, 0).unwrap()).is_ok());
+        assert!(engine.deposit(1, 2, Currency::new(5, 0).unwrap()).is_ok());
+        assert!(engine.dispute(1, 1).is_ok());
+        assert!(engine.chargeback(1, 1).is_ok());
+        assert!(engine.dispute(1, 2).is_ok());
+        assert!(engine.resolve(1, 2).is_ok());
+        assert_matches!(
+            admin(&engine, 1, 109, AdminAction::Freeze),
+            Err(EngineError::AccountLocked(1))
+        );
+        assert_eq!(
+            engine.get_account(1).unwrap().status,
+            Status::Locked {
+                reason: String::from("chargeback"),
+                tx: 1
+            }
+        );
+    }
+
+    #[test]
+    fn dispute_on_closed_account() {
+        let engine = Engine::new();
+        assert!(engine.deposit(1, 1, Currency::new(10, 0).unwrap()).is_ok());
+        assert!(engine
+            .withdrawal(1, 2, Currency::new(10, 0).unwrap())
+            .is_ok());
+        assert!(admin(&engine, 1, 110, AdminAction::Close).is_ok());
+        assert_matches!(engine.dispute(1, 1), Err(EngineError::AccountClosed(1)));
+    }
+
+    fn engine_with_history() -> Engine {
+        Engine::with_options(EngineOptions {
+            keep_history: true,
+            ..EngineOptions::default()
+        })
+    }
+
+    #[test]
+    fn history_of_admin_operations() {
+        let engine = engine_with_history();
+        assert!(engine.deposit(1, 1, Currency::new(10, 0).unwrap()).is_ok());
+        assert!(admin(&engine, 1, 2, AdminAction::Freeze).is_ok());
+        assert!(admin(&engine, 1, 3, AdminAction::Unlock).is_ok());
+
+        let events: Vec<_> = engine
+            .statement(1)
+            .iter()
+            .map(|entry| (entry.tx, entry.event))
+            .collect();
+        assert_eq!(
+            events,
+            vec![(1, Event::Deposit), (2, Event::Freeze), (3, Event::Unlock)]
+        );
+    }
+
+    #[test]
+    fn history_not_kept_by_default() {
+        let engine = Engine::new();
+        assert!(engine.deposit(1, 1, Currency::new(10, 0).unwrap()).is_ok());
+        assert!(engine.statement(1).is_empty());
+    }
+
+    #[test]
+    fn history_with_running_balances() {
+        let engine = engine_with_history();
+        assert!(engine.deposit(1, 1, Currency::new(10, 0).unwrap()).is_ok());
+        assert!(engine
+            .withdrawal(1, 2, Currency::new(3, 0).unwrap())
+            .is_ok());
+        assert!(engine
+            .withdrawal(1, 3, Currency::new(30, 0).unwrap())
+            .is_err());
+        assert!(engine.deposit(1, 4, Currency::new(5, 0).unwrap()).is_ok());
+        assert!(engine.dispute(1, 4).is_ok());
+        assert!(engine.chargeback(1, 4).is_ok());
+
+        let statement = engine.statement(1);
+        let events: Vec<_> = statement.iter().map(|entry| entry.event).collect();
+        assert_eq!(
+            events,
+            vec![
+                Event::Deposit,
+                Event::Withdrawal,
+                Event::Deposit,
+                Event::Dispute,
+                Event::Chargeback
+            ]
+        );
+        let amounts: Vec<_> = statement.iter().map(|entry| entry.amount).collect();
+        assert_eq!(
+            amounts,
+            vec![
+                balance("10"),
+                balance("-3"),
+                balance("5"),
+                balance("0"),
+                balance("-5")
+            ]
+        );
+        assert_eq!(Some(statement[3].available), balance("7"));
+        assert_eq!(statement[3].held, Currency::new(5, 0).unwrap());
+        assert_eq!(statement[4].held, Currency::zero());
+    }
+
+    #[test]
+    fn history_of_transfer_for_both_clients() {
+        let engine = engine_with_history();
+        let asset = Asset::default();
+        assert!(engine.deposit(1, 1, Currency::new(10, 0).unwrap()).is_ok());
+        assert!(engine
+            .transfer(1, 2, &asset, 2, Currency::new(4, 0).unwrap())
+            .is_ok());
+        assert_eq!(engine.statement(1)[1].amount, balance("-4"));
+        assert_eq!(engine.statement(2).len(), 1);
+        assert_eq!(engine.statement(2)[0].event, Event::Transfer);
+        assert_eq!(Some(engine.statement(2)[0].available), balance("4"));
+    }
+
+    #[test]
+    fn incorrect_chargeback_returning_funds_above_total_limit() {
+        let engine = Engine::new();
+        let asset = Asset::default();
+        let amount = |value| Currency::new(value, 0).unwrap();
+        assert!(engine.deposit(1, 1, amount(1_000_000_000_000_000)).is_ok());
+        assert!(engine
+            .transfer(1, 2, &asset, 2, amount(500_000_000_000_000))
+            .is_ok());
+        assert!(engine.deposit(1, 3, amount(900_000_000_000_000)).is_ok());
+        assert!(engine.dispute(2, 2).is_ok());
+        assert_matches!(
+            engine.chargeback(2, 2),
+            Err(EngineError::ChargebackCannotAddAvailable { .. })
+        );
+        assert!(engine.reconcile().is_balanced());
+    }
 }
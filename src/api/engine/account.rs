@@ -1,18 +1,208 @@
+// Standard paths
+use std::fmt;
+
 // Crate paths
+use crate::api::asset::Asset;
+use crate::api::currency::balance::Balance;
+use crate::api::currency::error::CurrencyError;
 use crate::api::currency::Currency;
+use crate::api::engine::error::EngineError;
+
+// External paths
+use serde::Serialize;
+
+/// Account is kept separately for each asset of client
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+pub struct AccountId {
+    pub client: u16,
+    pub asset: Asset,
+}
+
+impl AccountId {
+    pub fn new(client: u16, asset: &Asset) -> Self {
+        Self {
+            client,
+            asset: asset.clone(),
+        }
+    }
+}
+
+impl fmt::Display for AccountId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.asset.is_default() {
+            write!(f, "client: {}", self.client)
+        } else {
+            write!(f, "client: {}, asset: {}", self.client, self.asset)
+        }
+    }
+}
 
+/// Kind of operation checked against status of account
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Access {
+    /// Funds are given to account, e.g. by deposit
+    Receive,
+    /// Funds are taken from account, e.g. by withdrawal
+    Send,
+    /// Funds are held or released by dispute, resolve and chargeback
+    Dispute,
+}
+
+/// Restriction of account
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+    Active,
+    /// No funds may be given nor taken, e.g. after chargeback
+    Locked {
+        reason: String,
+        tx: u32,
+    },
+    /// Funds may be given but not taken
+    Frozen {
+        reason: Option<String>,
+    },
+    /// Account is not used anymore, it cannot be opened again
+    Closed {
+        reason: Option<String>,
+    },
+}
+
+impl Status {
+    /// Policy which operations are allowed in given status
+    pub fn allows(&self, access: Access) -> bool {
+        match self {
+            Status::Active => true,
+            // Transactions made before lock may still be disputed
+            Status::Locked { .. } => access == Access::Dispute,
+            Status::Frozen { .. } => access != Access::Send,
+            Status::Closed { .. } => false,
+        }
+    }
+
+    /// Name of status used in report
+    pub fn name(&self) -> &'static str {
+        match self {
+            Status::Active => "active",
+            Status::Locked { .. } => "locked",
+            Status::Frozen { .. } => "frozen",
+            Status::Closed { .. } => "closed",
+        }
+    }
+
+    /// Why account was restricted
+    pub fn reason(&self) -> Option<&str> {
+        match self {
+            Status::Active => None,
+            Status::Locked { reason, .. } => Some(reason),
+            Status::Frozen { reason } | Status::Closed { reason } => reason.as_deref(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct Account {
-    pub available: Currency,
+    pub available: Balance,
     pub held: Currency,
-    pub locked: bool,
+    pub status: Status,
+}
+
+impl Account {
+    /// Ensure status of account allows given kind of operation
+    pub fn ensure_allows(&self, access: Access, client: u16) -> Result<(), EngineError> {
+        if self.status.allows(access) {
+            return Ok(());
+        }
+
+        match self.status {
+            Status::Active => Ok(()),
+            Status::Locked { .. } => Err(EngineError::AccountLocked(client)),
+            Status::Frozen { .. } => Err(EngineError::AccountFrozen(client)),
+            Status::Closed { .. } => Err(EngineError::AccountClosed(client)),
+        }
+    }
+
+    pub fn is_locked(&self) -> bool {
+        matches!(self.status, Status::Locked { .. })
+    }
+
+    /// Sum of available and held funds
+    ///
+    /// Engine refuses any operation which would make it out of range, so it is
+    /// always given for accounts of engine.
+    pub fn total(&self) -> Option<Balance> {
+        let mut total = self.available;
+        total.add(self.held).ok()?;
+        Some(total)
+    }
+
+    /// Ensure total is in range after funds were given to account
+    pub fn ensure_total_fits(&self) -> Result<(), CurrencyError> {
+        let mut total = self.available;
+        total.add(self.held)
+    }
+
+    /// Check what has to be true after any operation
+    pub fn invariants_hold(&self, allow_negative: bool) -> bool {
+        let total_fits = self.total().is_some();
+        let available_allowed = allow_negative || !self.available.is_negative();
+        let closed_empty = match self.status {
+            Status::Closed { .. } => {
+                self.available == Balance::zero() && self.held == Currency::zero()
+            }
+            _ => true,
+        };
+
+        total_fits && available_allowed && closed_empty
+    }
+
+    /// Available funds went below zero, e.g. disputed funds were already withdrawn
+    pub fn is_overdrawn(&self) -> bool {
+        self.available.is_negative()
+    }
 }
 
 impl Default for Account {
     fn default() -> Self {
         Self {
-            available: Currency::new(0, 0).unwrap(),
-            held: Currency::new(0, 0).unwrap(),
-            locked: false,
+            available: Balance::zero(),
+            held: Currency::zero(),
+            status: Status::Active,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_policy() {
+        let locked = Status::Locked {
+            reason: String::from("chargeback"),
+            tx: 1,
+        };
+        let frozen = Status::Frozen { reason: None };
+        let closed = Status::Closed { reason: None };
+
+        assert!(Status::Active.allows(Access::Send));
+        assert!(!locked.allows(Access::Receive));
+        assert!(locked.allows(Access::Dispute));
+        assert!(frozen.allows(Access::Receive));
+        assert!(!frozen.allows(Access::Send));
+        assert!(!closed.allows(Access::Dispute));
+    }
+
+    #[test]
+    fn invariants() {
+        let mut account = Account::default();
+        assert!(account.invariants_hold(false));
+        account
+            .available
+            .substract_allow_negative(Currency::new(1, 0).unwrap())
+            .unwrap();
+        assert!(!account.invariants_hold(false));
+        assert!(account.invariants_hold(true));
+        account.status = Status::Closed { reason: None };
+        assert!(!account.invariants_hold(true));
+    }
+}
@@ -0,0 +1,32 @@
+//! Operations of administrators on accounts
+
+// Crate paths
+use crate::api::asset::Asset;
+
+// External paths
+use serde::Serialize;
+
+/// What administrator does with account
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AdminAction {
+    /// Stop giving and taking funds
+    Lock,
+    /// Let account be used again after lock or freeze
+    Unlock,
+    /// Stop taking funds, they may still be given
+    Freeze,
+    /// Stop using empty account for good
+    Close,
+}
+
+/// Administrative operation kept in history
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AdminRecord {
+    pub client: u16,
+    /// Account of single asset, all accounts of client if `None`
+    pub asset: Option<Asset>,
+    pub tx: u32,
+    pub action: AdminAction,
+    pub reason: Option<String>,
+}
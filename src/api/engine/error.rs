@@ -4,13 +4,22 @@
 use thiserror::Error;
 
 // Crate paths
+use crate::api::asset::Asset;
+use crate::api::currency::balance::Balance;
 use crate::api::currency::error::CurrencyError;
 use crate::api::currency::Currency;
+use crate::api::engine::limits::Window;
 
 #[derive(Error, Debug, PartialEq)]
 pub enum EngineError {
     #[error("cannot operate as client: {0} account is locked")]
     AccountLocked(u16),
+    #[error("cannot take funds as client: {0} account is frozen")]
+    AccountFrozen(u16),
+    #[error("cannot operate as client: {0} account is closed")]
+    AccountClosed(u16),
+    #[error("cannot close account of client: {0} as it still has funds")]
+    CannotCloseAccountWithFunds(u16),
     #[error("account for client: {0} does not exist")]
     AccountDoesNotExist(u16),
     #[error("cannot find account: {0}")]
@@ -31,7 +40,7 @@ pub enum EngineError {
         client: u16,
         tx: u32,
         amount: Currency,
-        available: Currency,
+        available: Balance,
         held: Currency,
         source: CurrencyError,
     },
@@ -52,10 +61,117 @@ pub enum EngineError {
     DisputeCannotAddHeld { source: CurrencyError },
     #[error("transaction was not disputed: {0}")]
     TransactionNotDisputed(u32),
+    #[error("administrative operation cannot be disputed: {0}")]
+    TransactionNotDisputable(u32),
     #[error("cannot substract available funds: {source:?} to resolve")]
     ResolveCannotAddAvailable { source: CurrencyError },
     #[error("cannot add held funds: {source:?} to resolve")]
     ResolveCannotSubstractHeld { source: CurrencyError },
     #[error("cannot add held funds: {source:?} to chargeback")]
     ChargebackCannotSubstractHeld { source: CurrencyError },
+    #[error("cannot return available funds: {source:?} to chargeback")]
+    ChargebackCannotAddAvailable { source: CurrencyError },
+    #[error("client: {client} is not party of transaction: {tx}")]
+    NotPartyOfTransaction { client: u16, tx: u32 },
+    #[error("cannot transfer to the same client: {0}")]
+    TransferToItself(u32),
+    #[error("cannot exchange into the same asset: {0}")]
+    ExchangeSameAsset(u32),
+    #[error("cannot find rate of exchange from: {from} to: {to}")]
+    CannotFindRate { from: Asset, to: Asset },
+    #[error("cannot exchange: transaction: {tx:?}, amount: {amount:?} as it is out of range")]
+    ExchangeOutOfRange { tx: u32, amount: Currency },
+    #[error("cannot exchange: transaction: {tx:?}, amount: {amount:?} as nothing is left after conversion")]
+    ExchangeToZero { tx: u32, amount: Currency },
+    #[error("fee: {fee:?} is bigger than amount: {amount:?} of transaction: {tx:?}")]
+    FeeExceedsAmount {
+        tx: u32,
+        amount: Currency,
+        fee: Currency,
+    },
+    #[error("cannot calculate fee: transaction: {tx:?}, amount: {amount:?} as it is out of range")]
+    FeeOutOfRange { tx: u32, amount: Currency },
+    #[error("cannot refund fee: {source:?} to dispute")]
+    CannotRefundFee { source: CurrencyError },
+    #[error("cannot charge fee: {source:?} to resolve")]
+    CannotChargeFee { source: CurrencyError },
+    #[error("cannot withdrawal: client: {client:?}, transaction: {tx:?}, amount: {amount:?} above limit: {limit:?}")]
+    WithdrawalAboveLimit {
+        client: u16,
+        tx: u32,
+        amount: Currency,
+        limit: Currency,
+    },
+    #[error("cannot deposit: client: {client:?}, transaction: {tx:?}, amount: {amount:?} above limit: {limit:?}")]
+    DepositAboveLimit {
+        client: u16,
+        tx: u32,
+        amount: Currency,
+        limit: Currency,
+    },
+    #[error("cannot withdrawal: client: {client:?}, transaction: {tx:?}, amount: {amount:?} exceeds cap: {cap:?} within: {window}")]
+    WithdrawalCapExceeded {
+        client: u16,
+        tx: u32,
+        amount: Currency,
+        cap: Currency,
+        window: Window,
+    },
+    #[error("too many operations: client: {client:?}, transaction: {tx:?}, more than: {count} within: {window}")]
+    TooManyOperations {
+        client: u16,
+        tx: u32,
+        count: u64,
+        window: Window,
+    },
+    #[error("limit needs timestamp column to measure window: transaction: {0}")]
+    LimitNeedsTimestamp(u32),
+}
+
+impl EngineError {
+    /// Short name of error, same as name of its variant, e.g. AccountLocked
+    pub fn code(&self) -> &'static str {
+        match self {
+            EngineError::AccountLocked(..) => "AccountLocked",
+            EngineError::AccountFrozen(..) => "AccountFrozen",
+            EngineError::AccountClosed(..) => "AccountClosed",
+            EngineError::CannotCloseAccountWithFunds(..) => "CannotCloseAccountWithFunds",
+            EngineError::AccountDoesNotExist(..) => "AccountDoesNotExist",
+            EngineError::CannotFindAccount(..) => "CannotFindAccount",
+            EngineError::TransactionNotUnique(..) => "TransactionNotUnique",
+            EngineError::CannotFindTransaction(..) => "CannotFindTransaction",
+            EngineError::CannotDeposit { .. } => "CannotDeposit",
+            EngineError::CannotDepositTotalExceededMaxLimit { .. } => {
+                "CannotDepositTotalExceededMaxLimit"
+            }
+            EngineError::DepositTryAgain(..) => "DepositTryAgain",
+            EngineError::CannotWithdrawal { .. } => "CannotWithdrawal",
+            EngineError::DisputeAlreadyDisputed(..) => "DisputeAlreadyDisputed",
+            EngineError::DisputeCannotSubstractAvailable { .. } => {
+                "DisputeCannotSubstractAvailable"
+            }
+            EngineError::DisputeCannotAddHeld { .. } => "DisputeCannotAddHeld",
+            EngineError::TransactionNotDisputed(..) => "TransactionNotDisputed",
+            EngineError::TransactionNotDisputable(..) => "TransactionNotDisputable",
+            EngineError::ResolveCannotAddAvailable { .. } => "ResolveCannotAddAvailable",
+            EngineError::ResolveCannotSubstractHeld { .. } => "ResolveCannotSubstractHeld",
+            EngineError::ChargebackCannotSubstractHeld { .. } => "ChargebackCannotSubstractHeld",
+            EngineError::ChargebackCannotAddAvailable { .. } => "ChargebackCannotAddAvailable",
+            EngineError::NotPartyOfTransaction { .. } => "NotPartyOfTransaction",
+            EngineError::TransferToItself(..) => "TransferToItself",
+            EngineError::ExchangeSameAsset(..) => "ExchangeSameAsset",
+            EngineError::CannotFindRate { .. } => "CannotFindRate",
+            EngineError::ExchangeOutOfRange { .. } => "ExchangeOutOfRange",
+            EngineError::ExchangeToZero { .. } => "ExchangeToZero",
+            EngineError::FeeExceedsAmount { .. } => "FeeExceedsAmount",
+            EngineError::FeeOutOfRange { .. } => "FeeOutOfRange",
+            EngineError::CannotRefundFee { .. } => "CannotRefundFee",
+            EngineError::CannotChargeFee { .. } => "CannotChargeFee",
+            EngineError::WithdrawalAboveLimit { .. } => "WithdrawalAboveLimit",
+            EngineError::DepositAboveLimit { .. } => "DepositAboveLimit",
+            EngineError::WithdrawalCapExceeded { .. } => "WithdrawalCapExceeded",
+            EngineError::TooManyOperations { .. } => "TooManyOperations",
+            EngineError::LimitNeedsTimestamp(..) => "LimitNeedsTimestamp",
+        }
+    }
 }
@@ -0,0 +1,152 @@
+//! Fees charged by engine for operations
+
+// External paths
+use serde::Deserialize;
+
+// Crate paths
+use crate::api::currency::rounding::RoundingMode;
+use crate::api::currency::Currency;
+use crate::api::currency::Decimal;
+use crate::api::engine::operation::Operation;
+
+/// Percent of amount taken as fee, e.g. `1.5` for 1.5%
+pub type Percent = Decimal<u64, 6>;
+
+/// Fee of single tier, flat part is added to percent of amount
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeeRule {
+    pub operation: Operation,
+    /// Client whom rule applies to, `None` for any client without own rules
+    pub client: Option<u16>,
+    /// Lowest amount of tier, rule with highest matching one is used
+    pub from: Currency,
+    pub flat: Currency,
+    pub percent: Percent,
+}
+
+/// Fees of all operations and account which collects them
+#[derive(Debug, Clone)]
+pub struct FeeSchedule {
+    /// Client whose account gets fees, in the same asset as operation
+    pub house: u16,
+    /// Rounding of percent part of fee
+    pub rounding: RoundingMode,
+    pub rules: Vec<FeeRule>,
+}
+
+impl FeeSchedule {
+    /// Fee of operation, `None` if it is out of range
+    ///
+    /// House account does not pay fees.
+    pub fn fee(&self, operation: Operation, client: u16, amount: Currency) -> Option<Currency> {
+        if client == self.house {
+            return Some(Currency::zero());
+        }
+
+        let applies = |rule: &&FeeRule| rule.operation == operation && rule.from <= amount;
+
+        // Own rules of client replace rules for any client
+        let own = self
+            .rules
+            .iter()
+            .filter(applies)
+            .any(|rule| rule.client == Some(client));
+        let owner = if own { Some(client) } else { None };
+
+        let rule = self
+            .rules
+            .iter()
+            .filter(applies)
+            .filter(|rule| rule.client == owner)
+            .max_by_key(|rule| rule.from);
+
+        match rule {
+            None => Some(Currency::zero()),
+            Some(rule) => amount
+                .checked_mul_percent(rule.percent, self.rounding)?
+                .checked_add(rule.flat),
+        }
+    }
+}
+
+/// Record of csv file with fees, missing values mean zero
+#[derive(Debug, Deserialize)]
+pub struct FeeRecord<'a> {
+    pub operation: Operation,
+    pub client: Option<u16>,
+    pub from: Option<&'a str>,
+    pub flat: Option<&'a str>,
+    pub percent: Option<&'a str>,
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::convert::TryFrom;
+
+    fn rule(
+        operation: Operation,
+        client: Option<u16>,
+        from: &str,
+        flat: &str,
+        percent: &str,
+    ) -> FeeRule {
+        FeeRule {
+            operation,
+            client,
+            from: Currency::try_from(from).unwrap(),
+            flat: Currency::try_from(flat).unwrap(),
+            percent: Percent::try_from(percent).unwrap(),
+        }
+    }
+
+    fn schedule() -> FeeSchedule {
+        FeeSchedule {
+            house: 0,
+            rounding: RoundingMode::HalfUp,
+            rules: vec![
+                rule(Operation::Withdrawal, None, "0", "0.5", "1"),
+                rule(Operation::Withdrawal, None, "1000", "0", "0.5"),
+                rule(Operation::Withdrawal, Some(7), "0", "0", "0"),
+            ],
+        }
+    }
+
+    #[test]
+    fn fee_of_tier() {
+        let schedule = schedule();
+        assert_eq!(
+            schedule.fee(Operation::Withdrawal, 1, Currency::new(100, 0).unwrap()),
+            Currency::new(1, 5000).ok()
+        );
+        assert_eq!(
+            schedule.fee(Operation::Withdrawal, 1, Currency::new(2000, 0).unwrap()),
+            Currency::new(10, 0).ok()
+        );
+    }
+
+    #[test]
+    fn fee_of_client() {
+        assert_eq!(
+            schedule().fee(Operation::Withdrawal, 7, Currency::new(100, 0).unwrap()),
+            Some(Currency::zero())
+        );
+    }
+
+    #[test]
+    fn no_fee_without_rule() {
+        assert_eq!(
+            schedule().fee(Operation::Deposit, 1, Currency::new(100, 0).unwrap()),
+            Some(Currency::zero())
+        );
+    }
+
+    #[test]
+    fn no_fee_of_house() {
+        assert_eq!(
+            schedule().fee(Operation::Withdrawal, 0, Currency::new(100, 0).unwrap()),
+            Some(Currency::zero())
+        );
+    }
+}
@@ -0,0 +1,134 @@
+//! Ledger of operations applied to accounts with running balances
+
+// Standard paths
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+
+// Crate paths
+use crate::api::asset::Asset;
+use crate::api::currency::balance::Balance;
+use crate::api::currency::Currency;
+use crate::api::engine::account::Account;
+use crate::api::engine::account::AccountId;
+use crate::api::engine::admin::AdminAction;
+
+// External paths
+use serde::Serialize;
+
+/// What changed account
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Event {
+    Deposit,
+    Withdrawal,
+    /// Fee given to or refunded from house account
+    Fee,
+    Exchange,
+    Transfer,
+    Dispute,
+    Resolve,
+    Chargeback,
+    /// Administrative operations, they change only status of account
+    Lock,
+    Unlock,
+    Freeze,
+    Close,
+}
+
+impl From<AdminAction> for Event {
+    fn from(action: AdminAction) -> Self {
+        match action {
+            AdminAction::Lock => Event::Lock,
+            AdminAction::Unlock => Event::Unlock,
+            AdminAction::Freeze => Event::Freeze,
+            AdminAction::Close => Event::Close,
+        }
+    }
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Event::Deposit => "deposit",
+            Event::Withdrawal => "withdrawal",
+            Event::Fee => "fee",
+            Event::Exchange => "exchange",
+            Event::Transfer => "transfer",
+            Event::Dispute => "dispute",
+            Event::Resolve => "resolve",
+            Event::Chargeback => "chargeback",
+            Event::Lock => "lock",
+            Event::Unlock => "unlock",
+            Event::Freeze => "freeze",
+            Event::Close => "close",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Single line of statement
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Entry {
+    pub client: u16,
+    pub asset: Asset,
+    pub tx: u32,
+    pub event: Event,
+    /// Change of total funds, `None` if it is out of range
+    pub amount: Option<Balance>,
+    /// Funds after operation
+    pub available: Balance,
+    pub held: Currency,
+    /// Why operation changed status of account, e.g. reason of lock
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl Entry {
+    /// Describe change of account from `before` to `after` state
+    pub fn new(id: &AccountId, tx: u32, event: Event, before: &Account, after: &Account) -> Self {
+        let amount = after.total().and_then(|mut change| {
+            change.substract_signed(before.total()?).ok()?;
+            Some(change)
+        });
+
+        let reason = if before.status != after.status {
+            after.status.reason().map(str::to_string)
+        } else {
+            None
+        };
+
+        Self {
+            client: id.client,
+            asset: id.asset.clone(),
+            tx,
+            event,
+            amount,
+            available: after.available,
+            held: after.held,
+            reason,
+        }
+    }
+}
+
+/// Entries of each client in order they were applied
+#[derive(Debug, Default)]
+pub struct History {
+    clients: Mutex<HashMap<u16, Vec<Entry>>>,
+}
+
+impl History {
+    /// Append entry, it has to be called while account is still locked to keep order
+    pub fn record(&self, entry: Entry) {
+        // Panic if lock is poisoned
+        let mut clients_lock = self.clients.lock().unwrap();
+        clients_lock.entry(entry.client).or_default().push(entry);
+    }
+
+    /// Entries of all accounts of client
+    pub fn statement(&self, client: u16) -> Vec<Entry> {
+        // Panic if lock is poisoned
+        let clients_lock = self.clients.lock().unwrap();
+        clients_lock.get(&client).cloned().unwrap_or_default()
+    }
+}
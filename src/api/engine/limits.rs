@@ -0,0 +1,497 @@
+//! Rules which limit operations on accounts before engine applies them
+
+// Standard paths
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+// Crate paths
+use crate::api::currency::Currency;
+use crate::api::engine::account::AccountId;
+use crate::api::engine::error::EngineError;
+use crate::api::engine::operation::Operation;
+
+// External paths
+use serde::Deserialize;
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Period which caps and counts of operations are measured in
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Window {
+    /// Last given number of records including current one
+    Records(u64),
+    /// Last given number of seconds including current one by timestamp column
+    Seconds(u64),
+    /// Calendar day in UTC by timestamp column
+    Day,
+}
+
+impl FromStr for Window {
+    type Err = String;
+
+    /// Read `day`, number of seconds e.g. `3600s` or number of records e.g. `100`
+    fn from_str(input: &str) -> Result<Self, String> {
+        let number = |digits: &str| {
+            digits
+                .parse()
+                .map_err(|err| format!("cannot parse window: {}, reason: {}", input, err))
+        };
+
+        match input {
+            "day" => Ok(Window::Day),
+            _ => match input.strip_suffix('s') {
+                Some(seconds) => number(seconds).map(Window::Seconds),
+                None => number(input).map(Window::Records),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Window {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Window::Records(records) => write!(f, "{} records", records),
+            Window::Seconds(seconds) => write!(f, "{} seconds", seconds),
+            Window::Day => write!(f, "day"),
+        }
+    }
+}
+
+/// Single restriction of operations
+#[derive(Debug, Clone, PartialEq)]
+pub enum Limit {
+    /// Max amount of single withdrawal
+    MaxWithdrawal(Currency),
+    /// Max amount of single deposit
+    MaxDeposit(Currency),
+    /// Max sum of withdrawals within window
+    WithdrawalCap { amount: Currency, window: Window },
+    /// Max number of operations within window
+    MaxOperations { count: u64, window: Window },
+}
+
+impl Limit {
+    /// Rules of the same kind given for client replace rules for any client
+    fn kind(&self) -> u8 {
+        match self {
+            Limit::MaxWithdrawal(_) => 0,
+            Limit::MaxDeposit(_) => 1,
+            Limit::WithdrawalCap { .. } => 2,
+            Limit::MaxOperations { .. } => 3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LimitRule {
+    /// Client whom rule applies to, `None` for any client without own rules
+    pub client: Option<u16>,
+    pub limit: Limit,
+}
+
+/// Record of csv file with limits
+#[derive(Debug, Deserialize)]
+pub struct LimitRecord<'a> {
+    pub rule: &'a str,
+    pub client: Option<u16>,
+    pub amount: Option<&'a str>,
+    pub count: Option<u64>,
+    pub window: Option<&'a str>,
+}
+
+/// When operation happens
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Moment {
+    /// Number of record in input
+    pub record: u64,
+    /// Seconds since unix epoch if input has timestamp column
+    pub timestamp: Option<u64>,
+}
+
+impl Moment {
+    /// Whether `earlier` moment is still within window ending at this moment
+    fn within(&self, earlier: &Moment, window: Window, tx: u32) -> Result<bool, EngineError> {
+        match window {
+            Window::Records(records) => Ok(self.record.saturating_sub(earlier.record) < records),
+            Window::Seconds(seconds) => {
+                let (now, then) = self.timestamps(earlier, tx)?;
+                Ok(now.saturating_sub(then) < seconds)
+            }
+            Window::Day => {
+                let (now, then) = self.timestamps(earlier, tx)?;
+                Ok(now / SECONDS_PER_DAY == then / SECONDS_PER_DAY)
+            }
+        }
+    }
+
+    fn timestamps(&self, earlier: &Moment, tx: u32) -> Result<(u64, u64), EngineError> {
+        match (self.timestamp, earlier.timestamp) {
+            (Some(now), Some(then)) => Ok((now, then)),
+            _ => Err(EngineError::LimitNeedsTimestamp(tx)),
+        }
+    }
+}
+
+/// Operation already applied, kept to measure windows
+#[derive(Debug, Clone)]
+struct Event {
+    moment: Moment,
+    operation: Operation,
+    amount: Currency,
+}
+
+/// Checks rules before operations and keeps history of applied ones
+#[derive(Debug, Default)]
+pub struct Limiter {
+    rules: Vec<LimitRule>,
+    /// History of each account has own lock, so only the same account is serialized
+    history: Mutex<HashMap<AccountId, Arc<Mutex<VecDeque<Event>>>>>,
+}
+
+impl Limiter {
+    pub fn new(rules: Vec<LimitRule>) -> Self {
+        Self {
+            rules,
+            history: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Run `apply` if operation passes all rules, remember it if it succeeds
+    ///
+    /// Lock of history of account is kept meanwhile, so parallel operations
+    /// cannot pass the same cap together. Other accounts are not blocked.
+    pub fn guard<F>(
+        &self,
+        id: &AccountId,
+        operation: Operation,
+        tx: u32,
+        amount: Currency,
+        moment: Moment,
+        apply: F,
+    ) -> Result<(), EngineError>
+    where
+        F: FnOnce() -> Result<(), EngineError>,
+    {
+        if self.rules.is_empty() {
+            return apply();
+        }
+
+        let events = self.events(id);
+        // Panic if lock is poisoned
+        let mut events = events.lock().unwrap();
+        let events = &mut *events;
+
+        for limit in self.limits(id.client) {
+            check(limit, events, id.client, operation, tx, amount, &moment)?;
+        }
+
+        apply()?;
+
+        events.push_back(Event {
+            moment,
+            operation,
+            amount,
+        });
+        self.forget_old(events, &moment);
+
+        Ok(())
+    }
+
+    /// History of account, map is locked only to find it
+    fn events(&self, id: &AccountId) -> Arc<Mutex<VecDeque<Event>>> {
+        // Panic if lock is poisoned
+        let mut history_lock = self.history.lock().unwrap();
+        Arc::clone(history_lock.entry(id.clone()).or_default())
+    }
+
+    /// Rules which apply to client
+    fn limits(&self, client: u16) -> Vec<&Limit> {
+        let own = |kind: u8| {
+            self.rules
+                .iter()
+                .any(|rule| rule.client == Some(client) && rule.limit.kind() == kind)
+        };
+
+        self.rules
+            .iter()
+            .filter(|rule| match rule.client {
+                Some(owner) => owner == client,
+                None => !own(rule.limit.kind()),
+            })
+            .map(|rule| &rule.limit)
+            .collect()
+    }
+
+    /// Drop events which are out of any window
+    fn forget_old(&self, events: &mut VecDeque<Event>, now: &Moment) {
+        let windows: Vec<Window> = self
+            .rules
+            .iter()
+            .filter_map(|rule| match rule.limit {
+                Limit::WithdrawalCap { window, .. } | Limit::MaxOperations { window, .. } => {
+                    Some(window)
+                }
+                _ => None,
+            })
+            .collect();
+
+        while let Some(oldest) = events.front() {
+            let needed = windows
+                .iter()
+                // Keep event if window cannot be measured
+                .any(|window| now.within(&oldest.moment, *window, 0).unwrap_or(true));
+            if needed {
+                break;
+            }
+            events.pop_front();
+        }
+    }
+}
+
+fn check(
+    limit: &Limit,
+    events: &VecDeque<Event>,
+    client: u16,
+    operation: Operation,
+    tx: u32,
+    amount: Currency,
+    moment: &Moment,
+) -> Result<(), EngineError> {
+    match (limit, operation) {
+        (Limit::MaxWithdrawal(max), Operation::Withdrawal) if amount > *max => {
+            Err(EngineError::WithdrawalAboveLimit {
+                client,
+                tx,
+                amount,
+                limit: *max,
+            })
+        }
+        (Limit::MaxDeposit(max), Operation::Deposit) if amount > *max => {
+            Err(EngineError::DepositAboveLimit {
+                client,
+                tx,
+                amount,
+                limit: *max,
+            })
+        }
+        (
+            Limit::WithdrawalCap {
+                amount: cap,
+                window,
+            },
+            Operation::Withdrawal,
+        ) => {
+            let mut withdrawn = amount;
+            for event in events {
+                if event.operation == Operation::Withdrawal
+                    && moment.within(&event.moment, *window, tx)?
+                {
+                    withdrawn = withdrawn
+                        .checked_add(event.amount)
+                        .unwrap_or_else(Currency::max);
+                }
+            }
+            if withdrawn > *cap {
+                return Err(EngineError::WithdrawalCapExceeded {
+                    client,
+                    tx,
+                    amount,
+                    cap: *cap,
+                    window: *window,
+                });
+            }
+            Ok(())
+        }
+        (Limit::MaxOperations { count, window }, _) => {
+            // Current operation is counted as well
+            let mut done = 1;
+            for event in events {
+                if moment.within(&event.moment, *window, tx)? {
+                    done += 1;
+                }
+            }
+            if done > *count {
+                return Err(EngineError::TooManyOperations {
+                    client,
+                    tx,
+                    count: *count,
+                    window: *window,
+                });
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::api::asset::Asset;
+    use assert_matches::assert_matches;
+
+    fn at(record: u64, timestamp: Option<u64>) -> Moment {
+        Moment { record, timestamp }
+    }
+
+    fn amount(decimal: u64) -> Currency {
+        Currency::new(decimal, 0).unwrap()
+    }
+
+    fn withdraw(
+        limiter: &Limiter,
+        client: u16,
+        tx: u32,
+        value: u64,
+        moment: Moment,
+    ) -> Result<(), EngineError> {
+        let id = AccountId::new(client, &Asset::default());
+        limiter.guard(
+            &id,
+            Operation::Withdrawal,
+            tx,
+            amount(value),
+            moment,
+            || Ok(()),
+        )
+    }
+
+    #[test]
+    fn other_account_is_not_blocked() {
+        let limiter = Limiter::new(vec![LimitRule {
+            client: None,
+            limit: Limit::MaxWithdrawal(amount(100)),
+        }]);
+        let id = AccountId::new(1, &Asset::default());
+
+        // Operation of other account finishes while first one is in progress
+        let result = limiter.guard(
+            &id,
+            Operation::Withdrawal,
+            1,
+            amount(1),
+            at(1, None),
+            || {
+                std::thread::scope(|scope| {
+                    scope
+                        .spawn(|| withdraw(&limiter, 2, 2, 1, at(2, None)))
+                        .join()
+                        .unwrap()
+                })
+            },
+        );
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn parse_window() {
+        assert_eq!("day".parse(), Ok(Window::Day));
+        assert_eq!("60s".parse(), Ok(Window::Seconds(60)));
+        assert_eq!("10".parse(), Ok(Window::Records(10)));
+        assert!("week".parse::<Window>().is_err());
+    }
+
+    #[test]
+    fn max_withdrawal() {
+        let limiter = Limiter::new(vec![LimitRule {
+            client: None,
+            limit: Limit::MaxWithdrawal(amount(100)),
+        }]);
+        assert!(withdraw(&limiter, 1, 1, 100, at(1, None)).is_ok());
+        assert_matches!(
+            withdraw(&limiter, 1, 2, 101, at(2, None)),
+            Err(EngineError::WithdrawalAboveLimit { .. })
+        );
+    }
+
+    #[test]
+    fn client_rule_replaces_general_one() {
+        let limiter = Limiter::new(vec![
+            LimitRule {
+                client: None,
+                limit: Limit::MaxWithdrawal(amount(100)),
+            },
+            LimitRule {
+                client: Some(2),
+                limit: Limit::MaxWithdrawal(amount(1000)),
+            },
+        ]);
+        assert!(withdraw(&limiter, 2, 1, 500, at(1, None)).is_ok());
+        assert!(withdraw(&limiter, 1, 2, 500, at(2, None)).is_err());
+    }
+
+    #[test]
+    fn daily_withdrawal_cap() {
+        let limiter = Limiter::new(vec![LimitRule {
+            client: None,
+            limit: Limit::WithdrawalCap {
+                amount: amount(100),
+                window: Window::Day,
+            },
+        }]);
+        assert!(withdraw(&limiter, 1, 1, 60, at(1, Some(10))).is_ok());
+        assert_matches!(
+            withdraw(&limiter, 1, 2, 50, at(2, Some(20))),
+            Err(EngineError::WithdrawalCapExceeded { .. })
+        );
+        assert!(withdraw(&limiter, 2, 3, 50, at(3, Some(30))).is_ok());
+        assert!(withdraw(&limiter, 1, 4, 50, at(4, Some(SECONDS_PER_DAY))).is_ok());
+    }
+
+    #[test]
+    fn rolling_cap_needs_timestamp() {
+        let limiter = Limiter::new(vec![LimitRule {
+            client: None,
+            limit: Limit::WithdrawalCap {
+                amount: amount(100),
+                window: Window::Seconds(60),
+            },
+        }]);
+        assert!(withdraw(&limiter, 1, 1, 60, at(1, None)).is_ok());
+        assert_matches!(
+            withdraw(&limiter, 1, 2, 10, at(2, None)),
+            Err(EngineError::LimitNeedsTimestamp(2))
+        );
+    }
+
+    #[test]
+    fn max_operations_by_records() {
+        let limiter = Limiter::new(vec![LimitRule {
+            client: None,
+            limit: Limit::MaxOperations {
+                count: 2,
+                window: Window::Records(3),
+            },
+        }]);
+        assert!(withdraw(&limiter, 1, 1, 1, at(1, None)).is_ok());
+        assert!(withdraw(&limiter, 1, 2, 1, at(2, None)).is_ok());
+        assert_matches!(
+            withdraw(&limiter, 1, 3, 1, at(3, None)),
+            Err(EngineError::TooManyOperations { .. })
+        );
+        assert!(withdraw(&limiter, 1, 4, 1, at(4, None)).is_ok());
+    }
+
+    #[test]
+    fn failed_operation_is_not_counted() {
+        let limiter = Limiter::new(vec![LimitRule {
+            client: None,
+            limit: Limit::MaxOperations {
+                count: 1,
+                window: Window::Records(10),
+            },
+        }]);
+        let id = AccountId::new(1, &Asset::default());
+        assert!(limiter
+            .guard(&id, Operation::Deposit, 1, amount(1), at(1, None), || {
+                Err(EngineError::AccountLocked(1))
+            })
+            .is_err());
+        assert!(withdraw(&limiter, 1, 2, 1, at(2, None)).is_ok());
+    }
+}
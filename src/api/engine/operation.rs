@@ -0,0 +1,10 @@
+// External paths
+use serde::Deserialize;
+
+/// Operation which fees and limits apply to
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Operation {
+    Deposit,
+    Withdrawal,
+}
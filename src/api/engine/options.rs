@@ -0,0 +1,20 @@
+// Crate paths
+use crate::api::engine::fees::FeeSchedule;
+use crate::api::engine::rates::RateTable;
+
+/// Behaviour of engine which may differ between deployments
+#[derive(Debug, Clone, Default)]
+pub struct EngineOptions {
+    /// Let dispute hold funds even when they are not available anymore
+    ///
+    /// Available funds of account go below zero then and account is
+    /// reported as overdrawn.
+    pub allow_negative_available: bool,
+
+    /// Rates used by exchange transactions
+    pub rates: RateTable,
+    /// Fees of deposits and withdrawals, none are charged without it
+    pub fees: Option<FeeSchedule>,
+    /// Keep ledger of applied operations to print statements of clients
+    pub keep_history: bool,
+}
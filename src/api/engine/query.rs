@@ -0,0 +1,222 @@
+//! Read access to state of engine
+//!
+//! Queries return owned copies, so callers never hold locks of engine.
+
+// Standard paths
+use std::sync::atomic::Ordering;
+
+// Crate paths
+use crate::api::asset::Asset;
+use crate::api::currency::Currency;
+use crate::api::engine::account::Account;
+use crate::api::engine::account::AccountId;
+use crate::api::engine::Engine;
+use crate::api::engine::Recorded;
+
+// External paths
+use serde::Serialize;
+
+/// Copy of recorded transaction together with its dispute state
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TransactionSnapshot {
+    pub tx: u32,
+    pub recorded: Recorded,
+    pub disputed: bool,
+}
+
+impl Engine {
+    /// Copy of account of default asset
+    pub fn get_account(&self, client: u16) -> Option<Account> {
+        self.get_account_asset(client, &Asset::default())
+    }
+
+    /// Copy of account of given asset
+    pub fn get_account_asset(&self, client: u16, asset: &Asset) -> Option<Account> {
+        // Panic if lock is poisoned
+        let accounts_lock_read = self.accounts.read().unwrap();
+
+        accounts_lock_read
+            .get(&AccountId::new(client, asset))
+            // Panic if mutex is poisoned
+            .map(|mutex| mutex.lock().unwrap().clone())
+    }
+
+    pub fn get_transaction(&self, tx: u32) -> Option<TransactionSnapshot> {
+        let recorded = self.find_transaction(tx).ok()?;
+
+        // Panic if lock is poisoned
+        let disputed = self.transactions_disputed.read().unwrap().contains(&tx);
+
+        Some(TransactionSnapshot {
+            tx,
+            recorded,
+            disputed,
+        })
+    }
+
+    /// Copies of all accounts in order of ids
+    ///
+    /// Each account is copied separately, operations applied meanwhile may be
+    /// seen only on some of them. Use `snapshot` for consistent view.
+    pub fn accounts(&self) -> impl Iterator<Item = (AccountId, Account)> {
+        let mut accounts: Vec<(AccountId, Account)> = {
+            // Panic if lock is poisoned
+            let accounts_lock_read = self.accounts.read().unwrap();
+
+            accounts_lock_read
+                .iter()
+                // Panic if mutex is poisoned
+                .map(|(id, mutex)| (id.clone(), mutex.lock().unwrap().clone()))
+                .collect()
+        };
+        accounts.sort_unstable_by(|(first, _), (second, _)| first.cmp(second));
+
+        accounts.into_iter()
+    }
+
+    /// Transactions which are disputed now in ascending order
+    pub fn disputed_transactions(&self) -> impl Iterator<Item = u32> {
+        let mut disputed: Vec<u32> = {
+            // Panic if lock is poisoned
+            let transactions_disputed_lock_read = self.transactions_disputed.read().unwrap();
+
+            transactions_disputed_lock_read.iter().copied().collect()
+        };
+        disputed.sort_unstable();
+
+        disputed.into_iter()
+    }
+
+    /// Number of finished operations, rejected ones included
+    pub fn operations(&self) -> u64 {
+        self.version.load(Ordering::Relaxed)
+    }
+
+    /// Sum of funds held on accounts of given asset, `None` if it is out of range
+    ///
+    /// Accounts are read one by one without waiting for reconcile, so the sum
+    /// may include only part of operations applied meanwhile. Use `snapshot`
+    /// when it has to match other values.
+    pub fn sum_held(&self, asset: &Asset) -> Option<Currency> {
+        // Panic if lock is poisoned
+        let accounts_lock_read = self.accounts.read().unwrap();
+
+        accounts_lock_read
+            .iter()
+            .filter(|(id, _)| &id.asset == asset)
+            // Panic if mutex is poisoned
+            .try_fold(Currency::zero(), |sum, (_, mutex)| {
+                sum.checked_add(mutex.lock().unwrap().held)
+            })
+    }
+
+    /// Number of locked accounts
+    ///
+    /// Like `sum_held` it does not wait for reconcile and may see operations
+    /// applied meanwhile only partially.
+    pub fn count_locked(&self) -> usize {
+        // Panic if lock is poisoned
+        let accounts_lock_read = self.accounts.read().unwrap();
+
+        accounts_lock_read
+            .values()
+            // Panic if mutex is poisoned
+            .filter(|mutex| mutex.lock().unwrap().is_locked())
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::engine::Leg;
+
+    #[test]
+    fn get_missing_account() {
+        let engine = Engine::new();
+        assert_eq!(engine.get_account(1).map(|account| account.held), None);
+    }
+
+    #[test]
+    fn get_transaction_with_dispute_state() {
+        let engine = Engine::new();
+        let amount = Currency::new(10, 0).unwrap();
+        assert!(engine.deposit(1, 1, amount).is_ok());
+        assert!(engine.get_transaction(2).is_none());
+        assert!(!engine.get_transaction(1).unwrap().disputed);
+        assert!(engine.dispute(1, 1).is_ok());
+        assert_eq!(
+            engine.get_transaction(1),
+            Some(TransactionSnapshot {
+                tx: 1,
+                recorded: Recorded::Single {
+                    asset: Asset::default(),
+                    amount,
+                    fee: None
+                },
+                disputed: true,
+            })
+        );
+    }
+
+    #[test]
+    fn transfer_recorded_as_movement() {
+        let engine = Engine::new();
+        let asset = Asset::default();
+        let amount = Currency::new(10, 0).unwrap();
+        assert!(engine.deposit(1, 1, amount).is_ok());
+        assert!(engine.transfer(1, 2, &asset, 2, amount).is_ok());
+        assert_eq!(
+            engine.get_transaction(2).unwrap().recorded,
+            Recorded::Movement {
+                from: Leg {
+                    account: AccountId::new(1, &asset),
+                    amount
+                },
+                to: Leg {
+                    account: AccountId::new(2, &asset),
+                    amount
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn aggregates() {
+        let engine = Engine::new();
+        let usd = Asset::new("USD");
+        assert!(engine
+            .deposit_asset(3, &usd, 1, Currency::new(7, 0).unwrap())
+            .is_ok());
+        assert!(engine.deposit(2, 2, Currency::new(10, 0).unwrap()).is_ok());
+        assert!(engine.deposit(1, 3, Currency::new(5, 0).unwrap()).is_ok());
+        assert!(engine.dispute(2, 2).is_ok());
+        assert!(engine.dispute(1, 3).is_ok());
+        assert!(engine.chargeback(1, 3).is_ok());
+
+        let ids: Vec<u16> = engine.accounts().map(|(id, _)| id.client).collect();
+        assert_eq!(ids, vec![1, 2, 3]);
+        assert_eq!(engine.disputed_transactions().collect::<Vec<_>>(), vec![2]);
+        assert_eq!(
+            engine.sum_held(&Asset::default()),
+            Some(Currency::new(10, 0).unwrap())
+        );
+        assert_eq!(engine.sum_held(&usd), Some(Currency::zero()));
+        assert_eq!(engine.count_locked(), 1);
+    }
+
+    #[test]
+    fn aggregates_do_not_wait_for_reconcile() {
+        let engine = Engine::new();
+        assert!(engine.deposit(1, 1, Currency::new(10, 0).unwrap()).is_ok());
+        assert!(engine.dispute(1, 1).is_ok());
+
+        // Taken as reconcile would, aggregates must not wait for it
+        let _gate = engine.snapshot_gate.write().unwrap();
+        assert_eq!(
+            engine.sum_held(&Asset::default()),
+            Some(Currency::new(10, 0).unwrap())
+        );
+        assert_eq!(engine.count_locked(), 0);
+    }
+}
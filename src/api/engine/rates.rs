@@ -0,0 +1,45 @@
+//! Rates of exchange between assets
+
+// Standard paths
+use std::collections::HashMap;
+
+// External paths
+use serde::Deserialize;
+
+// Crate paths
+use crate::api::asset::Asset;
+use crate::api::currency::Decimal;
+
+/// Exchange rate, it needs more fractional digits than amounts
+pub type Rate = Decimal<u64, 8>;
+
+/// Rates used to exchange funds of client between assets
+///
+/// Each direction has its own rate, e.g. to let buy and sell prices differ.
+#[derive(Debug, Clone, Default)]
+pub struct RateTable {
+    rates: HashMap<(Asset, Asset), Rate>,
+}
+
+impl RateTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set rate to get `to` asset for one unit of `from` asset
+    pub fn insert(&mut self, from: Asset, to: Asset, rate: Rate) {
+        self.rates.insert((from, to), rate);
+    }
+
+    pub fn get(&self, from: &Asset, to: &Asset) -> Option<Rate> {
+        self.rates.get(&(from.clone(), to.clone())).copied()
+    }
+}
+
+/// Record of csv file with rates
+#[derive(Debug, Deserialize)]
+pub struct RateRecord<'a> {
+    pub from: &'a str,
+    pub to: &'a str,
+    pub rate: &'a str,
+}
@@ -0,0 +1,293 @@
+//! Check that funds are conserved across engine
+
+// Standard paths
+use std::collections::BTreeMap;
+use std::sync::atomic::Ordering;
+
+// Crate paths
+use crate::api::asset::Asset;
+use crate::api::currency::balance::WideBalance;
+use crate::api::currency::Currency;
+use crate::api::currency::WideCurrency;
+use crate::api::engine::Engine;
+use crate::api::engine::Recorded;
+
+/// Kind of funds entering or leaving asset
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Flow {
+    Deposited,
+    Withdrawn,
+    /// Given by exchange from other asset
+    ExchangedIn,
+    /// Taken by exchange into other asset
+    ExchangedOut,
+    /// Held funds removed by chargeback
+    ChargedBack,
+    /// Funds given back to sender by chargeback of exchange or transfer
+    Returned,
+    /// Fee moved from client to house account, it stays within asset
+    FeeCharged,
+    /// Fee given back to client for time of dispute
+    FeeRefunded,
+}
+
+/// Sums of funds which entered or left single asset
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Flows {
+    pub deposited: WideCurrency,
+    pub withdrawn: WideCurrency,
+    pub exchanged_in: WideCurrency,
+    pub exchanged_out: WideCurrency,
+    pub charged_back: WideCurrency,
+    pub returned: WideCurrency,
+    pub fees_charged: WideCurrency,
+    pub fees_refunded: WideCurrency,
+}
+
+impl Default for Flows {
+    fn default() -> Self {
+        Self {
+            deposited: WideCurrency::zero(),
+            withdrawn: WideCurrency::zero(),
+            exchanged_in: WideCurrency::zero(),
+            exchanged_out: WideCurrency::zero(),
+            charged_back: WideCurrency::zero(),
+            returned: WideCurrency::zero(),
+            fees_charged: WideCurrency::zero(),
+            fees_refunded: WideCurrency::zero(),
+        }
+    }
+}
+
+impl Flows {
+    pub fn add(&mut self, flow: Flow, amount: Currency) {
+        let sum = match flow {
+            Flow::Deposited => &mut self.deposited,
+            Flow::Withdrawn => &mut self.withdrawn,
+            Flow::ExchangedIn => &mut self.exchanged_in,
+            Flow::ExchangedOut => &mut self.exchanged_out,
+            Flow::ChargedBack => &mut self.charged_back,
+            Flow::Returned => &mut self.returned,
+            Flow::FeeCharged => &mut self.fees_charged,
+            Flow::FeeRefunded => &mut self.fees_refunded,
+        };
+        // Each amount fits in 64 bits and there are at most 2^32 transactions
+        // Should never panic unless logic is buggy
+        sum.add(amount.into()).unwrap();
+    }
+
+    /// Fees kept by house accounts now, `None` if more was refunded than charged
+    pub fn fees(&self) -> Option<WideCurrency> {
+        self.fees_charged.checked_sub(self.fees_refunded)
+    }
+
+    /// Funds which should be kept on accounts, `None` if it is out of range
+    pub fn expected(&self) -> Option<WideBalance> {
+        let mut expected = WideBalance::zero();
+        for income in [self.deposited, self.exchanged_in, self.returned] {
+            expected.add(income).ok()?;
+        }
+        for outcome in [self.withdrawn, self.exchanged_out, self.charged_back] {
+            expected.substract_allow_negative(outcome).ok()?;
+        }
+
+        Some(expected)
+    }
+}
+
+/// Reconciliation of single asset
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssetReconciliation {
+    pub asset: Asset,
+    pub flows: Flows,
+    /// Sum of available funds of all accounts
+    pub available: WideBalance,
+    /// Sum of held funds of all accounts
+    pub held: WideCurrency,
+    /// Sum of amounts of transactions disputed now
+    pub disputed: WideCurrency,
+}
+
+impl AssetReconciliation {
+    fn new(asset: Asset) -> Self {
+        Self {
+            asset,
+            flows: Flows::default(),
+            available: WideBalance::zero(),
+            held: WideCurrency::zero(),
+            disputed: WideCurrency::zero(),
+        }
+    }
+
+    /// Funds kept on accounts, `None` if it is out of range
+    pub fn total(&self) -> Option<WideBalance> {
+        let mut total = self.available;
+        total.add(self.held).ok()?;
+        Some(total)
+    }
+
+    /// Accounts keep what flows brought and held funds match disputes
+    pub fn is_balanced(&self) -> bool {
+        let total = self.total();
+        total.is_some() && total == self.flows.expected() && self.held == self.disputed
+    }
+}
+
+/// Reconciliation of all assets taken at single moment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reconciliation {
+    /// Number of operations finished before reconciliation
+    pub version: u64,
+    /// Ordered by assets
+    pub assets: Vec<AssetReconciliation>,
+}
+
+impl Reconciliation {
+    pub fn is_balanced(&self) -> bool {
+        self.assets.iter().all(AssetReconciliation::is_balanced)
+    }
+}
+
+impl Engine {
+    /// Count funds which entered or left asset, called while operation is in progress
+    pub(super) fn add_flow(&self, asset: &Asset, flow: Flow, amount: Currency) {
+        // Panic if lock is poisoned
+        let mut flows_lock = self.flows.lock().unwrap();
+        flows_lock
+            .entry(asset.clone())
+            .or_default()
+            .add(flow, amount);
+    }
+
+    /// Compare sums of accounts with funds which entered and left engine
+    ///
+    /// It is taken between operations as snapshot is.
+    pub fn reconcile(&self) -> Reconciliation {
+        // Panic if lock is poisoned
+        let _gate = self.snapshot_gate.write().unwrap();
+
+        let snapshot = self.snapshot();
+        let mut assets: BTreeMap<Asset, AssetReconciliation> = BTreeMap::new();
+
+        // Limit lock time
+        {
+            // Panic if lock is poisoned
+            let flows_lock = self.flows.lock().unwrap();
+            for (asset, flows) in flows_lock.iter() {
+                entry(&mut assets, asset).flows = *flows;
+            }
+        }
+
+        // Each amount fits in 64 bits and there are at most 2^48 accounts
+        // Should never panic unless logic is buggy
+        for (id, account) in snapshot.accounts() {
+            let reconciliation = entry(&mut assets, &id.asset);
+            reconciliation
+                .available
+                .add_signed(account.available.into())
+                .unwrap();
+            reconciliation.held.add(account.held.into()).unwrap();
+        }
+
+        // Limit lock time
+        {
+            // Panic if lock is poisoned
+            let transactions_lock_read = self.transactions.read().unwrap();
+            let transactions_disputed_lock_read = self.transactions_disputed.read().unwrap();
+
+            for tx in transactions_disputed_lock_read.iter() {
+                // Held funds are in asset of disputed leg
+                let (asset, amount) = match transactions_lock_read.get(tx) {
+                    Some(Recorded::Single { asset, amount, .. }) => (asset, *amount),
+                    Some(Recorded::Movement { to, .. }) => (&to.account.asset, to.amount),
+                    Some(Recorded::Admin(_)) | None => continue,
+                };
+                // Should never panic unless logic is buggy
+                entry(&mut assets, asset)
+                    .disputed
+                    .add(amount.into())
+                    .unwrap();
+            }
+        }
+
+        Reconciliation {
+            version: self.version.load(Ordering::Relaxed),
+            assets: assets.into_values().collect(),
+        }
+    }
+}
+
+fn entry<'a>(
+    assets: &'a mut BTreeMap<Asset, AssetReconciliation>,
+    asset: &Asset,
+) -> &'a mut AssetReconciliation {
+    assets
+        .entry(asset.clone())
+        .or_insert_with(|| AssetReconciliation::new(asset.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::engine::options::EngineOptions;
+    use crate::api::engine::rates::Rate;
+    use crate::api::engine::rates::RateTable;
+
+    // Standard paths
+    use std::convert::TryFrom;
+
+    #[test]
+    fn empty_engine_is_balanced() {
+        let reconciliation = Engine::new().reconcile();
+        assert!(reconciliation.assets.is_empty());
+        assert!(reconciliation.is_balanced());
+    }
+
+    #[test]
+    fn balanced_after_all_kinds_of_operations() {
+        let usd = Asset::new("USD");
+        let eur = Asset::new("EUR");
+        let mut rates = RateTable::new();
+        rates.insert(usd.clone(), eur.clone(), Rate::try_from("0.9").unwrap());
+        let engine = Engine::with_options(EngineOptions {
+            rates,
+            allow_negative_available: true,
+            ..EngineOptions::default()
+        });
+
+        let amount = |value| Currency::new(value, 0).unwrap();
+        assert!(engine.deposit_asset(1, &usd, 1, amount(100)).is_ok());
+        assert!(engine.withdrawal_asset(1, &usd, 2, amount(30)).is_ok());
+        assert!(engine.exchange(1, 3, &usd, &eur, amount(10)).is_ok());
+        assert!(engine.transfer(1, 2, &usd, 4, amount(5)).is_ok());
+        assert!(engine.deposit_asset(2, &usd, 5, amount(50)).is_ok());
+        assert!(engine.withdrawal_asset(2, &usd, 6, amount(50)).is_ok());
+        assert!(engine.dispute(2, 5).is_ok());
+        assert!(engine.dispute(1, 3).is_ok());
+        assert!(engine.chargeback(1, 3).is_ok());
+        assert!(engine.dispute(2, 4).is_ok());
+
+        let reconciliation = engine.reconcile();
+        assert_eq!(reconciliation.version, 10);
+        assert!(reconciliation.is_balanced());
+
+        let usd_reconciliation = &reconciliation.assets[1];
+        assert_eq!(usd_reconciliation.asset, usd);
+        assert_eq!(usd_reconciliation.flows.deposited, amount(150).into());
+        assert_eq!(usd_reconciliation.flows.returned, amount(10).into());
+        assert_eq!(usd_reconciliation.disputed, amount(55).into());
+        assert_eq!(
+            usd_reconciliation.total(),
+            Some(WideBalance::from(WideCurrency::from(amount(70))))
+        );
+    }
+
+    #[test]
+    fn unbalanced_if_held_does_not_match_disputes() {
+        let engine = Engine::new();
+        assert!(engine.deposit(1, 1, Currency::new(10, 0).unwrap()).is_ok());
+        // Break state behind engine back to see it is noticed
+        engine.flows.lock().unwrap().clear();
+        assert!(!engine.reconcile().is_balanced());
+    }
+}
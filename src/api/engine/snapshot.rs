@@ -0,0 +1,378 @@
+//! Consistent copy of all accounts taken at single moment
+//!
+//! Operations keep states of accounts before their changes only while some
+//! snapshot is copied, so snapshot never stops them.
+
+// Standard paths
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Mutex;
+
+// Crate paths
+use crate::api::asset::Asset;
+use crate::api::currency::balance::Balance;
+use crate::api::currency::Currency;
+use crate::api::engine::account::Account;
+use crate::api::engine::account::AccountId;
+use crate::api::engine::Engine;
+
+/// Accounts as they were between operations, no operation is seen partially
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    /// Number of last change of accounts seen, later changes get greater numbers
+    pub version: u64,
+    /// Ordered by ids
+    accounts: Vec<(AccountId, Account)>,
+}
+
+impl Snapshot {
+    /// Accounts in order of ids
+    pub fn accounts(&self) -> &[(AccountId, Account)] {
+        &self.accounts
+    }
+
+    pub fn get_account(&self, id: &AccountId) -> Option<&Account> {
+        self.accounts
+            .binary_search_by(|(other, _)| other.cmp(id))
+            .ok()
+            .map(|index| &self.accounts[index].1)
+    }
+
+    /// Accounts of given asset
+    fn accounts_of<'a>(&'a self, asset: &'a Asset) -> impl Iterator<Item = &'a Account> {
+        self.accounts
+            .iter()
+            .filter(move |(id, _)| &id.asset == asset)
+            .map(|(_, account)| account)
+    }
+
+    /// Sum of available funds of given asset, `None` if it is out of range
+    pub fn sum_available(&self, asset: &Asset) -> Option<Balance> {
+        self.accounts_of(asset)
+            .try_fold(Balance::zero(), |mut sum, account| {
+                sum.add_signed(account.available).ok()?;
+                Some(sum)
+            })
+    }
+
+    /// Sum of held funds of given asset, `None` if it is out of range
+    pub fn sum_held(&self, asset: &Asset) -> Option<Currency> {
+        self.accounts_of(asset)
+            .try_fold(Currency::zero(), |sum, account| {
+                sum.checked_add(account.held)
+            })
+    }
+
+    /// Sum of all funds of given asset, `None` if it is out of range
+    pub fn sum_total(&self, asset: &Asset) -> Option<Balance> {
+        let mut total = self.sum_available(asset)?;
+        total.add(self.sum_held(asset)?).ok()?;
+        Some(total)
+    }
+
+    pub fn count_locked(&self) -> usize {
+        self.accounts
+            .iter()
+            .filter(|(_, account)| account.is_locked())
+            .count()
+    }
+}
+
+/// Numbering of changes of accounts and states kept for snapshots being copied
+#[derive(Debug, Default)]
+pub(super) struct Versions {
+    /// Number of last change taken
+    changes: AtomicU64,
+    /// Snapshots being copied, states before changes are kept only if any
+    readers: AtomicUsize,
+    kept: Mutex<Kept>,
+}
+
+#[derive(Debug, Default)]
+struct Kept {
+    /// Versions of snapshots being copied with count of each
+    snapshots: BTreeMap<u64, usize>,
+    /// States before changes in order of numbers, `None` if change created account
+    accounts: HashMap<AccountId, Vec<(u64, Option<Account>)>>,
+}
+
+impl Versions {
+    /// Let changes keep states before them, version of snapshot is returned
+    fn begin_snapshot(&self) -> u64 {
+        // Counted first, so every change numbered after version keeps state before it
+        self.readers.fetch_add(1, Ordering::SeqCst);
+
+        // Panic if lock is poisoned
+        let mut kept = self.kept.lock().unwrap();
+        let version = self.changes.load(Ordering::SeqCst);
+        *kept.snapshots.entry(version).or_default() += 1;
+
+        version
+    }
+
+    /// Drop states which no other snapshot being copied needs
+    fn end_snapshot(&self, version: u64) {
+        // Panic if lock is poisoned
+        let mut kept = self.kept.lock().unwrap();
+
+        // Should never panic unless logic is buggy
+        let count = kept.snapshots.get_mut(&version).unwrap();
+        *count -= 1;
+        if *count == 0 {
+            kept.snapshots.remove(&version);
+        }
+
+        match kept.snapshots.keys().next().copied() {
+            Some(oldest) => kept.accounts.retain(|_, states| {
+                states.retain(|(number, _)| *number > oldest);
+                !states.is_empty()
+            }),
+            None => kept.accounts.clear(),
+        }
+        self.readers.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Change of accounts by single operation
+///
+/// It has to be taken while all accounts it changes are locked, so number
+/// of change orders it with every other change of the same accounts.
+pub(super) struct Change {
+    number: u64,
+    /// Some snapshot may need states before change
+    keep: bool,
+}
+
+impl Change {
+    /// State before change has to be copied
+    pub(super) fn keeps(&self) -> bool {
+        self.keep
+    }
+}
+
+/// State of account before change
+pub(super) enum Before {
+    /// Neither history nor snapshot needs it
+    Skipped,
+    Existing(Account),
+    /// Account is created by change
+    Created,
+}
+
+impl Engine {
+    /// Copy all accounts at once
+    ///
+    /// Operations do not wait for it, changes applied while accounts are
+    /// copied are replaced with states kept before them.
+    pub fn snapshot(&self) -> Snapshot {
+        let version = self.versions.begin_snapshot();
+        let snapshot = self.copy_snapshot(version);
+        self.versions.end_snapshot(version);
+
+        snapshot
+    }
+
+    /// Copy accounts as they were at version, snapshot has to be begun already
+    fn copy_snapshot(&self, version: u64) -> Snapshot {
+        let mut accounts: HashMap<AccountId, Account> = {
+            // Panic if lock is poisoned
+            let accounts_lock_read = self.accounts.read().unwrap();
+
+            accounts_lock_read
+                .iter()
+                // Panic if mutex is poisoned
+                .map(|(id, mutex)| (id.clone(), mutex.lock().unwrap().clone()))
+                .collect()
+        };
+
+        // Limit lock time
+        {
+            // Panic if lock is poisoned
+            let kept = self.versions.kept.lock().unwrap();
+
+            // First change after version keeps state account had at version
+            for (id, states) in &kept.accounts {
+                match states.iter().find(|(number, _)| *number > version) {
+                    Some((_, Some(before))) => {
+                        if let Some(account) = accounts.get_mut(id) {
+                            *account = before.clone();
+                        }
+                    }
+                    Some((_, None)) => {
+                        accounts.remove(id);
+                    }
+                    None => {}
+                }
+            }
+        }
+
+        let mut accounts: Vec<(AccountId, Account)> = accounts.into_iter().collect();
+        accounts.sort_unstable_by(|(first, _), (second, _)| first.cmp(second));
+
+        Snapshot { version, accounts }
+    }
+
+    /// Number next change, all accounts it changes have to be locked already
+    pub(super) fn begin_change(&self) -> Change {
+        let number = self.versions.changes.fetch_add(1, Ordering::SeqCst) + 1;
+        Change {
+            number,
+            keep: self.versions.readers.load(Ordering::SeqCst) > 0,
+        }
+    }
+
+    /// Keep state before change for snapshots which started before it
+    ///
+    /// It has to be called before account is unlocked.
+    pub(super) fn keep_before(&self, change: &Change, id: &AccountId, before: Before) {
+        let before = match before {
+            Before::Existing(account) => Some(account),
+            Before::Created => None,
+            Before::Skipped => return,
+        };
+        if !change.keep {
+            return;
+        }
+
+        // Panic if lock is poisoned
+        let mut kept = self.versions.kept.lock().unwrap();
+        if kept.snapshots.range(..change.number).next().is_some() {
+            kept.accounts
+                .entry(id.clone())
+                .or_default()
+                .push((change.number, before));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Standard paths
+    use std::sync::Arc;
+    use std::thread;
+
+    // Crate paths
+    use crate::api::engine::options::EngineOptions;
+
+    #[test]
+    fn snapshot_is_not_changed_by_later_operations() {
+        let engine = Engine::new();
+        let asset = Asset::default();
+        assert!(engine.deposit(1, 1, Currency::new(10, 0).unwrap()).is_ok());
+        let snapshot = engine.snapshot();
+        assert!(engine.deposit(1, 2, Currency::new(5, 0).unwrap()).is_ok());
+
+        assert_eq!(snapshot.version, 1);
+        assert_eq!(
+            snapshot
+                .get_account(&AccountId::new(1, &asset))
+                .unwrap()
+                .available,
+            Balance::from(Currency::new(10, 0).unwrap())
+        );
+        assert_eq!(engine.snapshot().version, 2);
+    }
+
+    #[test]
+    fn changes_applied_while_copying_are_not_seen() {
+        let engine = Engine::with_options(EngineOptions {
+            keep_history: true,
+            ..EngineOptions::default()
+        });
+        let asset = Asset::default();
+        assert!(engine.deposit(1, 1, Currency::new(10, 0).unwrap()).is_ok());
+
+        let version = engine.versions.begin_snapshot();
+        assert!(engine.deposit(1, 2, Currency::new(5, 0).unwrap()).is_ok());
+        assert!(engine.deposit(2, 3, Currency::new(7, 0).unwrap()).is_ok());
+        let snapshot = engine.copy_snapshot(version);
+        engine.versions.end_snapshot(version);
+
+        assert_eq!(snapshot.version, 1);
+        assert_eq!(
+            snapshot
+                .get_account(&AccountId::new(1, &asset))
+                .unwrap()
+                .available,
+            Balance::from(Currency::new(10, 0).unwrap())
+        );
+        assert!(snapshot.get_account(&AccountId::new(2, &asset)).is_none());
+        assert!(engine.versions.kept.lock().unwrap().accounts.is_empty());
+        assert_eq!(engine.statement(1).len(), 2);
+    }
+
+    #[test]
+    fn snapshot_does_not_wait_for_operations() {
+        let engine = Engine::new();
+        assert!(engine.deposit(1, 1, Currency::new(10, 0).unwrap()).is_ok());
+
+        // Taken as operation in progress would
+        let _gate = engine.snapshot_gate.read().unwrap();
+        assert_eq!(engine.snapshot().accounts().len(), 1);
+    }
+
+    #[test]
+    fn totals() {
+        let engine = Engine::new();
+        let asset = Asset::default();
+        assert!(engine.deposit(1, 1, Currency::new(10, 0).unwrap()).is_ok());
+        assert!(engine.deposit(2, 2, Currency::new(5, 0).unwrap()).is_ok());
+        assert!(engine.dispute(2, 2).is_ok());
+
+        let snapshot = engine.snapshot();
+        assert_eq!(
+            snapshot.sum_available(&asset),
+            Some(Balance::from(Currency::new(10, 0).unwrap()))
+        );
+        assert_eq!(snapshot.sum_held(&asset), Currency::new(5, 0).ok());
+        assert_eq!(
+            snapshot.sum_total(&asset),
+            Some(Balance::from(Currency::new(15, 0).unwrap()))
+        );
+        assert_eq!(snapshot.count_locked(), 0);
+    }
+
+    #[test]
+    fn transfers_in_parallel_keep_total() {
+        let engine = Arc::new(Engine::new());
+        let asset = Asset::default();
+        assert!(engine
+            .deposit(1, 1, Currency::new(1000, 0).unwrap())
+            .is_ok());
+        assert!(engine
+            .deposit(2, 2, Currency::new(1000, 0).unwrap())
+            .is_ok());
+
+        let workers: Vec<_> = (0..2u32)
+            .map(|worker| {
+                let engine = Arc::clone(&engine);
+                let asset = asset.clone();
+                thread::spawn(move || {
+                    for i in 0..200u32 {
+                        let tx = 10 + worker * 1000 + i;
+                        let (from, to) = if worker == 0 { (1, 2) } else { (2, 1) };
+                        let _ = engine.transfer(from, to, &asset, tx, Currency::new(1, 0).unwrap());
+                    }
+                })
+            })
+            .collect();
+
+        // Every snapshot sees each transfer fully or not at all
+        for _ in 0..50 {
+            assert_eq!(
+                engine.snapshot().sum_total(&asset),
+                Some(Balance::from(Currency::new(2000, 0).unwrap()))
+            );
+        }
+
+        for worker in workers {
+            worker.join().unwrap();
+        }
+        assert_eq!(engine.snapshot().version, 402);
+    }
+}
@@ -5,6 +5,7 @@ use thiserror::Error;
 
 // Crate paths
 use crate::api::currency::error::CurrencyError;
+use crate::api::currency::Currency;
 use crate::api::engine::error::EngineError;
 
 #[derive(Error, Debug)]
@@ -17,6 +18,30 @@ pub enum TransactionsProcessorError {
     CannotReadInputFileRecord { file: String, source: csv::Error },
     #[error("cannot deserialize csv record in input file: {file:?}, reason: {source:?}")]
     CannotDeserializeRecord { file: String, source: csv::Error },
+    #[error("cannot read rates file: {file:?}, reason: {source:?}")]
+    CannotReadRatesFile { file: String, source: csv::Error },
+    #[error("cannot parse rate: {rate:?} in rates file: {file:?}, reason: {source:?}")]
+    CannotParseRate {
+        file: String,
+        rate: String,
+        source: CurrencyError,
+    },
+    #[error("cannot read fees file: {file:?}, reason: {source:?}")]
+    CannotReadFeesFile { file: String, source: csv::Error },
+    #[error("cannot parse value: {value:?} in fees file: {file:?}, reason: {source:?}")]
+    CannotParseFee {
+        file: String,
+        value: String,
+        source: CurrencyError,
+    },
+    #[error("cannot read limits file: {file:?}, reason: {source:?}")]
+    CannotReadLimitsFile { file: String, source: csv::Error },
+    #[error("invalid rule in limits file: {file:?}, reason: {reason}")]
+    InvalidLimit { file: String, reason: String },
+    #[error("input file misses mandatory to_client value")]
+    MissedMandatoryToClientInInputRecord,
+    #[error("input file misses mandatory to_asset value")]
+    MissedMandatoryToAssetInInputRecord,
     #[error("input file misses mandatory amount value")]
     MissedMandatoryAmountInInputRecord,
     #[error("cannot parse input amount: {amount:?}, reason: {source:?}")]
@@ -24,6 +49,69 @@ pub enum TransactionsProcessorError {
         amount: String,
         source: CurrencyError,
     },
+    #[error("asset: {asset:?} of record differs from currency code: {code:?} of its amount")]
+    AssetMismatch { asset: String, code: String },
+    #[error("amount: {input:?} was rounded to: {rounded} before record was rejected, reason: {source:?}")]
+    RejectedRoundedAmount {
+        input: String,
+        rounded: Currency,
+        source: Box<TransactionsProcessorError>,
+    },
+    #[error("cannot use format of output amounts, reason: {source:?}")]
+    InvalidOutputFormat { source: CurrencyError },
+    #[error("cannot write output, reason: {source:?}")]
+    CannotWriteOutput { source: std::io::Error },
+    #[error("cannot deserialize json transaction, reason: {source:?}")]
+    CannotDeserializeJson { source: serde_json::Error },
+    #[error("cannot start server on address: {address:?}, reason: {reason}")]
+    CannotStartServer { address: String, reason: String },
+    #[error("funds of engine do not balance after operations: {version}")]
+    Unbalanced { version: u64 },
     #[error("engine gives error")]
     NestedEngineError(#[from] EngineError),
 }
+
+impl TransactionsProcessorError {
+    /// Short name of error used to count rejected records, e.g. AccountLocked
+    ///
+    /// Errors of engine are told apart by their own variants.
+    pub fn code(&self) -> &'static str {
+        match self {
+            TransactionsProcessorError::CannotReadInputFile { .. } => "CannotReadInputFile",
+            TransactionsProcessorError::CannotReadInputFileHeaders { .. } => {
+                "CannotReadInputFileHeaders"
+            }
+            TransactionsProcessorError::CannotReadInputFileRecord { .. } => {
+                "CannotReadInputFileRecord"
+            }
+            TransactionsProcessorError::CannotDeserializeRecord { .. } => "CannotDeserializeRecord",
+            TransactionsProcessorError::CannotReadRatesFile { .. } => "CannotReadRatesFile",
+            TransactionsProcessorError::CannotParseRate { .. } => "CannotParseRate",
+            TransactionsProcessorError::CannotReadFeesFile { .. } => "CannotReadFeesFile",
+            TransactionsProcessorError::CannotParseFee { .. } => "CannotParseFee",
+            TransactionsProcessorError::CannotReadLimitsFile { .. } => "CannotReadLimitsFile",
+            TransactionsProcessorError::InvalidLimit { .. } => "InvalidLimit",
+            TransactionsProcessorError::MissedMandatoryToClientInInputRecord => {
+                "MissedMandatoryToClientInInputRecord"
+            }
+            TransactionsProcessorError::MissedMandatoryToAssetInInputRecord => {
+                "MissedMandatoryToAssetInInputRecord"
+            }
+            TransactionsProcessorError::MissedMandatoryAmountInInputRecord => {
+                "MissedMandatoryAmountInInputRecord"
+            }
+            TransactionsProcessorError::CannotParseMandatoryInputAmountInInputRecord { .. } => {
+                "CannotParseMandatoryInputAmountInInputRecord"
+            }
+            TransactionsProcessorError::AssetMismatch { .. } => "AssetMismatch",
+            // Rounding only explains why record differs from input
+            TransactionsProcessorError::RejectedRoundedAmount { source, .. } => source.code(),
+            TransactionsProcessorError::InvalidOutputFormat { .. } => "InvalidOutputFormat",
+            TransactionsProcessorError::CannotWriteOutput { .. } => "CannotWriteOutput",
+            TransactionsProcessorError::CannotDeserializeJson { .. } => "CannotDeserializeJson",
+            TransactionsProcessorError::CannotStartServer { .. } => "CannotStartServer",
+            TransactionsProcessorError::Unbalanced { .. } => "Unbalanced",
+            TransactionsProcessorError::NestedEngineError(err) => err.code(),
+        }
+    }
+}
@@ -1,6 +1,11 @@
+use std::fmt;
+
+use serde::de;
 use serde::Deserialize;
+use serde::Deserializer;
+use serde::Serialize;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Type {
     Deposit,
@@ -8,13 +13,213 @@ pub enum Type {
     Dispute,
     Resolve,
     Chargeback,
+    Exchange,
+    Transfer,
+    Lock,
+    Unlock,
+    Freeze,
+    Close,
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Type::Deposit => "deposit",
+            Type::Withdrawal => "withdrawal",
+            Type::Dispute => "dispute",
+            Type::Resolve => "resolve",
+            Type::Chargeback => "chargeback",
+            Type::Exchange => "exchange",
+            Type::Transfer => "transfer",
+            Type::Lock => "lock",
+            Type::Unlock => "unlock",
+            Type::Freeze => "freeze",
+            Type::Close => "close",
+        };
+
+        write!(f, "{}", name)
+    }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct Transaction<'a> {
     #[serde(rename = "type")]
     pub type_: Type,
     pub client: u16,
     pub tx: u32,
     pub amount: Option<&'a str>,
+    /// Optional column, accounts of default asset are used without it
+    #[serde(default, alias = "currency")]
+    pub asset: Option<&'a str>,
+    /// Client who receives transfer
+    #[serde(default)]
+    pub to_client: Option<u16>,
+    /// Seconds since unix epoch, needed by limits measured in time
+    #[serde(default)]
+    pub timestamp: Option<u64>,
+    /// Asset which exchange gives
+    #[serde(default)]
+    pub to_asset: Option<&'a str>,
+    /// Why administrator changed account
+    #[serde(default)]
+    pub reason: Option<&'a str>,
+}
+
+/// Transaction which keeps its fields, e.g. to be sent between tasks
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct OwnedTransaction {
+    #[serde(rename = "type")]
+    pub type_: Type,
+    pub client: u16,
+    pub tx: u32,
+    /// Given as string or number, numbers keep only precision of `f64`
+    #[serde(default, deserialize_with = "amount_from_string_or_number")]
+    pub amount: Option<String>,
+    #[serde(default, alias = "currency")]
+    pub asset: Option<String>,
+    #[serde(default)]
+    pub to_client: Option<u16>,
+    #[serde(default)]
+    pub timestamp: Option<u64>,
+    #[serde(default)]
+    pub to_asset: Option<String>,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// Amount of json transaction, e.g. `"10.5"` or `10.5`
+fn amount_from_string_or_number<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct AmountVisitor;
+
+    impl<'de> de::Visitor<'de> for AmountVisitor {
+        type Value = Option<String>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("amount as string or number")
+        }
+
+        fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+            Ok(None)
+        }
+
+        fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+            Ok(None)
+        }
+
+        fn visit_some<D: Deserializer<'de>>(
+            self,
+            deserializer: D,
+        ) -> Result<Self::Value, D::Error> {
+            deserializer.deserialize_any(self)
+        }
+
+        fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+            Ok(Some(value.to_string()))
+        }
+
+        fn visit_u64<E: de::Error>(self, value: u64) -> Result<Self::Value, E> {
+            Ok(Some(value.to_string()))
+        }
+
+        fn visit_i64<E: de::Error>(self, value: i64) -> Result<Self::Value, E> {
+            Ok(Some(value.to_string()))
+        }
+
+        fn visit_f64<E: de::Error>(self, value: f64) -> Result<Self::Value, E> {
+            // Shortest form which gives the same number, e.g. 10.1 instead of 10.0999...
+            Ok(Some(value.to_string()))
+        }
+    }
+
+    deserializer.deserialize_option(AmountVisitor)
+}
+
+impl OwnedTransaction {
+    /// Transaction of given type without any optional field
+    pub fn new(type_: Type, client: u16, tx: u32, amount: Option<&str>) -> Self {
+        Self {
+            type_,
+            client,
+            tx,
+            amount: amount.map(str::to_string),
+            asset: None,
+            to_client: None,
+            timestamp: None,
+            to_asset: None,
+            reason: None,
+        }
+    }
+
+    pub fn as_transaction(&self) -> Transaction<'_> {
+        Transaction {
+            type_: self.type_,
+            client: self.client,
+            tx: self.tx,
+            amount: self.amount.as_deref(),
+            asset: self.asset.as_deref(),
+            to_client: self.to_client,
+            timestamp: self.timestamp,
+            to_asset: self.to_asset.as_deref(),
+            reason: self.reason.as_deref(),
+        }
+    }
+}
+
+impl From<&Transaction<'_>> for OwnedTransaction {
+    fn from(transaction: &Transaction) -> Self {
+        Self {
+            type_: transaction.type_,
+            client: transaction.client,
+            tx: transaction.tx,
+            amount: transaction.amount.map(str::to_string),
+            asset: transaction.asset.map(str::to_string),
+            to_client: transaction.to_client,
+            timestamp: transaction.timestamp,
+            to_asset: transaction.to_asset.map(str::to_string),
+            reason: transaction.reason.map(str::to_string),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_amount_as_string_or_number() {
+        let parse = |json: &str| {
+            serde_json::from_str::<OwnedTransaction>(json)
+                .unwrap()
+                .amount
+        };
+        assert_eq!(
+            parse(r#"{"type": "deposit", "client": 1, "tx": 1, "amount": "10.5"}"#),
+            Some(String::from("10.5"))
+        );
+        assert_eq!(
+            parse(r#"{"type": "deposit", "client": 1, "tx": 1, "amount": 10.5}"#),
+            Some(String::from("10.5"))
+        );
+        assert_eq!(
+            parse(r#"{"type": "deposit", "client": 1, "tx": 1, "amount": 10}"#),
+            Some(String::from("10"))
+        );
+        assert_eq!(
+            parse(r#"{"type": "dispute", "client": 1, "tx": 1, "amount": null}"#),
+            None
+        );
+        assert_eq!(parse(r#"{"type": "dispute", "client": 1, "tx": 1}"#), None);
+    }
+
+    #[test]
+    fn json_with_escaped_string() {
+        let transaction: OwnedTransaction = serde_json::from_str(
+            r#"{"type": "lock", "client": 1, "tx": 1, "reason": "said \"stop\""}"#,
+        )
+        .unwrap();
+        assert_eq!(transaction.reason.as_deref(), Some(r#"said "stop""#));
+    }
 }
@@ -0,0 +1,222 @@
+//! Command line options of processing shared by binaries, e.g. cli and grpc server
+
+// Crate paths
+use crate::api::asset::Asset;
+use crate::api::asset::AssetOptions;
+use crate::api::currency::parse::ParseOptions;
+use crate::api::currency::parse::Syntax;
+use crate::api::currency::rounding::RoundingMode;
+use crate::api::currency::Currency;
+use crate::api::engine::fees::FeeSchedule;
+use crate::api::engine::options::EngineOptions;
+use crate::api::error::TransactionsProcessorError;
+use crate::options::Options;
+use crate::read_fees;
+use crate::read_limits;
+use crate::read_rates;
+
+// External paths
+use clap::ValueEnum;
+
+/// How engine applies transactions, to be flattened into arguments of binary
+#[derive(clap::Args, Debug, Clone)]
+pub struct ProcessingArgs {
+    /// Csv file with rates of exchange, columns: from, to, rate
+    #[arg(long)]
+    pub rates: Option<String>,
+
+    /// Csv file with fees, columns: operation, client, from, flat, percent
+    #[arg(long, requires = "house_client")]
+    pub fees: Option<String>,
+
+    /// Client whose accounts collect fees
+    #[arg(long, requires = "fees")]
+    pub house_client: Option<u16>,
+
+    /// Rounding of percent part of fees
+    #[arg(long, value_enum, default_value_t = FeeRounding::HalfUp)]
+    pub fee_rounding: FeeRounding,
+
+    /// Csv file with limits, columns: rule, client, amount, count, window
+    #[arg(long)]
+    pub limits: Option<String>,
+
+    /// Let disputes hold funds which are already withdrawn, available funds may go below zero
+    #[arg(long)]
+    pub allow_negative: bool,
+
+    /// What to do with amounts which have more fractional digits than supported
+    #[arg(long, value_enum, default_value_t = Rounding::Reject)]
+    pub rounding: Rounding,
+
+    /// Which notations of amounts to accept in input
+    #[arg(long, value_enum, default_value_t = AmountSyntax::Strict)]
+    pub amount_syntax: AmountSyntax,
+
+    /// Max fractional digits of amounts in given asset, e.g. USD=2, may be repeated
+    #[arg(long, value_name = "ASSET=DIGITS", value_parser = parse_asset_precision)]
+    pub asset_precision: Vec<(Asset, u32)>,
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+pub enum AmountSyntax {
+    /// Only digits with optional fractional part, e.g. 1000.5
+    Strict,
+    /// Also sign, separators, exponent and currency code, e.g. +1_000.5, .5, 1e3, 10.00 USD
+    Lenient,
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+pub enum Rounding {
+    /// Reject record
+    Reject,
+    /// Drop excess digits
+    Truncate,
+    /// Round away from zero
+    Up,
+    /// Round to nearest, ties away from zero
+    HalfUp,
+    /// Round to nearest, ties to even
+    HalfEven,
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+pub enum FeeRounding {
+    /// Drop excess digits
+    Truncate,
+    /// Round away from zero
+    Up,
+    /// Round to nearest, ties away from zero
+    HalfUp,
+    /// Round to nearest, ties to even
+    HalfEven,
+}
+
+impl From<FeeRounding> for RoundingMode {
+    fn from(rounding: FeeRounding) -> Self {
+        match rounding {
+            FeeRounding::Truncate => RoundingMode::Truncate,
+            FeeRounding::Up => RoundingMode::Up,
+            FeeRounding::HalfUp => RoundingMode::HalfUp,
+            FeeRounding::HalfEven => RoundingMode::HalfEven,
+        }
+    }
+}
+
+impl From<Rounding> for Option<RoundingMode> {
+    fn from(rounding: Rounding) -> Self {
+        match rounding {
+            Rounding::Reject => None,
+            Rounding::Truncate => Some(RoundingMode::Truncate),
+            Rounding::Up => Some(RoundingMode::Up),
+            Rounding::HalfUp => Some(RoundingMode::HalfUp),
+            Rounding::HalfEven => Some(RoundingMode::HalfEven),
+        }
+    }
+}
+
+fn parse_asset_precision(input: &str) -> Result<(Asset, u32), String> {
+    let (asset, digits) = input
+        .split_once('=')
+        .ok_or_else(|| format!("expected ASSET=DIGITS, got: {}", input))?;
+    let digits: u32 = digits
+        .parse()
+        .map_err(|err| format!("cannot parse digits: {}, reason: {}", digits, err))?;
+    // Amounts are kept with fixed scale, more digits would be silently dropped
+    if digits > Currency::scale() {
+        return Err(format!(
+            "digits: {} exceed supported fractional digits: {}",
+            digits,
+            Currency::scale()
+        ));
+    }
+
+    Ok((Asset::new(asset), digits))
+}
+
+impl ProcessingArgs {
+    /// Options of processing with files they refer read, output uses defaults
+    pub fn into_options(self) -> Result<Options, TransactionsProcessorError> {
+        let rates = self.rates.as_deref().map(read_rates).transpose()?;
+        let fees = self.fees.as_deref().map(read_fees).transpose()?;
+        let limits = self.limits.as_deref().map(read_limits).transpose()?;
+
+        let mut engine = EngineOptions {
+            allow_negative_available: self.allow_negative,
+            ..EngineOptions::default()
+        };
+        if let Some(rates) = rates {
+            engine.rates = rates;
+        }
+        // House client is required together with fees
+        if let (Some(rules), Some(house)) = (fees, self.house_client) {
+            engine.fees = Some(FeeSchedule {
+                house,
+                rounding: self.fee_rounding.into(),
+                rules,
+            });
+        }
+
+        Ok(Options {
+            engine,
+            parse: ParseOptions {
+                rounding: self.rounding.into(),
+                syntax: match self.amount_syntax {
+                    AmountSyntax::Strict => Syntax::STRICT,
+                    AmountSyntax::Lenient => Syntax::LENIENT,
+                },
+                precision: None,
+            },
+            assets: AssetOptions {
+                precisions: self.asset_precision.into_iter().collect(),
+            },
+            limits: limits.unwrap_or_default(),
+            ..Options::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // External paths
+    use clap::Parser;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        args: ProcessingArgs,
+    }
+
+    #[test]
+    fn options_from_args() {
+        let cli = Cli::parse_from([
+            "test",
+            "--allow-negative",
+            "--rounding",
+            "half-even",
+            "--asset-precision",
+            "USD=2",
+        ]);
+        let options = cli.args.into_options().unwrap();
+        assert!(options.engine.allow_negative_available);
+        assert_eq!(options.parse.rounding, Some(RoundingMode::HalfEven));
+        assert_eq!(options.assets.precisions[&Asset::new("USD")], 2);
+    }
+
+    #[test]
+    fn asset_precision_above_scale() {
+        assert_eq!(parse_asset_precision("USD=4"), Ok((Asset::new("USD"), 4)));
+        assert!(parse_asset_precision("BTC=8").is_err());
+    }
+
+    #[test]
+    fn missing_rates_file() {
+        let cli = Cli::parse_from(["test", "--rates", "tmp/missing_rates_file.csv"]);
+        assert!(matches!(
+            cli.args.into_options(),
+            Err(TransactionsProcessorError::CannotReadRatesFile { .. })
+        ));
+    }
+}
@@ -1,32 +1,357 @@
 // Standard paths
 use std::convert::TryFrom;
+use std::io;
+use std::io::Write;
+use std::time::Instant;
 
 // Crate paths
+use api::asset::Asset;
+use api::currency::error::CurrencyError;
 use api::currency::Currency;
+use api::engine::account::AccountId;
+use api::engine::admin::AdminAction;
+use api::engine::admin::AdminRecord;
+use api::engine::error::EngineError;
+use api::engine::fees::FeeRecord;
+use api::engine::fees::FeeRule;
+use api::engine::limits::Limit;
+use api::engine::limits::LimitRecord;
+use api::engine::limits::LimitRule;
+use api::engine::limits::Limiter;
+use api::engine::limits::Moment;
+use api::engine::operation::Operation;
+use api::engine::rates::Rate;
+use api::engine::rates::RateRecord;
+use api::engine::rates::RateTable;
 use api::engine::Engine;
 use api::error::TransactionsProcessorError;
 use api::transactions::Transaction;
 use api::transactions::Type;
+use options::Options;
+use server::Server;
+use statement::StatementFormat;
+use summary::ProcessingSummary;
 
 // External paths
 use anyhow::Result;
+use tracing::debug_span;
+use tracing::info;
+use tracing::info_span;
+use tracing::trace;
+use tracing::warn;
 
 // Crate modules
 pub mod api;
+pub mod args;
+pub mod metrics;
+pub mod options;
+pub mod reconciliation;
+pub mod server;
+pub mod service;
+pub mod statement;
+#[cfg(feature = "async")]
+pub mod stream;
+pub mod summary;
 
-pub fn process(file: &str) -> anyhow::Result<()> {
-    // Create transaction engine
-    let mut engine = Engine::new();
+/// Process transactions, print final state of accounts and tell what happened to records
+pub fn process(file: &str, options: &Options) -> anyhow::Result<ProcessingSummary> {
+    // Fail early before any processing if report could not be read back
+    options
+        .format
+        .validate()
+        .map_err(|source| TransactionsProcessorError::InvalidOutputFormat { source })?;
 
     // Prepare input stream with transactions to process
-    let mut rdr = csv::ReaderBuilder::new()
-        .trim(csv::Trim::All)
+    let rdr = transactions_reader_builder()
         .from_path(file)
         .map_err(|err| TransactionsProcessorError::CannotReadInputFile {
             file: file.to_string(),
             source: err,
         })?;
 
+    let (engine, summary) = process_transactions(rdr, file, options)?;
+
+    print_accounts(&engine, options)?;
+    log_overdrawn_accounts(&engine);
+
+    Ok(summary)
+}
+
+/// Process transactions and print history of operations of given clients
+pub fn print_statement(
+    file: &str,
+    clients: &[u16],
+    options: &Options,
+    format: StatementFormat,
+) -> anyhow::Result<()> {
+    // Fail early before any processing if statement could not be read back
+    options
+        .format
+        .validate()
+        .map_err(|source| TransactionsProcessorError::InvalidOutputFormat { source })?;
+
+    let rdr = transactions_reader_builder()
+        .from_path(file)
+        .map_err(|err| TransactionsProcessorError::CannotReadInputFile {
+            file: file.to_string(),
+            source: err,
+        })?;
+
+    // History is needed for statements
+    let mut options = options.clone();
+    options.engine.keep_history = true;
+
+    let (engine, _) = process_transactions(rdr, file, &options)?;
+
+    let stdout = io::stdout();
+    let mut writer = io::BufWriter::new(stdout.lock());
+    statement::write_statement(&engine, clients, &mut writer, &options, format)
+        .and_then(|_| writer.flush())
+        .map_err(|source| TransactionsProcessorError::CannotWriteOutput { source })?;
+
+    Ok(())
+}
+
+/// Process transactions and print reconciliation of funds of each asset
+///
+/// Fails if funds do not balance.
+pub fn print_reconciliation(file: &str, options: &Options) -> anyhow::Result<()> {
+    options
+        .format
+        .validate()
+        .map_err(|source| TransactionsProcessorError::InvalidOutputFormat { source })?;
+
+    let rdr = transactions_reader_builder()
+        .from_path(file)
+        .map_err(|err| TransactionsProcessorError::CannotReadInputFile {
+            file: file.to_string(),
+            source: err,
+        })?;
+
+    let (engine, _) = process_transactions(rdr, file, options)?;
+    let reconciliation = engine.reconcile();
+
+    let stdout = io::stdout();
+    let mut writer = io::BufWriter::new(stdout.lock());
+    reconciliation::write_reconciliation(&reconciliation, &mut writer, options)
+        .and_then(|_| writer.flush())
+        .map_err(|source| TransactionsProcessorError::CannotWriteOutput { source })?;
+
+    if !reconciliation.is_balanced() {
+        return Err(TransactionsProcessorError::Unbalanced {
+            version: reconciliation.version,
+        }
+        .into());
+    }
+
+    Ok(())
+}
+
+/// Serve engine over http until process is stopped
+///
+/// Transactions of given file are applied first, limits count only records
+/// received by server.
+pub fn serve(
+    address: &str,
+    file: Option<&str>,
+    options: &Options,
+    threads: usize,
+) -> anyhow::Result<()> {
+    let engine = match file {
+        Some(file) => {
+            let rdr = transactions_reader_builder()
+                .from_path(file)
+                .map_err(|err| TransactionsProcessorError::CannotReadInputFile {
+                    file: file.to_string(),
+                    source: err,
+                })?;
+            process_transactions(rdr, file, options)?.0
+        }
+        None => Engine::with_options(options.engine.clone()),
+    };
+
+    let server = Server::bind(address, engine, options.clone())?;
+    server.run(threads);
+
+    Ok(())
+}
+
+/// Process transactions from any csv source, e.g. buffer already kept in memory
+///
+/// `name` is only used to describe source in errors.
+pub fn process_reader<R: io::Read>(
+    reader: R,
+    name: &str,
+    options: &Options,
+) -> Result<Engine, TransactionsProcessorError> {
+    let rdr = transactions_reader_builder().from_reader(reader);
+
+    process_transactions(rdr, name, options).map(|(engine, _)| engine)
+}
+
+/// Read rates of exchange from csv file with columns: from, to, rate
+pub fn read_rates(file: &str) -> Result<RateTable, TransactionsProcessorError> {
+    let read_error = |source| TransactionsProcessorError::CannotReadRatesFile {
+        file: file.to_string(),
+        source,
+    };
+
+    let mut rdr = csv_reader_builder().from_path(file).map_err(read_error)?;
+    let headers = rdr.byte_headers().map_err(read_error)?.clone();
+
+    let mut rates = RateTable::new();
+    let mut raw_record = csv::ByteRecord::new();
+    while rdr.read_byte_record(&mut raw_record).map_err(read_error)? {
+        let record: RateRecord = raw_record.deserialize(Some(&headers)).map_err(read_error)?;
+        let rate = Rate::try_from(record.rate).map_err(|source| {
+            TransactionsProcessorError::CannotParseRate {
+                file: file.to_string(),
+                rate: record.rate.to_string(),
+                source,
+            }
+        })?;
+        rates.insert(Asset::new(record.from), Asset::new(record.to), rate);
+    }
+
+    Ok(rates)
+}
+
+/// Read fee rules from csv file with columns: operation, client, from, flat, percent
+pub fn read_fees(file: &str) -> Result<Vec<FeeRule>, TransactionsProcessorError> {
+    let read_error = |source| TransactionsProcessorError::CannotReadFeesFile {
+        file: file.to_string(),
+        source,
+    };
+
+    let mut rdr = csv_reader_builder().from_path(file).map_err(read_error)?;
+    let headers = rdr.byte_headers().map_err(read_error)?.clone();
+
+    let mut rules = Vec::new();
+    let mut raw_record = csv::ByteRecord::new();
+    while rdr.read_byte_record(&mut raw_record).map_err(read_error)? {
+        let record: FeeRecord = raw_record.deserialize(Some(&headers)).map_err(read_error)?;
+        rules.push(FeeRule {
+            operation: record.operation,
+            client: record.client,
+            from: parse_fee_value(file, record.from)?,
+            flat: parse_fee_value(file, record.flat)?,
+            percent: parse_fee_value(file, record.percent)?,
+        });
+    }
+
+    Ok(rules)
+}
+
+/// Read limits from csv file with columns: rule, client, amount, count, window
+///
+/// Rules are `max_withdrawal` and `max_deposit` with amount, `withdrawal_cap`
+/// with amount and window, `max_operations` with count and window.
+pub fn read_limits(file: &str) -> Result<Vec<LimitRule>, TransactionsProcessorError> {
+    let read_error = |source| TransactionsProcessorError::CannotReadLimitsFile {
+        file: file.to_string(),
+        source,
+    };
+    let invalid = |reason: String| TransactionsProcessorError::InvalidLimit {
+        file: file.to_string(),
+        reason,
+    };
+
+    let mut rdr = csv_reader_builder().from_path(file).map_err(read_error)?;
+    let headers = rdr.byte_headers().map_err(read_error)?.clone();
+
+    let mut rules = Vec::new();
+    let mut raw_record = csv::ByteRecord::new();
+    while rdr.read_byte_record(&mut raw_record).map_err(read_error)? {
+        let record: LimitRecord = raw_record.deserialize(Some(&headers)).map_err(read_error)?;
+
+        let amount = || {
+            let amount = record
+                .amount
+                .ok_or_else(|| invalid(format!("rule: {} misses amount", record.rule)))?;
+            Currency::try_from(amount).map_err(|err| {
+                invalid(format!(
+                    "cannot parse amount: {}, reason: {:?}",
+                    amount, err
+                ))
+            })
+        };
+        let count = || {
+            record
+                .count
+                .ok_or_else(|| invalid(format!("rule: {} misses count", record.rule)))
+        };
+        let window = || {
+            record
+                .window
+                .ok_or_else(|| invalid(format!("rule: {} misses window", record.rule)))?
+                .parse()
+                .map_err(invalid)
+        };
+
+        let limit = match record.rule {
+            "max_withdrawal" => Limit::MaxWithdrawal(amount()?),
+            "max_deposit" => Limit::MaxDeposit(amount()?),
+            "withdrawal_cap" => Limit::WithdrawalCap {
+                amount: amount()?,
+                window: window()?,
+            },
+            "max_operations" => Limit::MaxOperations {
+                count: count()?,
+                window: window()?,
+            },
+            rule => return Err(invalid(format!("unknown rule: {}", rule))),
+        };
+
+        rules.push(LimitRule {
+            client: record.client,
+            limit,
+        });
+    }
+
+    Ok(rules)
+}
+
+/// Parse amount or percent of fee, missing value is zero
+fn parse_fee_value<V>(file: &str, value: Option<&str>) -> Result<V, TransactionsProcessorError>
+where
+    V: for<'a> TryFrom<&'a str, Error = CurrencyError>,
+{
+    let value = value.unwrap_or("0");
+
+    V::try_from(value).map_err(|source| TransactionsProcessorError::CannotParseFee {
+        file: file.to_string(),
+        value: value.to_string(),
+        source,
+    })
+}
+
+fn csv_reader_builder() -> csv::ReaderBuilder {
+    let mut builder = csv::ReaderBuilder::new();
+    builder.trim(csv::Trim::All);
+    builder
+}
+
+/// Reader of transactions, other files keep the same number of fields in each record
+fn transactions_reader_builder() -> csv::ReaderBuilder {
+    let mut builder = csv_reader_builder();
+    // Let records skip trailing optional columns, e.g. asset of dispute
+    builder.flexible(true);
+    builder
+}
+
+fn process_transactions<R: io::Read>(
+    mut rdr: csv::Reader<R>,
+    file: &str,
+    options: &Options,
+) -> Result<(Engine, ProcessingSummary), TransactionsProcessorError> {
+    let _file = info_span!("file", file).entered();
+    let started = Instant::now();
+    let mut summary = ProcessingSummary::default();
+    // Create transaction engine
+    let engine = Engine::with_options(options.engine.clone());
+    // Create rules checked before operations
+    let limiter = Limiter::new(options.limits.clone());
+
     // Read first row which is supposed csv headers
     let mut raw_record = csv::ByteRecord::new();
     let headers = rdr.byte_headers().map_err(|err| {
@@ -47,149 +372,459 @@ pub fn process(file: &str) -> anyhow::Result<()> {
                     source: err,
                 };
                 // Finish processing with fatal error
-                return Err(anyhow::Error::new(nested_error));
+                return Err(nested_error);
                 // Or only print warning if error is not considered fatal
                 // and continue processing any following records
-                // print_record_warning(&raw_record, nested_error);
+                // log_record_warning(&raw_record, nested_error);
             }
             // End of input csv file, finish
             Ok(false) => break,
             // Record is read into buffer
             Ok(true) => {
+                summary.records_read += 1;
+                let _record = debug_span!(
+                    "record",
+                    line = raw_record.position().map(|position| position.line())
+                )
+                .entered();
                 // Process record
                 // If any errors, then print them as warnings and continue with others
-                process_record(&mut engine, &raw_record, &headers, file).unwrap_or_else(|err| {
-                    print_record_warning(raw_record.position(), err);
-                });
+                let processor = Processor {
+                    engine: &engine,
+                    limiter: &limiter,
+                    options,
+                };
+                match processor.process_record(&raw_record, &headers, file) {
+                    Ok((type_, rounded)) => {
+                        trace!(%type_, "applied record");
+                        summary.applied(type_);
+                        if let Some(rounded) = rounded {
+                            summary.rounded += 1;
+                            log_rounded_warning(raw_record.position(), rounded);
+                        }
+                    }
+                    Err(err) => {
+                        summary.rejected(&err);
+                        log_record_warning(raw_record.position(), err);
+                    }
+                }
             }
         }
     }
 
-    print_accounts(&engine);
+    // Accounts are never removed, so all of them were created by this run
+    let snapshot = engine.snapshot();
+    summary.accounts_created = snapshot.accounts().len() as u64;
+    summary.accounts_locked = snapshot.count_locked() as u64;
+    summary.finish(started.elapsed());
+    if let Some(metrics) = &options.metrics {
+        metrics.observe(&engine);
+    }
+    info!(
+        records = summary.records_read,
+        accounts = summary.accounts_created,
+        "processed file"
+    );
 
-    Ok(())
+    Ok((engine, summary))
 }
 
-fn process_record(
-    engine: &mut Engine,
-    raw_record: &csv::ByteRecord,
-    headers: &csv::ByteRecord,
-    file: &str,
-) -> Result<(), TransactionsProcessorError> {
-    // Try to deserialize record into assumed structure
-    let transaction: Transaction = raw_record.deserialize(Some(headers)).map_err(|err| {
-        TransactionsProcessorError::CannotDeserializeRecord {
-            file: file.to_string(),
-            source: err,
-        }
-    })?;
-
-    // Dispatach transaction into proper engine call
-    dispatch(engine, &transaction)?;
+/// Amount of record which had to be rounded to fit into precision
+#[derive(Debug)]
+pub struct RoundedAmount {
+    pub input: String,
+    pub rounded: Currency,
+}
 
-    Ok(())
+/// Amount of transaction together with asset it is given in
+struct AssetAmount {
+    asset: Asset,
+    amount: Currency,
+    rounded: Option<RoundedAmount>,
 }
 
-fn get_and_parse_amount(amount: Option<&str>) -> Result<Currency, TransactionsProcessorError> {
+fn get_and_parse_amount(
+    transaction: &Transaction,
+    options: &Options,
+) -> Result<AssetAmount, TransactionsProcessorError> {
     // Ensure required field is provided
-    let amount = amount.ok_or(TransactionsProcessorError::MissedMandatoryAmountInInputRecord)?;
+    let amount = transaction
+        .amount
+        .ok_or(TransactionsProcessorError::MissedMandatoryAmountInInputRecord)?;
+    let asset = transaction.asset.map(Asset::new).unwrap_or_default();
+
     // Parse input string into Currency type
-    let amount = Currency::try_from(amount).map_err(|err| {
-        TransactionsProcessorError::CannotParseMandatoryInputAmountInInputRecord {
-            amount: amount.to_string(),
-            source: err,
+    let parse = |asset: &Asset| {
+        let parse_options = options.assets.parse_options(asset, &options.parse);
+        Currency::parse_with(amount, &parse_options).map_err(|err| {
+            TransactionsProcessorError::CannotParseMandatoryInputAmountInInputRecord {
+                amount: amount.to_string(),
+                source: err,
+            }
+        })
+    };
+    let mut parsed = parse(&asset)?;
+
+    // Currency code written after amount may stand for asset column
+    let asset = match (transaction.asset, parsed.code.take()) {
+        (Some(asset), Some(code)) if asset != code => {
+            return Err(TransactionsProcessorError::AssetMismatch {
+                asset: asset.to_string(),
+                code,
+            });
         }
-    })?;
+        (None, Some(code)) => {
+            let asset = Asset::new(&code);
+            // Amount has to fit into precision of asset known only now
+            if options.assets.precisions.contains_key(&asset) {
+                parsed = parse(&asset)?;
+            }
+            asset
+        }
+        _ => asset,
+    };
+
+    // Keep original input to let know what was changed
+    let rounded = if parsed.rounded {
+        Some(RoundedAmount {
+            input: amount.to_string(),
+            rounded: parsed.value,
+        })
+    } else {
+        None
+    };
 
-    Ok(amount)
+    Ok(AssetAmount {
+        asset,
+        amount: parsed.value,
+        rounded,
+    })
 }
 
-fn dispatch(
-    engine: &mut Engine,
-    transaction: &Transaction,
-) -> Result<(), TransactionsProcessorError> {
-    match transaction.type_ {
-        Type::Deposit => {
-            let amount = get_and_parse_amount(transaction.amount)?;
+/// Everything needed to apply single record
+struct Processor<'a> {
+    engine: &'a Engine,
+    limiter: &'a Limiter,
+    options: &'a Options,
+}
 
-            engine.deposit(transaction.client, transaction.tx, amount)?;
+/// Type of applied transaction together with amount rounded to apply it
+pub type Processed = Result<(Type, Option<RoundedAmount>), TransactionsProcessorError>;
 
-            Ok(())
-        }
-        Type::Withdrawal => {
-            let amount = get_and_parse_amount(transaction.amount)?;
+impl Processor<'_> {
+    fn process_record(
+        &self,
+        raw_record: &csv::ByteRecord,
+        headers: &csv::ByteRecord,
+        file: &str,
+    ) -> Processed {
+        let record = raw_record
+            .position()
+            .map_or(0, |position| position.record());
 
-            engine.withdrawal(transaction.client, transaction.tx, amount)?;
+        self.measured(|| {
+            // Try to deserialize record into assumed structure
+            let transaction: Transaction =
+                raw_record.deserialize(Some(headers)).map_err(|err| {
+                    TransactionsProcessorError::CannotDeserializeRecord {
+                        file: file.to_string(),
+                        source: err,
+                    }
+                })?;
 
-            Ok(())
-        }
-        Type::Dispute => {
-            engine.dispute(transaction.client, transaction.tx)?;
+            self.apply(&transaction, record)
+        })
+    }
 
-            Ok(())
-        }
-        Type::Resolve => {
-            engine.resolve(transaction.client, transaction.tx)?;
+    /// Apply transaction as given record of input, which limits count in windows
+    fn apply(&self, transaction: &Transaction, record: u64) -> Processed {
+        let moment = Moment {
+            record,
+            timestamp: transaction.timestamp,
+        };
 
-            Ok(())
+        // Dispatach transaction into proper engine call
+        self.dispatch(transaction, moment)
+            .map(|rounded| (transaction.type_, rounded))
+    }
+
+    /// Count result of processing in metrics if anyone needs them
+    fn measured(&self, process: impl FnOnce() -> Processed) -> Processed {
+        let metrics = match &self.options.metrics {
+            Some(metrics) => metrics,
+            None => return process(),
+        };
+
+        let started = Instant::now();
+        let result = process();
+        match &result {
+            Ok((type_, rounded)) => {
+                metrics.applied(*type_, started.elapsed());
+                if rounded.is_some() {
+                    metrics.rounded();
+                }
+            }
+            Err(err) => metrics.rejected(err, started.elapsed()),
         }
-        Type::Chargeback => {
-            engine.chargeback(transaction.client, transaction.tx)?;
 
-            Ok(())
+        result
+    }
+
+    fn dispatch(
+        &self,
+        transaction: &Transaction,
+        moment: Moment,
+    ) -> Result<Option<RoundedAmount>, TransactionsProcessorError> {
+        let Processor {
+            engine,
+            limiter,
+            options,
+        } = self;
+        let client = transaction.client;
+        let tx = transaction.tx;
+
+        // Asset of dispute, resolve and chargeback is the one of referenced transaction
+        match transaction.type_ {
+            Type::Deposit => {
+                let AssetAmount {
+                    asset,
+                    amount,
+                    rounded,
+                } = get_and_parse_amount(transaction, options)?;
+
+                let id = AccountId::new(client, &asset);
+                let result = limiter.guard(&id, Operation::Deposit, tx, amount, moment, || {
+                    engine.deposit_asset(client, &asset, tx, amount)
+                });
+
+                rounded_result(result, rounded)
+            }
+            Type::Withdrawal => {
+                let AssetAmount {
+                    asset,
+                    amount,
+                    rounded,
+                } = get_and_parse_amount(transaction, options)?;
+
+                let id = AccountId::new(client, &asset);
+                let result = limiter.guard(&id, Operation::Withdrawal, tx, amount, moment, || {
+                    engine.withdrawal_asset(client, &asset, tx, amount)
+                });
+
+                rounded_result(result, rounded)
+            }
+            Type::Exchange => {
+                let to = transaction
+                    .to_asset
+                    .map(Asset::new)
+                    .ok_or(TransactionsProcessorError::MissedMandatoryToAssetInInputRecord)?;
+                let AssetAmount {
+                    asset,
+                    amount,
+                    rounded,
+                } = get_and_parse_amount(transaction, options)?;
+
+                // Taking funds of source asset is limited as withdrawal
+                let id = AccountId::new(client, &asset);
+                let result = limiter.guard(&id, Operation::Withdrawal, tx, amount, moment, || {
+                    engine.exchange(client, tx, &asset, &to, amount)
+                });
+
+                rounded_result(result, rounded)
+            }
+            Type::Transfer => {
+                let to_client = transaction
+                    .to_client
+                    .ok_or(TransactionsProcessorError::MissedMandatoryToClientInInputRecord)?;
+                let AssetAmount {
+                    asset,
+                    amount,
+                    rounded,
+                } = get_and_parse_amount(transaction, options)?;
+
+                // Sending funds is limited as withdrawal
+                let id = AccountId::new(client, &asset);
+                let result = limiter.guard(&id, Operation::Withdrawal, tx, amount, moment, || {
+                    engine.transfer(client, to_client, &asset, tx, amount)
+                });
+
+                rounded_result(result, rounded)
+            }
+            Type::Dispute => {
+                engine.dispute(client, tx)?;
+
+                Ok(None)
+            }
+            Type::Resolve => {
+                engine.resolve(client, tx)?;
+
+                Ok(None)
+            }
+            Type::Chargeback => {
+                engine.chargeback(client, tx)?;
+
+                Ok(None)
+            }
+            Type::Lock => self.admin(transaction, AdminAction::Lock),
+            Type::Unlock => self.admin(transaction, AdminAction::Unlock),
+            Type::Freeze => self.admin(transaction, AdminAction::Freeze),
+            Type::Close => self.admin(transaction, AdminAction::Close),
         }
     }
+
+    /// Administrative operation applies to all accounts of client without asset column
+    fn admin(
+        &self,
+        transaction: &Transaction,
+        action: AdminAction,
+    ) -> Result<Option<RoundedAmount>, TransactionsProcessorError> {
+        self.engine.admin(AdminRecord {
+            client: transaction.client,
+            asset: transaction.asset.map(Asset::new),
+            tx: transaction.tx,
+            action,
+            reason: transaction.reason.map(str::to_string),
+        })?;
+
+        Ok(None)
+    }
 }
 
-fn print_accounts(engine: &Engine) {
-    // Print csv header
-    println!("client, available, held, total, locked");
+/// Rejected record still tells its amount was rounded
+fn rounded_result(
+    result: Result<(), EngineError>,
+    rounded: Option<RoundedAmount>,
+) -> Result<Option<RoundedAmount>, TransactionsProcessorError> {
+    match (result, rounded) {
+        (Ok(()), rounded) => Ok(rounded),
+        (Err(err), Some(rounded)) => Err(TransactionsProcessorError::RejectedRoundedAmount {
+            input: rounded.input,
+            rounded: rounded.rounded,
+            source: Box::new(err.into()),
+        }),
+        (Err(err), None) => Err(err.into()),
+    }
+}
 
-    let accounts = engine.accounts();
+fn print_accounts(engine: &Engine, options: &Options) -> Result<(), TransactionsProcessorError> {
+    let stdout = io::stdout();
+    let mut writer = io::BufWriter::new(stdout.lock());
 
-    // Limit lock time
-    {
-        // Panic if lock is poisoned
-        let accounts_lock_read = accounts.read().unwrap();
-
-        for (client, mutex) in accounts_lock_read.iter() {
-            let account = mutex.lock().unwrap();
-
-            // Calculate total
-            let mut total = account.available;
-            // What is better?
-            // To refuse operations which exceed total? (Then implement total field in Account)
-            // Or to print inacurate total value and warning during structure dump?
-            total.add(account.held).unwrap_or_else(|err| {
-                eprintln!("WARNING: total is out of range: {:?}", err);
-            });
+    write_accounts(engine, &mut writer, options)
+        .and_then(|_| writer.flush())
+        .map_err(|source| TransactionsProcessorError::CannotWriteOutput { source })
+}
 
-            // Print data
-            // To easy to serde or csv crates
-            // This way is fastest
-            // Speed matters
-            println!(
-                "{},{},{},{},{}",
-                client, account.available, account.held, total, account.locked
-            );
+/// Write states of all accounts as csv with amounts written in format of given `options`
+pub fn write_accounts<W: Write>(
+    engine: &Engine,
+    writer: &mut W,
+    options: &Options,
+) -> io::Result<()> {
+    let format = &options.format;
+    // Consistent view even if other threads still process transactions
+    let snapshot = engine.snapshot();
+    let accounts = snapshot.accounts();
+
+    // Asset column is written only if input had any
+    let with_assets = accounts.iter().any(|(id, _)| !id.asset.is_default());
+
+    // Write csv header
+    if with_assets {
+        write!(writer, "client, asset, available, held, total, locked")?;
+    } else {
+        write!(writer, "client, available, held, total, locked")?;
+    }
+    if options.report_status {
+        write!(writer, ", status, reason")?;
+    }
+    writeln!(writer)?;
+
+    // Rows of the same client are kept together as accounts are ordered by ids
+    for (id, account) in accounts {
+        // Engine never lets total go out of range, report is refused rather than wrong
+        let total = account.total().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("total of account is out of range, {}", id),
+            )
+        })?;
+
+        // Write data
+        // To easy to serde or csv crates
+        // This way is fastest
+        // Speed matters
+        if with_assets {
+            write!(writer, "{},{},", id.client, csv_field(id.asset.to_string()))?;
+        } else {
+            write!(writer, "{},", id.client)?;
+        }
+        write!(
+            writer,
+            "{},{},{},{}",
+            csv_field(account.available.format(format)),
+            csv_field(format.format(account.held)),
+            csv_field(total.format(format)),
+            account.is_locked()
+        )?;
+        if options.report_status {
+            write!(
+                writer,
+                ",{},{}",
+                account.status.name(),
+                csv_field(account.status.reason().unwrap_or_default().to_string())
+            )?;
         }
+        writeln!(writer)?;
+    }
+
+    Ok(())
+}
+
+/// Quote field if it has comma, quote or new line, e.g. amount written with comma as decimal mark
+fn csv_field(field: String) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field
+    }
+}
+
+fn log_overdrawn_accounts(engine: &Engine) {
+    for id in engine.overdrawn_accounts() {
+        warn!(account = %id, "account is overdrawn");
     }
 }
 
-fn print_record_warning(
-    optional_position: Option<&csv::Position>,
-    err: TransactionsProcessorError,
-) {
+fn log_rounded_warning(optional_position: Option<&csv::Position>, rounded: RoundedAmount) {
+    let line = optional_position.map(|position| position.line());
+
+    warn!(
+        line,
+        amount = %rounded.input,
+        rounded = %rounded.rounded,
+        "rounded amount of record"
+    );
+}
+
+fn log_record_warning(optional_position: Option<&csv::Position>, err: TransactionsProcessorError) {
+    if let TransactionsProcessorError::RejectedRoundedAmount {
+        input,
+        rounded,
+        source,
+    } = &err
+    {
+        let line = optional_position.map(|position| position.line());
+        warn!(line, amount = %input, %rounded, reason = ?source, "failed to process record");
+        return;
+    }
+
     match optional_position {
         Some(position) => {
-            eprintln!(
-                "WARNING: failed to process record:\nline: {}\nreason: {:?}",
-                position.line(),
-                err
-            );
+            warn!(line = position.line(), reason = ?err, "failed to process record");
         }
         None => {
-            eprintln!("WARNING: ignored record, reason: {:?}", err);
+            warn!(reason = ?err, "ignored record");
         }
     };
 }
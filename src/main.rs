@@ -1,19 +1,313 @@
 #![forbid(unsafe_code)]
 
 // Standard paths
-use std::env;
+use std::fs::File;
+use std::io;
+use std::io::BufWriter;
+use std::io::IsTerminal;
+use std::io::Write;
 use std::process;
+use std::sync::Arc;
 
 // Crate paths
+use transactions_processor::api::currency::format::Format;
+use transactions_processor::api::currency::format::Scale;
+use transactions_processor::args::ProcessingArgs;
+use transactions_processor::metrics::Metrics;
+use transactions_processor::options::Options;
+use transactions_processor::print_reconciliation;
+use transactions_processor::print_statement;
 use transactions_processor::process;
+use transactions_processor::serve;
+use transactions_processor::statement::StatementFormat;
+use transactions_processor::summary::ProcessingSummary;
+
+// External paths
+use anyhow::anyhow;
+use anyhow::Context;
+use clap::ArgAction;
+use clap::Parser;
+use clap::Subcommand;
+use clap::ValueEnum;
+use tracing::level_filters::LevelFilter;
+
+/// Engine to process transactions and print final state of accounts
+#[derive(Parser)]
+#[command(
+    version,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    args: Args,
+
+    /// Json file to write summary of processing to instead of printing it to stderr
+    #[arg(long, value_name = "PATH")]
+    summary: Option<String>,
+
+    /// Log more, may be repeated: info, debug, trace
+    #[arg(short, long, action = ArgAction::Count, global = true)]
+    verbose: u8,
+
+    /// Log less, may be repeated: only errors, nothing
+    #[arg(short, long, action = ArgAction::Count, global = true, conflicts_with = "verbose")]
+    quiet: u8,
+
+    /// How to write logs to stderr
+    #[arg(long, value_enum, default_value_t = LogFormat::Text, global = true)]
+    log_format: LogFormat,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Process transactions and print history of operations of clients
+    Statement {
+        /// Client whose history is printed, may be repeated
+        #[arg(long = "client", required = true)]
+        clients: Vec<u16>,
+
+        /// How to write history
+        #[arg(long, value_enum, default_value_t = StatementOutput::Csv)]
+        output: StatementOutput,
+
+        #[command(flatten)]
+        args: Args,
+    },
+    /// Process transactions and check that funds of each asset balance
+    Reconcile {
+        #[command(flatten)]
+        args: Args,
+    },
+    /// Run local http server applying transactions to shared engine
+    #[command(mut_arg("transactions", |arg| arg.required(false)))]
+    Serve {
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        address: String,
+
+        /// Number of requests handled at once
+        #[arg(long, default_value_t = 4)]
+        threads: usize,
+
+        #[command(flatten)]
+        args: Args,
+    },
+}
+
+#[derive(clap::Args)]
+struct Args {
+    /// Csv file with transactions
+    #[arg(required = true)]
+    transactions: Option<String>,
+
+    #[command(flatten)]
+    processing: ProcessingArgs,
+
+    /// Which fractional digits of amounts to write in report
+    #[arg(long, value_enum, default_value_t = AmountFormat::Legacy)]
+    amount_format: AmountFormat,
+
+    /// Separator put between each 3 digits of amounts in report
+    #[arg(long)]
+    thousands_separator: Option<char>,
+
+    /// Character put between decimal and fractional part of amounts in report
+    #[arg(long, default_value_t = '.')]
+    decimal_mark: char,
+
+    /// Write status of accounts and reason of it in report
+    #[arg(long)]
+    with_status: bool,
+
+    /// File to write metrics of processing to in Prometheus text format at exit
+    #[arg(long, value_name = "PATH")]
+    metrics: Option<String>,
+}
+
+#[derive(Copy, Clone, ValueEnum)]
+enum StatementOutput {
+    /// Csv with one operation per line
+    Csv,
+    /// Json array of operations
+    Json,
+}
+
+impl From<StatementOutput> for StatementFormat {
+    fn from(output: StatementOutput) -> Self {
+        match output {
+            StatementOutput::Csv => StatementFormat::Csv,
+            StatementOutput::Json => StatementFormat::Json,
+        }
+    }
+}
+
+#[derive(Copy, Clone, ValueEnum)]
+enum LogFormat {
+    /// Line of text per event
+    Text,
+    /// Json object per event, e.g. to be collected with logs of other services
+    Json,
+}
+
+#[derive(Copy, Clone, ValueEnum)]
+enum AmountFormat {
+    /// All fractional digits, e.g. 0.0500 or 1.0000
+    Fixed,
+    /// All fractional digits, but whole amounts with single zero as reports always had, e.g. 1.0
+    Legacy,
+    /// Without trailing zeros, e.g. 0.05
+    Trimmed,
+}
+
+/// Get file with transactions and options of processing with files they refer read
+fn prepare(args: Args) -> anyhow::Result<(String, Options)> {
+    // Clap ensures file is given, it is optional only to let run subcommands
+    let file = args
+        .transactions
+        .clone()
+        .ok_or_else(|| anyhow!("missed file with transactions"))?;
+    let options = prepare_options(args)?;
+
+    Ok((file, options))
+}
+
+/// Get options of processing with files they refer read
+fn prepare_options(args: Args) -> anyhow::Result<Options> {
+    let mut options = args.processing.into_options()?;
+    options.format = Format {
+        scale: match args.amount_format {
+            AmountFormat::Fixed => Scale::Fixed,
+            AmountFormat::Legacy => Scale::Legacy,
+            AmountFormat::Trimmed => Scale::Trimmed,
+        },
+        thousands_separator: args.thousands_separator,
+        decimal_mark: args.decimal_mark,
+    };
+    options.report_status = args.with_status;
+    options.metrics = args.metrics.as_ref().map(|_| Arc::new(Metrics::new()));
+
+    Ok(options)
+}
+
+/// Prepare processing and run it, metrics are exported even if it failed
+fn run_prepared<T>(
+    args: Args,
+    run: impl FnOnce(&str, &Options) -> anyhow::Result<T>,
+) -> anyhow::Result<T> {
+    let metrics_file = args.metrics.clone();
+    let (file, options) = prepare(args)?;
+
+    let result = run(&file, &options);
+
+    if let (Some(path), Some(metrics)) = (metrics_file, &options.metrics) {
+        write_metrics(&path, metrics)?;
+    }
+
+    result
+}
+
+fn run(cli: Cli) -> anyhow::Result<()> {
+    match cli.command {
+        None => {
+            let summary = run_prepared(cli.args, process)?;
+            report_summary(&summary, cli.summary.as_deref(), cli.quiet == 0)
+        }
+        Some(Command::Statement {
+            clients,
+            output,
+            args,
+        }) => run_prepared(args, |file, options| {
+            print_statement(file, &clients, options, output.into())
+        }),
+        Some(Command::Reconcile { args }) => run_prepared(args, print_reconciliation),
+        Some(Command::Serve {
+            address,
+            threads,
+            args,
+        }) => {
+            // Server runs until it is killed, so file would never be written
+            if args.metrics.is_some() {
+                return Err(anyhow!(
+                    "option --metrics cannot be used with serve, metrics are given by GET /metrics"
+                ));
+            }
+            let file = args.transactions.clone();
+            let mut options = prepare_options(args)?;
+            // Metrics are always served
+            options
+                .metrics
+                .get_or_insert_with(|| Arc::new(Metrics::new()));
+            serve(&address, file.as_deref(), &options, threads)
+        }
+    }
+}
+
+fn write_metrics(path: &str, metrics: &Metrics) -> anyhow::Result<()> {
+    let file =
+        File::create(path).with_context(|| format!("cannot create metrics file: {:?}", path))?;
+    let mut writer = BufWriter::new(file);
+    metrics.write(&mut writer)?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Send logs to stderr, warnings are logged by default
+fn init_logging(verbose: u8, quiet: u8, format: LogFormat) {
+    let level = match (verbose, quiet) {
+        (0, 0) => LevelFilter::WARN,
+        (0, 1) => LevelFilter::ERROR,
+        (0, _) => LevelFilter::OFF,
+        (1, _) => LevelFilter::INFO,
+        (2, _) => LevelFilter::DEBUG,
+        _ => LevelFilter::TRACE,
+    };
+
+    let builder = tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_writer(io::stderr)
+        .with_ansi(io::stderr().is_terminal());
+    match format {
+        LogFormat::Text => builder.init(),
+        LogFormat::Json => builder.json().init(),
+    }
+}
+
+/// Write summary as json to given file or print it to stderr unless quiet
+fn report_summary(
+    summary: &ProcessingSummary,
+    path: Option<&str>,
+    print: bool,
+) -> anyhow::Result<()> {
+    let path = match path {
+        Some(path) => path,
+        None => {
+            if print {
+                eprintln!("{}", summary);
+            }
+            return Ok(());
+        }
+    };
+
+    let file =
+        File::create(path).with_context(|| format!("cannot create summary file: {:?}", path))?;
+    let mut writer = BufWriter::new(file);
+    serde_json::to_writer_pretty(&mut writer, summary)?;
+    writeln!(writer)?;
+    writer.flush()?;
+
+    Ok(())
+}
 
 fn main() {
-    let file = env::args().nth(1).unwrap_or_else(|| {
-        eprintln!("Usage: <executable> <transactions>");
-        process::exit(1);
-    });
+    let cli = Cli::parse();
+    init_logging(cli.verbose, cli.quiet, cli.log_format);
 
-    if let Err(err) = process(&file) {
+    if let Err(err) = run(cli) {
         eprintln!("Error: {:?}", err);
         process::exit(1);
     }
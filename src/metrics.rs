@@ -0,0 +1,243 @@
+//! Counters of processing exported in Prometheus text format
+
+// Standard paths
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::io;
+use std::io::Write;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Mutex;
+use std::time::Duration;
+
+// Crate paths
+use crate::api::engine::Engine;
+use crate::api::error::TransactionsProcessorError;
+use crate::api::transactions::Type;
+
+/// Upper bounds of buckets of latency of single record in seconds
+const LATENCY_BOUNDS: [f64; 10] = [
+    0.000_001, 0.000_005, 0.000_01, 0.000_05, 0.000_1, 0.000_5, 0.001, 0.005, 0.01, 0.1,
+];
+
+/// Counters shared by threads which process records
+///
+/// State of accounts is not counted per record, it is taken from engine by
+/// `observe` instead.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    records: Mutex<BTreeMap<Type, u64>>,
+    errors: Mutex<BTreeMap<&'static str, u64>>,
+    rounded: AtomicU64,
+    latency: Histogram,
+    operations: AtomicU64,
+    accounts: Mutex<BTreeMap<&'static str, u64>>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count record applied by engine
+    pub fn applied(&self, type_: Type, latency: Duration) {
+        // Panic if lock is poisoned
+        *self.records.lock().unwrap().entry(type_).or_default() += 1;
+        self.latency.observe(latency);
+    }
+
+    /// Count record rejected with given error
+    pub fn rejected(&self, err: &TransactionsProcessorError, latency: Duration) {
+        // Panic if lock is poisoned
+        *self.errors.lock().unwrap().entry(err.code()).or_default() += 1;
+        self.latency.observe(latency);
+    }
+
+    pub fn rounded(&self) {
+        self.rounded.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Take state of accounts of engine at single moment
+    pub fn observe(&self, engine: &Engine) {
+        let snapshot = engine.snapshot();
+
+        let mut accounts = BTreeMap::new();
+        for status in ["active", "locked", "frozen", "closed"] {
+            accounts.insert(status, 0);
+        }
+        for (_, account) in snapshot.accounts() {
+            *accounts.entry(account.status.name()).or_default() += 1;
+        }
+
+        self.operations
+            .store(engine.operations(), Ordering::Relaxed);
+        // Panic if lock is poisoned
+        *self.accounts.lock().unwrap() = accounts;
+    }
+
+    /// Write all metrics, state of accounts is the one last observed
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writeln!(
+            writer,
+            "# HELP transactions_processor_records_total Records applied by engine."
+        )?;
+        writeln!(
+            writer,
+            "# TYPE transactions_processor_records_total counter"
+        )?;
+        // Panic if lock is poisoned
+        for (type_, count) in self.records.lock().unwrap().iter() {
+            writeln!(
+                writer,
+                "transactions_processor_records_total{{type=\"{}\"}} {}",
+                type_, count
+            )?;
+        }
+
+        writeln!(
+            writer,
+            "# HELP transactions_processor_errors_total Records rejected by error."
+        )?;
+        writeln!(writer, "# TYPE transactions_processor_errors_total counter")?;
+        // Panic if lock is poisoned
+        for (code, count) in self.errors.lock().unwrap().iter() {
+            writeln!(
+                writer,
+                "transactions_processor_errors_total{{code=\"{}\"}} {}",
+                code, count
+            )?;
+        }
+
+        writeln!(
+            writer,
+            "# HELP transactions_processor_rounded_total Records with rounded amount."
+        )?;
+        writeln!(
+            writer,
+            "# TYPE transactions_processor_rounded_total counter"
+        )?;
+        writeln!(
+            writer,
+            "transactions_processor_rounded_total {}",
+            self.rounded.load(Ordering::Relaxed)
+        )?;
+
+        self.latency
+            .write(writer, "transactions_processor_record_duration_seconds")?;
+
+        writeln!(
+            writer,
+            "# HELP transactions_processor_operations_total Operations run by engine, rejected included."
+        )?;
+        writeln!(
+            writer,
+            "# TYPE transactions_processor_operations_total counter"
+        )?;
+        writeln!(
+            writer,
+            "transactions_processor_operations_total {}",
+            self.operations.load(Ordering::Relaxed)
+        )?;
+
+        writeln!(
+            writer,
+            "# HELP transactions_processor_accounts Accounts by status."
+        )?;
+        writeln!(writer, "# TYPE transactions_processor_accounts gauge")?;
+        // Panic if lock is poisoned
+        for (status, count) in self.accounts.lock().unwrap().iter() {
+            writeln!(
+                writer,
+                "transactions_processor_accounts{{status=\"{}\"}} {}",
+                status, count
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Cumulative buckets of durations
+#[derive(Debug)]
+struct Histogram {
+    buckets: [AtomicU64; LATENCY_BOUNDS.len()],
+    count: AtomicU64,
+    sum_nanos: AtomicU64,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self {
+            buckets: Default::default(),
+            count: AtomicU64::new(0),
+            sum_nanos: AtomicU64::new(0),
+        }
+    }
+}
+
+impl Histogram {
+    fn observe(&self, duration: Duration) {
+        let seconds = duration.as_secs_f64();
+        // Only first matching bucket is counted, they are summed when written
+        if let Some(index) = LATENCY_BOUNDS.iter().position(|bound| seconds <= *bound) {
+            self.buckets[index].fetch_add(1, Ordering::Relaxed);
+        }
+        self.count.fetch_add(1, Ordering::Relaxed);
+        let nanos = u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX);
+        self.sum_nanos.fetch_add(nanos, Ordering::Relaxed);
+    }
+
+    fn write<W: Write>(&self, writer: &mut W, name: &str) -> io::Result<()> {
+        writeln!(writer, "# HELP {} Time of processing single record.", name)?;
+        writeln!(writer, "# TYPE {} histogram", name)?;
+        let mut cumulative = 0;
+        for (bound, bucket) in LATENCY_BOUNDS.iter().zip(&self.buckets) {
+            cumulative += bucket.load(Ordering::Relaxed);
+            writeln!(writer, "{}_bucket{{le=\"{}\"}} {}", name, bound, cumulative)?;
+        }
+        let count = self.count.load(Ordering::Relaxed);
+        writeln!(writer, "{}_bucket{{le=\"+Inf\"}} {}", name, count)?;
+        let sum = Duration::from_nanos(self.sum_nanos.load(Ordering::Relaxed));
+        writeln!(writer, "{}_sum {}", name, sum.as_secs_f64())?;
+        writeln!(writer, "{}_count {}", name, count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::currency::Currency;
+    use crate::api::engine::error::EngineError;
+
+    #[test]
+    fn write_metrics() {
+        let engine = Engine::new();
+        engine.deposit(1, 1, Currency::new(1, 0).unwrap()).unwrap();
+
+        let metrics = Metrics::new();
+        metrics.applied(Type::Deposit, Duration::from_micros(2));
+        metrics.applied(Type::Deposit, Duration::from_secs(1));
+        metrics.rejected(
+            &TransactionsProcessorError::NestedEngineError(EngineError::AccountLocked(1)),
+            Duration::from_micros(20),
+        );
+
+        metrics.observe(&engine);
+        let mut output = Vec::new();
+        metrics.write(&mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(output.contains("transactions_processor_records_total{type=\"deposit\"} 2\n"));
+        assert!(output.contains("transactions_processor_errors_total{code=\"AccountLocked\"} 1\n"));
+        assert!(output.contains(
+            "transactions_processor_record_duration_seconds_bucket{le=\"0.000005\"} 1\n"
+        ));
+        assert!(output
+            .contains("transactions_processor_record_duration_seconds_bucket{le=\"0.00005\"} 2\n"));
+        assert!(output
+            .contains("transactions_processor_record_duration_seconds_bucket{le=\"+Inf\"} 3\n"));
+        assert!(output.contains("transactions_processor_operations_total 1\n"));
+        assert!(output.contains("transactions_processor_accounts{status=\"active\"} 1\n"));
+        assert!(output.contains("transactions_processor_accounts{status=\"locked\"} 0\n"));
+    }
+}
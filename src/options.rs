@@ -0,0 +1,29 @@
+//! Options to tune processing of transactions
+
+// Standard paths
+use std::sync::Arc;
+
+// Crate paths
+use crate::api::asset::AssetOptions;
+use crate::api::currency::format::Format;
+use crate::api::currency::parse::ParseOptions;
+use crate::api::engine::limits::LimitRule;
+use crate::api::engine::options::EngineOptions;
+use crate::metrics::Metrics;
+
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    pub engine: EngineOptions,
+    /// How to read amounts from input records
+    pub parse: ParseOptions,
+    /// How to write amounts in report
+    pub format: Format,
+    /// Settings of particular assets
+    pub assets: AssetOptions,
+    /// Rules checked before operations are applied by engine
+    pub limits: Vec<LimitRule>,
+    /// Write status of accounts and reason of it in report
+    pub report_status: bool,
+    /// Counters of processed records, e.g. to be exported at exit
+    pub metrics: Option<Arc<Metrics>>,
+}
@@ -0,0 +1,55 @@
+//! Report of reconciliation of funds of engine
+
+// Standard paths
+use std::io;
+use std::io::Write;
+
+// Crate paths
+use crate::api::engine::reconcile::Reconciliation;
+use crate::csv_field;
+use crate::options::Options;
+
+/// Write one row of sums for each asset
+pub fn write_reconciliation<W: Write>(
+    reconciliation: &Reconciliation,
+    writer: &mut W,
+    options: &Options,
+) -> io::Result<()> {
+    let format = &options.format;
+
+    writeln!(
+        writer,
+        "asset, deposited, withdrawn, exchanged_in, exchanged_out, charged_back, returned, fees, available, held, disputed, total, balanced"
+    )?;
+
+    for asset in &reconciliation.assets {
+        let flows = &asset.flows;
+        let fields = [
+            format.format(flows.deposited),
+            format.format(flows.withdrawn),
+            format.format(flows.exchanged_in),
+            format.format(flows.exchanged_out),
+            format.format(flows.charged_back),
+            format.format(flows.returned),
+            flows
+                .fees()
+                .map(|fees| format.format(fees))
+                .unwrap_or_default(),
+            asset.available.format(format),
+            format.format(asset.held),
+            format.format(asset.disputed),
+            asset
+                .total()
+                .map(|total| total.format(format))
+                .unwrap_or_default(),
+        ];
+
+        write!(writer, "{}", csv_field(asset.asset.to_string()))?;
+        for field in fields {
+            write!(writer, ",{}", csv_field(field))?;
+        }
+        writeln!(writer, ",{}", asset.is_balanced())?;
+    }
+
+    Ok(())
+}
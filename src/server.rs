@@ -0,0 +1,433 @@
+//! Local http service applying transactions to shared engine
+
+// Standard paths
+use std::io::Read;
+use std::net::SocketAddr;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::thread;
+
+// Crate paths
+use crate::api::asset::Asset;
+use crate::api::currency::balance::Balance;
+use crate::api::currency::Currency;
+use crate::api::engine::account::Account;
+use crate::api::engine::account::AccountId;
+use crate::api::engine::Engine;
+use crate::api::error::TransactionsProcessorError;
+use crate::api::transactions::OwnedTransaction;
+use crate::api::transactions::Transaction;
+use crate::options::Options;
+use crate::service::Service;
+use crate::transactions_reader_builder;
+use crate::write_accounts;
+use crate::Processed;
+
+// External paths
+use serde::Serialize;
+use tiny_http::Header;
+use tiny_http::Method;
+use tiny_http::Request;
+use tiny_http::Response;
+use tracing::debug;
+use tracing::info;
+use tracing::warn;
+
+/// Name of source used in errors of batches
+const BATCH: &str = "request";
+
+/// Longest body of request which is read, bigger ones are refused
+pub const MAX_BODY_LENGTH: u64 = 16 * 1024 * 1024;
+
+/// Engine shared by all requests
+///
+/// Endpoints:
+/// - `POST /transaction` applies json transaction,
+/// - `POST /transactions` applies csv with header and any number of records,
+/// - `GET /accounts` and `GET /accounts/{client}` give accounts as json,
+/// - `GET /transactions/{tx}` gives recorded transaction as json,
+/// - `GET /report` gives the same csv as command line,
+/// - `GET /metrics` gives metrics if they are enabled by options.
+pub struct Server {
+    http: tiny_http::Server,
+    service: Service,
+    /// Threads which wait for requests and were not unblocked yet
+    workers: AtomicUsize,
+    stopped: AtomicBool,
+}
+
+/// What happened to single record
+#[derive(Debug, Serialize)]
+struct Outcome {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    line: Option<u64>,
+    applied: bool,
+    /// Amount which was applied instead of given one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rounded: Option<Currency>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    code: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl Outcome {
+    fn new(line: Option<u64>, processed: Processed) -> Self {
+        match processed {
+            Ok((_, rounded)) => Self {
+                line,
+                applied: true,
+                rounded: rounded.map(|rounded| rounded.rounded),
+                code: None,
+                error: None,
+            },
+            Err(err) => {
+                debug!(line, reason = ?err, "rejected record");
+                let rounded = match &err {
+                    TransactionsProcessorError::RejectedRoundedAmount { rounded, .. } => {
+                        Some(*rounded)
+                    }
+                    _ => None,
+                };
+                Self {
+                    line,
+                    applied: false,
+                    rounded,
+                    code: Some(err.code().to_string()),
+                    error: Some(format!("{:?}", err)),
+                }
+            }
+        }
+    }
+}
+
+/// Account together with its id and total
+#[derive(Debug, Serialize)]
+struct AccountView {
+    client: u16,
+    asset: Asset,
+    available: Balance,
+    held: Currency,
+    total: Option<Balance>,
+    locked: bool,
+    status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<String>,
+}
+
+impl AccountView {
+    fn new(id: &AccountId, account: &Account) -> Self {
+        Self {
+            client: id.client,
+            asset: id.asset.clone(),
+            available: account.available,
+            held: account.held,
+            total: account.total(),
+            locked: account.is_locked(),
+            status: account.status.name(),
+            reason: account.status.reason().map(str::to_string),
+        }
+    }
+}
+
+/// Error given instead of expected resource
+#[derive(Debug, Serialize)]
+struct Failure {
+    code: String,
+    error: String,
+}
+
+struct Reply {
+    status: u16,
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+impl Reply {
+    fn json<T: Serialize>(status: u16, value: &T) -> Self {
+        match serde_json::to_vec(value) {
+            Ok(body) => Self {
+                status,
+                content_type: "application/json",
+                body,
+            },
+            Err(err) => Self::failure(500, "CannotWriteOutput", format!("{:?}", err)),
+        }
+    }
+
+    fn text(content_type: &'static str, body: Vec<u8>) -> Self {
+        Self {
+            status: 200,
+            content_type,
+            body,
+        }
+    }
+
+    fn failure(status: u16, code: &str, error: String) -> Self {
+        let failure = Failure {
+            code: code.to_string(),
+            error,
+        };
+        Self {
+            status,
+            content_type: "application/json",
+            // Should never panic unless logic is buggy
+            body: serde_json::to_vec(&failure).unwrap(),
+        }
+    }
+
+    fn error(status: u16, err: &TransactionsProcessorError) -> Self {
+        Self::failure(status, err.code(), format!("{:?}", err))
+    }
+
+    fn not_found() -> Self {
+        Self::failure(404, "NotFound", String::from("no such resource"))
+    }
+}
+
+impl Server {
+    /// Listen on given address, e.g. `127.0.0.1:8080`, port 0 picks any free one
+    pub fn bind(
+        address: &str,
+        engine: Engine,
+        options: Options,
+    ) -> Result<Self, TransactionsProcessorError> {
+        let http = tiny_http::Server::http(address).map_err(|err| {
+            TransactionsProcessorError::CannotStartServer {
+                address: address.to_string(),
+                reason: err.to_string(),
+            }
+        })?;
+
+        Ok(Self {
+            http,
+            service: Service::new(engine, options),
+            workers: AtomicUsize::new(0),
+            stopped: AtomicBool::new(false),
+        })
+    }
+
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.http.server_addr().to_ip()
+    }
+
+    pub fn engine(&self) -> &Engine {
+        self.service.engine()
+    }
+
+    /// Handle requests with given number of threads until `stop` is called
+    ///
+    /// It returns at once if server was stopped already.
+    pub fn run(&self, threads: usize) {
+        info!(address = ?self.local_addr(), threads, "serving");
+
+        let threads = threads.max(1);
+        self.workers.fetch_add(threads, Ordering::SeqCst);
+        // Stop called before workers were counted has not unblocked them
+        if self.stopped.load(Ordering::SeqCst) {
+            self.stop();
+        }
+
+        thread::scope(|scope| {
+            for _ in 0..threads {
+                scope.spawn(|| {
+                    // Ends when worker is unblocked by stop
+                    for request in self.http.incoming_requests() {
+                        self.handle(request);
+                    }
+                });
+            }
+        });
+    }
+
+    /// Let all threads of `run` finish after requests they handle now
+    ///
+    /// It may be called before `run`, which then returns at once.
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        // Unblocking is queued, so workers which wait later still see it
+        for _ in 0..self.workers.swap(0, Ordering::SeqCst) {
+            self.http.unblock();
+        }
+    }
+
+    fn handle(&self, mut request: Request) {
+        let mut body = Vec::new();
+        // One byte more than limit tells that body is too long
+        let read = request
+            .as_reader()
+            .take(MAX_BODY_LENGTH + 1)
+            .read_to_end(&mut body);
+        let reply = match read {
+            Ok(length) if length as u64 > MAX_BODY_LENGTH => Self::too_large(),
+            Ok(_) => {
+                // Query is not used by any endpoint
+                let path = request.url().split('?').next().unwrap_or_default();
+                self.route(request.method(), path, &body)
+            }
+            Err(err) => Self::unreadable(err),
+        };
+
+        // Should never panic unless logic is buggy
+        let content_type = Header::from_bytes(&b"Content-Type"[..], reply.content_type).unwrap();
+        let response = Response::from_data(reply.body)
+            .with_status_code(reply.status)
+            .with_header(content_type);
+        if let Err(err) = request.respond(response) {
+            warn!(reason = ?err, "cannot respond to request");
+        }
+    }
+
+    fn unreadable(err: std::io::Error) -> Reply {
+        Reply::failure(400, "CannotReadRequest", format!("{:?}", err))
+    }
+
+    fn too_large() -> Reply {
+        Reply::failure(
+            413,
+            "RequestTooLarge",
+            format!("body is longer than: {} bytes", MAX_BODY_LENGTH),
+        )
+    }
+
+    fn route(&self, method: &Method, path: &str, body: &[u8]) -> Reply {
+        let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+        match (method, segments.as_slice()) {
+            (Method::Post, ["transaction"]) => self.submit(body),
+            (Method::Post, ["transactions"]) => self.submit_batch(body),
+            (Method::Get, ["accounts"]) => self.accounts(None),
+            (Method::Get, ["accounts", client]) => match client.parse() {
+                Ok(client) => self.accounts(Some(client)),
+                Err(_) => Reply::not_found(),
+            },
+            (Method::Get, ["transactions", tx]) => match tx.parse() {
+                Ok(tx) => self.transaction(tx),
+                Err(_) => Reply::not_found(),
+            },
+            (Method::Get, ["report"]) => self.report(),
+            (Method::Get, ["metrics"]) => self.metrics(),
+            _ => Reply::not_found(),
+        }
+    }
+
+    fn submit(&self, body: &[u8]) -> Reply {
+        let parsed = serde_json::from_slice::<OwnedTransaction>(body);
+        // Malformed request is not a transaction which engine rejected
+        let malformed = parsed.is_err();
+        let processed = match parsed {
+            Ok(owned) => self.service.submit_with(|| Ok(owned.as_transaction())),
+            Err(source) => self
+                .service
+                .submit_with(|| Err(TransactionsProcessorError::CannotDeserializeJson { source })),
+        };
+
+        let outcome = Outcome::new(None, processed);
+        let status = match (outcome.applied, malformed) {
+            (true, _) => 200,
+            (false, true) => 400,
+            (false, false) => 422,
+        };
+        Reply::json(status, &outcome)
+    }
+
+    /// Apply records in order they are given, each is accepted or rejected alone
+    fn submit_batch(&self, body: &[u8]) -> Reply {
+        let mut rdr = transactions_reader_builder().from_reader(body);
+        let headers = match rdr.byte_headers() {
+            Ok(headers) => headers.clone(),
+            Err(source) => {
+                let err = TransactionsProcessorError::CannotReadInputFileHeaders {
+                    file: BATCH.to_string(),
+                    source,
+                };
+                return Reply::error(400, &err);
+            }
+        };
+
+        let mut outcomes = Vec::new();
+        let mut raw_record = csv::ByteRecord::new();
+        loop {
+            match rdr.read_byte_record(&mut raw_record) {
+                // Records already applied are still reported
+                Err(source) => {
+                    let err = TransactionsProcessorError::CannotReadInputFileRecord {
+                        file: BATCH.to_string(),
+                        source,
+                    };
+                    outcomes.push(Outcome::new(None, Err(err)));
+                    break;
+                }
+                Ok(false) => break,
+                Ok(true) => {
+                    let line = raw_record.position().map(|position| position.line());
+                    let processed = self.service.submit_with(|| {
+                        raw_record
+                            .deserialize::<Transaction>(Some(&headers))
+                            .map_err(
+                                |source| TransactionsProcessorError::CannotDeserializeRecord {
+                                    file: BATCH.to_string(),
+                                    source,
+                                },
+                            )
+                    });
+                    outcomes.push(Outcome::new(line, processed));
+                }
+            }
+        }
+
+        Reply::json(200, &outcomes)
+    }
+
+    fn accounts(&self, client: Option<u16>) -> Reply {
+        // Consistent view even if other requests apply transactions meanwhile
+        let snapshot = self.engine().snapshot();
+        let accounts: Vec<AccountView> = snapshot
+            .accounts()
+            .iter()
+            .filter(|(id, _)| client.is_none_or(|client| id.client == client))
+            .map(|(id, account)| AccountView::new(id, account))
+            .collect();
+
+        if client.is_some() && accounts.is_empty() {
+            return Reply::not_found();
+        }
+        Reply::json(200, &accounts)
+    }
+
+    fn transaction(&self, tx: u32) -> Reply {
+        match self.engine().get_transaction(tx) {
+            Some(transaction) => Reply::json(200, &transaction),
+            None => Reply::not_found(),
+        }
+    }
+
+    fn report(&self) -> Reply {
+        let mut body = Vec::new();
+        match write_accounts(self.engine(), &mut body, self.service.options()) {
+            Ok(()) => Reply::text("text/csv", body),
+            Err(source) => Reply::error(
+                500,
+                &TransactionsProcessorError::CannotWriteOutput { source },
+            ),
+        }
+    }
+
+    fn metrics(&self) -> Reply {
+        let metrics = match &self.service.options().metrics {
+            Some(metrics) => metrics,
+            None => return Reply::not_found(),
+        };
+
+        metrics.observe(self.engine());
+        let mut body = Vec::new();
+        match metrics.write(&mut body) {
+            Ok(()) => Reply::text("text/plain; version=0.0.4", body),
+            Err(source) => Reply::error(
+                500,
+                &TransactionsProcessorError::CannotWriteOutput { source },
+            ),
+        }
+    }
+}
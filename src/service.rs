@@ -0,0 +1,67 @@
+//! Engine applying transactions submitted one by one, e.g. by requests of servers
+
+// Standard paths
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+
+// Crate paths
+use crate::api::engine::limits::Limiter;
+use crate::api::engine::Engine;
+use crate::api::error::TransactionsProcessorError;
+use crate::api::transactions::Transaction;
+use crate::options::Options;
+use crate::Processed;
+use crate::Processor;
+
+/// Everything needed to apply transactions outside of file, shared by threads
+pub struct Service {
+    engine: Engine,
+    limiter: Limiter,
+    options: Options,
+    /// Transactions submitted so far, limits count them as records
+    records: AtomicU64,
+}
+
+impl Service {
+    pub fn new(engine: Engine, options: Options) -> Self {
+        let limiter = Limiter::new(options.limits.clone());
+
+        Self {
+            engine,
+            limiter,
+            options,
+            records: AtomicU64::new(0),
+        }
+    }
+
+    pub fn engine(&self) -> &Engine {
+        &self.engine
+    }
+
+    pub fn options(&self) -> &Options {
+        &self.options
+    }
+
+    /// Apply transaction as next record
+    pub fn submit(&self, transaction: &Transaction) -> Processed {
+        self.submit_with(|| Ok(transaction.clone()))
+    }
+
+    /// Apply transaction as next record once it is read, failed reading is counted as rejection
+    pub fn submit_with<'a>(
+        &self,
+        read: impl FnOnce() -> Result<Transaction<'a>, TransactionsProcessorError>,
+    ) -> Processed {
+        let record = self.records.fetch_add(1, Ordering::Relaxed) + 1;
+        let processor = Processor {
+            engine: &self.engine,
+            limiter: &self.limiter,
+            options: &self.options,
+        };
+
+        processor.measured(|| {
+            let transaction = read()?;
+            processor.apply(&transaction, record)
+        })
+    }
+}
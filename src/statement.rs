@@ -0,0 +1,88 @@
+//! Statements with history of operations of clients
+
+// Standard paths
+use std::io;
+use std::io::Write;
+
+// Crate paths
+use crate::api::engine::history::Entry;
+use crate::api::engine::Engine;
+use crate::csv_field;
+use crate::options::Options;
+
+/// How to write statements
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum StatementFormat {
+    #[default]
+    Csv,
+    Json,
+}
+
+/// Write history of given clients, one after another in order of `clients`
+pub fn write_statement<W: Write>(
+    engine: &Engine,
+    clients: &[u16],
+    writer: &mut W,
+    options: &Options,
+    format: StatementFormat,
+) -> io::Result<()> {
+    let entries: Vec<Entry> = clients
+        .iter()
+        .flat_map(|client| engine.statement(*client))
+        .collect();
+
+    match format {
+        StatementFormat::Csv => write_csv(&entries, writer, options),
+        StatementFormat::Json => {
+            serde_json::to_writer_pretty(&mut *writer, &entries)?;
+            writeln!(writer)
+        }
+    }
+}
+
+fn write_csv<W: Write>(entries: &[Entry], writer: &mut W, options: &Options) -> io::Result<()> {
+    let format = &options.format;
+
+    // Asset column is written only if input had any as in report of accounts
+    let with_assets = entries.iter().any(|entry| !entry.asset.is_default());
+
+    // Write csv header
+    if with_assets {
+        writeln!(
+            writer,
+            "client, asset, tx, event, amount, available, held, reason"
+        )?;
+    } else {
+        writeln!(writer, "client, tx, event, amount, available, held, reason")?;
+    }
+
+    for entry in entries {
+        if with_assets {
+            write!(
+                writer,
+                "{},{},",
+                entry.client,
+                csv_field(entry.asset.to_string())
+            )?;
+        } else {
+            write!(writer, "{},", entry.client)?;
+        }
+        writeln!(
+            writer,
+            "{},{},{},{},{},{}",
+            entry.tx,
+            entry.event,
+            csv_field(
+                entry
+                    .amount
+                    .map(|amount| amount.format(format))
+                    .unwrap_or_default()
+            ),
+            csv_field(entry.available.format(format)),
+            csv_field(format.format(entry.held)),
+            csv_field(entry.reason.clone().unwrap_or_default()),
+        )?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,252 @@
+//! Async api applying streams of transactions to shared engine
+
+// Standard paths
+use std::io;
+use std::panic;
+use std::sync::Arc;
+
+// Crate paths
+use crate::api::error::TransactionsProcessorError;
+use crate::api::transactions::OwnedTransaction;
+use crate::api::transactions::Transaction;
+use crate::service::Service;
+use crate::transactions_reader_builder;
+use crate::Processed;
+
+// External paths
+use futures_core::Stream;
+use futures_util::stream;
+use futures_util::StreamExt;
+use tokio::io::AsyncRead;
+use tokio::sync::mpsc;
+use tokio::task;
+use tokio_util::io::SyncIoBridge;
+
+/// Result of single record of stream
+#[derive(Debug)]
+pub struct RecordResult {
+    /// Number of record in stream starting from 1, 0 if error is not caused by any record
+    pub record: u64,
+    /// Line of csv input if record was read from it
+    pub line: Option<u64>,
+    pub result: Processed,
+}
+
+/// Apply transactions in order they come on blocking thread, results are yielded in the same order
+///
+/// Next transaction is taken only once result of previous one is consumed, so
+/// slow consumer holds producer back. Panics if polled outside of tokio runtime.
+pub fn process_stream<S>(service: Arc<Service>, transactions: S) -> impl Stream<Item = RecordResult>
+where
+    S: Stream<Item = OwnedTransaction>,
+{
+    transactions.enumerate().then(move |(index, transaction)| {
+        let service = Arc::clone(&service);
+        async move {
+            // Engine may wait for its locks, which must not stop async workers
+            let submitted =
+                task::spawn_blocking(move || service.submit(&transaction.as_transaction())).await;
+            let result = match submitted {
+                Ok(result) => result,
+                // Panic of engine is passed on to consumer
+                Err(err) => panic::resume_unwind(err.into_panic()),
+            };
+
+            RecordResult {
+                record: index as u64 + 1,
+                line: None,
+                result,
+            }
+        }
+    })
+}
+
+/// Read csv with header from async source and apply its records on blocking thread
+///
+/// At most `buffer` results wait for consumer, reading stops until they are
+/// consumed. Failed reading of csv is the last result. `name` is only used to
+/// describe source in errors. Panics if called outside of tokio runtime.
+pub fn process_csv<R>(
+    service: Arc<Service>,
+    reader: R,
+    name: &str,
+    buffer: usize,
+) -> impl Stream<Item = RecordResult>
+where
+    R: AsyncRead + Unpin + Send + 'static,
+{
+    let (sender, receiver) = mpsc::channel(buffer.max(1));
+    let reader = SyncIoBridge::new(reader);
+    let name = name.to_string();
+
+    task::spawn_blocking(move || read_csv(&service, reader, &name, &sender));
+
+    stream::unfold(receiver, |mut receiver| async move {
+        receiver.recv().await.map(|result| (result, receiver))
+    })
+}
+
+/// Apply records until input ends, reading fails or consumer is gone
+fn read_csv<R: io::Read>(
+    service: &Service,
+    reader: R,
+    name: &str,
+    sender: &mpsc::Sender<RecordResult>,
+) {
+    let mut rdr = transactions_reader_builder().from_reader(reader);
+
+    let headers = match rdr.byte_headers() {
+        Ok(headers) => headers.clone(),
+        Err(source) => {
+            let result = Err(TransactionsProcessorError::CannotReadInputFileHeaders {
+                file: name.to_string(),
+                source,
+            });
+            // Nothing more to do if consumer is gone
+            let _ = sender.blocking_send(RecordResult {
+                record: 0,
+                line: None,
+                result,
+            });
+            return;
+        }
+    };
+
+    let mut raw_record = csv::ByteRecord::new();
+    let mut record = 0;
+    loop {
+        record += 1;
+        let (result, fatal) = match rdr.read_byte_record(&mut raw_record) {
+            Ok(false) => return,
+            Ok(true) => {
+                let result = service.submit_with(|| {
+                    raw_record
+                        .deserialize::<Transaction>(Some(&headers))
+                        .map_err(
+                            |source| TransactionsProcessorError::CannotDeserializeRecord {
+                                file: name.to_string(),
+                                source,
+                            },
+                        )
+                });
+                (result, false)
+            }
+            // Any following records cannot be trusted
+            Err(source) => {
+                let result = Err(TransactionsProcessorError::CannotReadInputFileRecord {
+                    file: name.to_string(),
+                    source,
+                });
+                (result, true)
+            }
+        };
+
+        let line = raw_record.position().map(|position| position.line());
+        // Waits while consumer does not take results
+        let sent = sender.blocking_send(RecordResult {
+            record,
+            line,
+            result,
+        });
+        if sent.is_err() || fatal {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::engine::Engine;
+    use crate::api::transactions::Type;
+    use crate::options::Options;
+    use assert_matches::assert_matches;
+    use std::io::Cursor;
+    use tokio::runtime;
+
+    fn service() -> Arc<Service> {
+        Arc::new(Service::new(Engine::new(), Options::default()))
+    }
+
+    fn collect<S: Stream<Item = RecordResult>>(results: S) -> Vec<RecordResult> {
+        // Should never panic unless logic is buggy
+        let runtime = runtime::Builder::new_current_thread().build().unwrap();
+        runtime.block_on(results.collect())
+    }
+
+    #[test]
+    fn stream_of_transactions() {
+        let service = service();
+        let transactions = stream::iter(vec![
+            OwnedTransaction::new(Type::Deposit, 1, 1, Some("2.0")),
+            OwnedTransaction::new(Type::Withdrawal, 1, 2, Some("3.0")),
+            OwnedTransaction::new(Type::Withdrawal, 1, 3, Some("1.5")),
+        ]);
+
+        let results = collect(process_stream(Arc::clone(&service), transactions));
+
+        assert_eq!(results.len(), 3);
+        assert_matches!(results[0].result, Ok((Type::Deposit, None)));
+        assert_eq!(results[1].record, 2);
+        assert!(results[1].result.is_err());
+        assert_matches!(results[2].result, Ok((Type::Withdrawal, None)));
+        assert_eq!(
+            service
+                .engine()
+                .get_account(1)
+                .unwrap()
+                .available
+                .to_string(),
+            "0.5000"
+        );
+    }
+
+    #[test]
+    fn csv_of_async_reader() {
+        let service = service();
+        let input = "type, client, tx, amount\ndeposit, 1, 1, 2.0\nwithdrawal, 1, 2\n";
+        let runtime = runtime::Builder::new_current_thread().build().unwrap();
+
+        let results: Vec<RecordResult> = runtime.block_on(async {
+            // Results wait one by one for consumer
+            process_csv(
+                Arc::clone(&service),
+                Cursor::new(input.as_bytes().to_vec()),
+                "input",
+                1,
+            )
+            .collect()
+            .await
+        });
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].line, Some(2));
+        assert_matches!(results[0].result, Ok((Type::Deposit, None)));
+        assert_matches!(
+            results[1].result,
+            Err(TransactionsProcessorError::MissedMandatoryAmountInInputRecord)
+        );
+    }
+
+    #[test]
+    fn csv_without_header() {
+        let results = collect_csv("");
+        assert_eq!(results.len(), 0);
+    }
+
+    #[test]
+    fn csv_with_unterminated_quote() {
+        let results = collect_csv("type, client\n\"");
+        assert_eq!(results.len(), 1);
+        assert_matches!(
+            results[0].result,
+            Err(TransactionsProcessorError::CannotDeserializeRecord { .. })
+        );
+    }
+
+    fn collect_csv(input: &str) -> Vec<RecordResult> {
+        let runtime = runtime::Builder::new_current_thread().build().unwrap();
+        let input = Cursor::new(input.as_bytes().to_vec());
+        runtime.block_on(async { process_csv(service(), input, "input", 4).collect().await })
+    }
+}
@@ -0,0 +1,92 @@
+//! Statistics of single run of processing
+
+// Standard paths
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::Duration;
+
+// Crate paths
+use crate::api::error::TransactionsProcessorError;
+use crate::api::transactions::Type;
+
+// External paths
+use serde::Serialize;
+
+/// What happened to records of input
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ProcessingSummary {
+    pub records_read: u64,
+    /// Records applied by engine for each type of transaction
+    pub applied: BTreeMap<Type, u64>,
+    /// Records rejected for each code of error
+    pub rejected: BTreeMap<&'static str, u64>,
+    /// Applied records which amount had to be rounded
+    pub rounded: u64,
+    pub accounts_created: u64,
+    pub accounts_locked: u64,
+    pub elapsed_seconds: f64,
+    /// Records read per second
+    pub throughput: f64,
+}
+
+impl ProcessingSummary {
+    pub fn applied(&mut self, type_: Type) {
+        *self.applied.entry(type_).or_default() += 1;
+    }
+
+    pub fn rejected(&mut self, err: &TransactionsProcessorError) {
+        *self.rejected.entry(err.code()).or_default() += 1;
+    }
+
+    /// Set time of whole run and throughput based on it
+    pub fn finish(&mut self, elapsed: Duration) {
+        self.elapsed_seconds = elapsed.as_secs_f64();
+        self.throughput = if self.elapsed_seconds > 0.0 {
+            self.records_read as f64 / self.elapsed_seconds
+        } else {
+            0.0
+        };
+    }
+}
+
+impl fmt::Display for ProcessingSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "SUMMARY:")?;
+        writeln!(f, "records read: {}", self.records_read)?;
+        for (type_, count) in &self.applied {
+            writeln!(f, "applied {}: {}", type_, count)?;
+        }
+        for (code, count) in &self.rejected {
+            writeln!(f, "rejected {}: {}", code, count)?;
+        }
+        writeln!(f, "rounded: {}", self.rounded)?;
+        writeln!(f, "accounts created: {}", self.accounts_created)?;
+        writeln!(f, "accounts locked: {}", self.accounts_locked)?;
+        writeln!(f, "elapsed: {:.3}s", self.elapsed_seconds)?;
+        write!(f, "throughput: {:.0} records/s", self.throughput)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::engine::error::EngineError;
+
+    #[test]
+    fn count_records() {
+        let mut summary = ProcessingSummary::default();
+        summary.applied(Type::Deposit);
+        summary.applied(Type::Deposit);
+        summary.rejected(&TransactionsProcessorError::NestedEngineError(
+            EngineError::AccountLocked(1),
+        ));
+        summary.rejected(&TransactionsProcessorError::MissedMandatoryAmountInInputRecord);
+        summary.records_read = 4;
+        summary.finish(Duration::from_secs(2));
+
+        assert_eq!(summary.applied[&Type::Deposit], 2);
+        assert_eq!(summary.rejected["AccountLocked"], 1);
+        assert_eq!(summary.rejected["MissedMandatoryAmountInInputRecord"], 1);
+        assert_eq!(summary.throughput, 2.0);
+    }
+}
@@ -0,0 +1,211 @@
+// Standard paths
+use std::error::Error;
+use std::io::Read;
+use std::io::Write;
+use std::net::SocketAddr;
+use std::net::TcpStream;
+use std::sync::Arc;
+use std::thread;
+use std::thread::JoinHandle;
+
+// Crate paths
+use transactions_processor::api::engine::Engine;
+use transactions_processor::metrics::Metrics;
+use transactions_processor::options::Options;
+use transactions_processor::server::Server;
+use transactions_processor::server::MAX_BODY_LENGTH;
+
+/// Send single request and get status and body of response
+fn request(
+    address: SocketAddr,
+    method: &str,
+    path: &str,
+    body: &str,
+) -> Result<(u16, String), Box<dyn Error>> {
+    let mut stream = TcpStream::connect(address)?;
+    write!(
+        stream,
+        "{} {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\nContent-Length: {}\r\n\r\n{}",
+        method,
+        path,
+        body.len(),
+        body
+    )?;
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response)?;
+    let (head, body) = response
+        .split_once("\r\n\r\n")
+        .ok_or("response without body")?;
+    let status = head.split(' ').nth(1).ok_or("response without status")?;
+
+    Ok((status.parse()?, body.to_string()))
+}
+
+/// Server running in its own thread
+struct Running {
+    server: Arc<Server>,
+    address: SocketAddr,
+    thread: JoinHandle<()>,
+}
+
+impl Running {
+    /// Stop server and wait until all its workers finish
+    fn stop(self) {
+        self.server.stop();
+        self.thread.join().unwrap();
+    }
+}
+
+/// Run server on any free port of loopback
+fn start() -> Result<Running, Box<dyn Error>> {
+    let options = Options {
+        metrics: Some(Arc::new(Metrics::new())),
+        ..Options::default()
+    };
+    let server = Arc::new(Server::bind("127.0.0.1:0", Engine::new(), options)?);
+    let address = server.local_addr().ok_or("server without ip address")?;
+
+    let running = Arc::clone(&server);
+    let thread = thread::spawn(move || running.run(2));
+
+    Ok(Running {
+        server,
+        address,
+        thread,
+    })
+}
+
+#[test]
+fn server_submit_and_query() -> Result<(), Box<dyn Error>> {
+    let server = start()?;
+    let address = server.address;
+
+    let (status, body) = request(
+        address,
+        "POST",
+        "/transaction",
+        r#"{"type": "deposit", "client": 1, "tx": 1, "amount": "10.0"}"#,
+    )?;
+    assert_eq!(status, 200);
+    assert_eq!(body, r#"{"applied":true}"#);
+
+    let (status, body) = request(
+        address,
+        "POST",
+        "/transaction",
+        r#"{"type": "withdrawal", "client": 1, "tx": 2, "amount": "20.0"}"#,
+    )?;
+    assert_eq!(status, 422);
+    assert!(body.contains(r#""code":"CannotWithdrawal""#));
+
+    let (status, body) = request(
+        address,
+        "POST",
+        "/transactions",
+        "type, client, tx, amount\ndeposit, 2, 3, 5.0\ndispute, 2, 3,\nresolve, 2, 9,\n",
+    )?;
+    assert_eq!(status, 200);
+    let outcomes: serde_json::Value = serde_json::from_str(&body)?;
+    assert_eq!(outcomes[0]["applied"], true);
+    assert_eq!(outcomes[1]["line"], 3);
+    assert_eq!(outcomes[2]["code"], "CannotFindTransaction");
+
+    let (status, body) = request(address, "GET", "/accounts/2", "")?;
+    assert_eq!(status, 200);
+    let accounts: serde_json::Value = serde_json::from_str(&body)?;
+    assert_eq!(accounts[0]["held"], "5.0000");
+    assert_eq!(accounts[0]["status"], "active");
+
+    let (status, body) = request(address, "GET", "/transactions/3", "")?;
+    assert_eq!(status, 200);
+    let transaction: serde_json::Value = serde_json::from_str(&body)?;
+    assert_eq!(transaction["disputed"], true);
+
+    let (status, _) = request(address, "GET", "/accounts/7", "")?;
+    assert_eq!(status, 404);
+
+    let (status, body) = request(address, "GET", "/report", "")?;
+    assert_eq!(status, 200);
+    assert_eq!(
+        body,
+        "client, available, held, total, locked\n1,10.0,0.0,10.0,false\n2,0.0,5.0,5.0,false\n"
+    );
+
+    let (status, body) = request(address, "GET", "/metrics", "")?;
+    assert_eq!(status, 200);
+    assert!(body.contains("transactions_processor_records_total{type=\"deposit\"} 2\n"));
+
+    server.stop();
+    Ok(())
+}
+
+#[test]
+fn server_rejects_invalid_json() -> Result<(), Box<dyn Error>> {
+    let server = start()?;
+    let address = server.address;
+
+    let (status, body) = request(address, "POST", "/transaction", "{")?;
+    assert_eq!(status, 400);
+    assert!(body.contains(r#""code":"CannotDeserializeJson""#));
+
+    server.stop();
+    Ok(())
+}
+
+#[test]
+fn server_accepts_numeric_amount_and_escaped_strings() -> Result<(), Box<dyn Error>> {
+    let server = start()?;
+    let address = server.address;
+
+    let deposit = r#"{"type": "deposit", "client": 1, "tx": 1, "amount": 10.5}"#;
+    let (status, _) = request(address, "POST", "/transaction", deposit)?;
+    assert_eq!(status, 200);
+
+    let lock = r#"{"type": "lock", "client": 1, "tx": 2, "reason": "said \"stop\""}"#;
+    let (status, _) = request(address, "POST", "/transaction", lock)?;
+    assert_eq!(status, 200);
+
+    let (status, body) = request(address, "GET", "/accounts/1", "")?;
+    assert_eq!(status, 200);
+    assert!(body.contains(r#""available":"10.5000""#));
+
+    let withdrawal = r#"{"type": "withdrawal", "client": 1, "tx": 3, "amount": 1}"#;
+    let (status, body) = request(address, "POST", "/transaction", withdrawal)?;
+    assert_eq!(status, 422);
+    assert!(body.contains(r#""code":"AccountLocked""#));
+
+    server.stop();
+    Ok(())
+}
+
+#[test]
+fn server_refuses_too_long_body() -> Result<(), Box<dyn Error>> {
+    let server = start()?;
+    let address = server.address;
+
+    let body = " ".repeat(MAX_BODY_LENGTH as usize + 1);
+    let (status, body) = request(address, "POST", "/transactions", &body)?;
+    assert_eq!(status, 413);
+    assert!(body.contains(r#""code":"RequestTooLarge""#));
+
+    server.stop();
+    Ok(())
+}
+
+#[test]
+fn server_stopped_before_run() -> Result<(), Box<dyn Error>> {
+    let server = Arc::new(Server::bind(
+        "127.0.0.1:0",
+        Engine::new(),
+        Options::default(),
+    )?);
+    server.stop();
+
+    let running = Arc::clone(&server);
+    thread::spawn(move || running.run(2)).join().unwrap();
+
+    // Stop may come before workers of run start waiting
+    start()?.stop();
+    Ok(())
+}
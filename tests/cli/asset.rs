@@ -0,0 +1,191 @@
+// Standard paths
+use std::error::Error;
+use std::process::Command; // Run programs // Used for writing assertions
+
+// Crate paths
+use crate::cli::common::prepare;
+use crate::cli::common::run_prepared_transactions;
+use crate::cli::common::run_prepared_transactions_with_args;
+
+// External paths
+use assert_cmd::prelude::*; // Add methods on commands
+use predicates::prelude::*;
+
+#[test]
+fn report_rows_per_asset() -> Result<(), Box<dyn Error>> {
+    let input = "type,       client,  tx, amount, asset
+deposit,         2,   1,    1.0,    USD
+deposit,         1,   2,    2.0,    USD
+deposit,         1,   3,    3.0,    EUR
+withdrawal,      1,   4,    1.0,    EUR
+dispute,         1,   3,
+";
+    let output = String::from(
+        "client, asset, available, held, total, locked
+1,EUR,-1.0,3.0,2.0,false
+1,USD,2.0,0.0,2.0,false
+2,USD,1.0,0.0,1.0,false
+",
+    );
+    run_prepared_transactions_with_args(
+        "report_rows_per_asset",
+        &["--allow-negative"],
+        input,
+        output,
+        "",
+    )?;
+    Ok(())
+}
+
+#[test]
+fn currency_column() -> Result<(), Box<dyn Error>> {
+    let input = "type,       client,  tx, amount, currency
+deposit,         1,   1,    1.0,    USD
+";
+    let output = String::from(
+        "client, asset, available, held, total, locked
+1,USD,1.0,0.0,1.0,false
+",
+    );
+    run_prepared_transactions("currency_column", input, output, "")?;
+    Ok(())
+}
+
+#[test]
+fn asset_from_currency_code() -> Result<(), Box<dyn Error>> {
+    let input = "type,       client,  tx, amount
+deposit,         1,   1,    1.0 USD
+deposit,         1,   2,    2.0
+";
+    let output = String::from(
+        "client, asset, available, held, total, locked
+1,,2.0,0.0,2.0,false
+1,USD,1.0,0.0,1.0,false
+",
+    );
+    run_prepared_transactions_with_args(
+        "asset_from_currency_code",
+        &["--amount-syntax", "lenient"],
+        input,
+        output,
+        "",
+    )?;
+    Ok(())
+}
+
+#[test]
+fn asset_mismatch_currency_code() -> Result<(), Box<dyn Error>> {
+    let input = "type,       client,  tx, amount, asset
+deposit,         1,   1,    1.0 EUR, USD
+";
+    let output = String::from(
+        "client, available, held, total, locked
+",
+    );
+    let stderr = "AssetMismatch";
+    run_prepared_transactions_with_args(
+        "asset_mismatch_currency_code",
+        &["--amount-syntax", "lenient"],
+        input,
+        output,
+        stderr,
+    )?;
+    Ok(())
+}
+
+#[test]
+fn asset_precision() -> Result<(), Box<dyn Error>> {
+    let input = "type,       client,  tx, amount, asset
+deposit,         1,   1,    1.25,   USD
+deposit,         1,   2,    1.255,  USD
+deposit,         1,   3,    100,    JPY
+deposit,         1,   4,    100.5,  JPY
+";
+    let output = String::from(
+        "client, asset, available, held, total, locked
+1,JPY,100.0,0.0,100.0,false
+1,USD,1.2500,0.0,1.2500,false
+",
+    );
+    let stderr = "FractionalTooLong";
+    run_prepared_transactions_with_args(
+        "asset_precision",
+        &["--asset-precision", "USD=2", "--asset-precision", "JPY=0"],
+        input,
+        output,
+        stderr,
+    )?;
+    Ok(())
+}
+
+#[test]
+fn asset_precision_above_scale() -> Result<(), Box<dyn Error>> {
+    let file = prepare(
+        "asset_precision_above_scale",
+        "type, client, tx, amount, asset\n",
+    )?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    cmd.args(["--asset-precision", "BTC=8"]);
+    cmd.arg(file);
+    cmd.assert()
+        .failure()
+        .stdout("")
+        .stderr(predicate::str::contains(
+            "digits: 8 exceed supported fractional digits: 4",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn exchange_with_rates_file() -> Result<(), Box<dyn Error>> {
+    let rates = prepare(
+        "exchange_with_rates_file_rates",
+        "from, to, rate
+USD,  EUR, 0.9
+",
+    )?;
+
+    let input = "type,       client,  tx, amount, asset, to_asset
+deposit,         1,   1,    10.0,   USD
+exchange,        1,   2,    5.0,    USD,   EUR
+exchange,        1,   3,    5.0,    EUR,   USD
+";
+    let output = String::from(
+        "client, asset, available, held, total, locked
+1,EUR,4.5000,0.0,4.5000,false
+1,USD,5.0,0.0,5.0,false
+",
+    );
+    let stderr = "CannotFindRate";
+    run_prepared_transactions_with_args(
+        "exchange_with_rates_file",
+        &["--rates", rates.to_str().unwrap()],
+        input,
+        output,
+        stderr,
+    )?;
+    Ok(())
+}
+
+#[test]
+fn rates_file_with_extra_field() -> Result<(), Box<dyn Error>> {
+    let rates = prepare(
+        "rates_file_with_extra_field_rates",
+        "from, to, rate
+USD,  EUR, 0.9, 1.1
+",
+    )?;
+    let file = prepare("rates_file_with_extra_field", "type, client, tx, amount\n")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    cmd.args(["--rates", rates.to_str().unwrap()]);
+    cmd.arg(file);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot read rates file"))
+        .stderr(predicate::str::contains("UnequalLengths"));
+
+    Ok(())
+}
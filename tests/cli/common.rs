@@ -0,0 +1,49 @@
+//! Helpers shared by tests of command line
+
+// Standard paths
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command; // Run programs // Used for writing assertions
+
+// External paths
+use assert_cmd::prelude::*; // Add methods on commands
+use predicates::prelude::*;
+
+/// Write input of test to its own file in tmp directory
+pub fn prepare(testname: &str, input: &str) -> Result<PathBuf, Box<dyn Error>> {
+    fs::create_dir_all("tmp")?;
+    let file = Path::new("tmp").join(testname);
+    fs::write(&file, input)?;
+    Ok(file)
+}
+
+pub fn run_prepared_transactions(
+    testname: &str,
+    input: &str,
+    output: String,
+    stderr: &str,
+) -> Result<(), Box<dyn Error>> {
+    run_prepared_transactions_with_args(testname, &[], input, output, stderr)
+}
+
+pub fn run_prepared_transactions_with_args(
+    testname: &str,
+    args: &[&str],
+    input: &str,
+    output: String,
+    stderr: &str,
+) -> Result<(), Box<dyn Error>> {
+    let file = prepare(testname, input)?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    cmd.args(args);
+    cmd.arg(file);
+    cmd.assert()
+        .success()
+        .stdout(output)
+        .stderr(predicate::str::contains(stderr));
+
+    Ok(())
+}
@@ -1,33 +1,16 @@
 // Standard paths
 use std::error::Error;
-use std::fs;
-use std::path::Path;
 use std::process::Command; // Run programs // Used for writing assertions
 
+// Crate paths
+use crate::cli::common::prepare;
+use crate::cli::common::run_prepared_transactions;
+use crate::cli::common::run_prepared_transactions_with_args;
+
 // External paths
 use assert_cmd::prelude::*; // Add methods on commands
 use predicates::prelude::*;
 
-fn run_prepared_transactions(
-    testname: &str,
-    input: &str,
-    output: String,
-    stderr: &str,
-) -> Result<(), Box<dyn Error>> {
-    fs::create_dir_all("tmp")?;
-    let file = Path::new("tmp").join(testname);
-    fs::write(&file, input)?;
-
-    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
-    cmd.arg(file);
-    cmd.assert()
-        .success()
-        .stdout(output)
-        .stderr(predicate::str::contains(stderr));
-
-    Ok(())
-}
-
 #[test]
 fn parse_decimal_out_of_range() -> Result<(), Box<dyn Error>> {
     let input = "type,       client,  tx, amount
@@ -69,3 +52,234 @@ deposit,         1,   1,
     run_prepared_transactions("parse_missed_decimal", input, output, stderr)?;
     Ok(())
 }
+
+#[test]
+fn parse_amount_fractional_too_long_rounded_half_even() -> Result<(), Box<dyn Error>> {
+    let input = "type,       client,  tx, amount
+deposit,         1,   1,    1.12345
+";
+    let output = String::from(
+        "client, available, held, total, locked
+1,1.1234,0.0,1.1234,false
+",
+    );
+    let stderr = "line=2 amount=1.12345 rounded=1.1234";
+    run_prepared_transactions_with_args(
+        "parse_amount_fractional_too_long_rounded_half_even",
+        &["--rounding", "half-even"],
+        input,
+        output,
+        stderr,
+    )?;
+    Ok(())
+}
+
+#[test]
+fn parse_amount_fractional_too_long_rounded_half_up() -> Result<(), Box<dyn Error>> {
+    let input = "type,       client,  tx, amount
+deposit,         1,   1,    1.12345
+";
+    let output = String::from(
+        "client, available, held, total, locked
+1,1.1235,0.0,1.1235,false
+",
+    );
+    let stderr = "rounded amount";
+    run_prepared_transactions_with_args(
+        "parse_amount_fractional_too_long_rounded_half_up",
+        &["--rounding", "half-up"],
+        input,
+        output,
+        stderr,
+    )?;
+    Ok(())
+}
+
+#[test]
+fn parse_amount_fractional_too_long_rounded_and_rejected() -> Result<(), Box<dyn Error>> {
+    let input = "type,       client,  tx, amount
+withdrawal,      1,   1,    1.12345
+";
+    let output = String::from(
+        "client, available, held, total, locked
+",
+    );
+    let stderr = "line=2 amount=1.12345 rounded=1.1234 reason=NestedEngineError";
+    run_prepared_transactions_with_args(
+        "parse_amount_fractional_too_long_rounded_and_rejected",
+        &["--rounding", "half-even"],
+        input,
+        output,
+        stderr,
+    )?;
+    Ok(())
+}
+
+#[test]
+fn parse_amount_fractional_too_long_truncated() -> Result<(), Box<dyn Error>> {
+    let input = "type,       client,  tx, amount
+deposit,         1,   1,    1.99999
+";
+    let output = String::from(
+        "client, available, held, total, locked
+1,1.9999,0.0,1.9999,false
+",
+    );
+    let stderr = "rounded=1.9999";
+    run_prepared_transactions_with_args(
+        "parse_amount_fractional_too_long_truncated",
+        &["--rounding", "truncate"],
+        input,
+        output,
+        stderr,
+    )?;
+    Ok(())
+}
+
+#[test]
+fn parse_amount_lenient() -> Result<(), Box<dyn Error>> {
+    let input = "type,       client,  tx, amount
+deposit,         1,   1,    +1_000.5
+deposit,         1,   2,    .5
+deposit,         1,   3,    1e3
+";
+    let output = String::from(
+        "client, available, held, total, locked
+1,2001.0,0.0,2001.0,false
+",
+    );
+    run_prepared_transactions_with_args(
+        "parse_amount_lenient",
+        &["--amount-syntax", "lenient"],
+        input,
+        output,
+        "",
+    )?;
+    Ok(())
+}
+
+#[test]
+fn parse_amount_strict_rejects_exponent() -> Result<(), Box<dyn Error>> {
+    let input = "type,       client,  tx, amount
+deposit,         1,   1,    1e3
+";
+    let output = String::from(
+        "client, available, held, total, locked
+",
+    );
+    let stderr = "CannotParseDecimalPart { position: 1, found: Some('e') }";
+    run_prepared_transactions(
+        "parse_amount_strict_rejects_exponent",
+        input,
+        output,
+        stderr,
+    )?;
+    Ok(())
+}
+
+#[test]
+fn format_amount_trimmed() -> Result<(), Box<dyn Error>> {
+    let input = "type,       client,  tx, amount
+deposit,         1,   1,    0.05
+";
+    let output = String::from(
+        "client, available, held, total, locked
+1,0.05,0.0,0.05,false
+",
+    );
+    let stderr = "";
+    run_prepared_transactions_with_args(
+        "format_amount_trimmed",
+        &["--amount-format", "trimmed"],
+        input,
+        output,
+        stderr,
+    )?;
+    Ok(())
+}
+
+#[test]
+fn format_amount_fixed() -> Result<(), Box<dyn Error>> {
+    let input = "type,       client,  tx, amount
+deposit,         1,   1,    0.05
+";
+    let output = String::from(
+        "client, available, held, total, locked
+1,0.0500,0.0000,0.0500,false
+",
+    );
+    let stderr = "";
+    run_prepared_transactions_with_args(
+        "format_amount_fixed",
+        &["--amount-format", "fixed"],
+        input,
+        output,
+        stderr,
+    )?;
+    Ok(())
+}
+
+#[test]
+fn format_amount_legacy() -> Result<(), Box<dyn Error>> {
+    let input = "type,       client,  tx, amount
+deposit,         1,   1,    0.05
+deposit,         2,   2,    3
+";
+    let output = String::from(
+        "client, available, held, total, locked
+1,0.0500,0.0,0.0500,false
+2,3.0,0.0,3.0,false
+",
+    );
+    let stderr = "";
+    run_prepared_transactions_with_args(
+        "format_amount_legacy",
+        &["--amount-format", "legacy"],
+        input,
+        output,
+        stderr,
+    )?;
+    Ok(())
+}
+
+#[test]
+fn format_amount_locale() -> Result<(), Box<dyn Error>> {
+    let input = "type,       client,  tx, amount
+deposit,         1,   1,    1234.5
+";
+    let output = String::from(
+        "client, available, held, total, locked
+1,\"1 234,5\",\"0,0\",\"1 234,5\",false
+",
+    );
+    let stderr = "";
+    run_prepared_transactions_with_args(
+        "format_amount_locale",
+        &[
+            "--amount-format",
+            "trimmed",
+            "--thousands-separator",
+            " ",
+            "--decimal-mark",
+            ",",
+        ],
+        input,
+        output,
+        stderr,
+    )?;
+    Ok(())
+}
+
+#[test]
+fn format_amount_ambiguous() -> Result<(), Box<dyn Error>> {
+    let file = prepare("format_amount_ambiguous", "type, client, tx, amount\n")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    cmd.args(["--thousands-separator", ".", "--decimal-mark", "."]);
+    cmd.arg(file);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("AmbiguousFormat"));
+
+    Ok(())
+}
@@ -1,32 +1,14 @@
 // Standard paths
 use std::error::Error;
-use std::fs;
-use std::path::Path;
 use std::process::Command; // Run programs // Used for writing assertions
 
+// Crate paths
+use crate::cli::common::prepare;
+use crate::cli::common::run_prepared_transactions;
+use crate::cli::common::run_prepared_transactions_with_args;
+
 // External paths
 use assert_cmd::prelude::*; // Add methods on commands
-use predicates::prelude::*;
-
-fn run_prepared_transactions(
-    testname: &str,
-    input: &str,
-    output: String,
-    stderr: &str,
-) -> Result<(), Box<dyn Error>> {
-    fs::create_dir_all("tmp")?;
-    let file = Path::new("tmp").join(testname);
-    fs::write(&file, input)?;
-
-    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
-    cmd.arg(file);
-    cmd.assert()
-        .success()
-        .stdout(output)
-        .stderr(predicate::str::contains(stderr));
-
-    Ok(())
-}
 
 #[test]
 fn deposit() -> Result<(), Box<dyn Error>> {
@@ -273,7 +255,7 @@ dispute,         1,   2,
 1,0.0,1000000000000000.0,1000000000000000.0,false
 ",
     );
-    let stderr = "DisputeCannotAddHeld";
+    let stderr = "CannotDepositTotalExceededMaxLimit";
     run_prepared_transactions("dispute_hold_too_much", input, output, stderr)?;
     Ok(())
 }
@@ -353,13 +335,13 @@ dispute,         1,   1,
 deposit,         1,   2,    1000000000000000.0
 resolve,         1,   1,
 ";
-    // Total of available and held is also too high to successfully sum them and properly print
+    // Second deposit would make total too high, so it is refused before resolve
     let output = String::from(
         "client, available, held, total, locked
-1,1000000000000000.0,1000000000000000.0,1000000000000000.0,false
+1,1000000000000000.0,0.0,1000000000000000.0,false
 ",
     );
-    let stderr = "ResolveCannotAddAvailable";
+    let stderr = "CannotDepositTotalExceededMaxLimit";
     run_prepared_transactions(
         "resolve_available_too_high_to_add_more",
         input,
@@ -431,3 +413,225 @@ withdrawal,      1,   2,    1.0
     )?;
     Ok(())
 }
+
+#[test]
+fn dispute_withdrawn_allow_negative() -> Result<(), Box<dyn Error>> {
+    let input = "type,       client,  tx, amount
+deposit,         1,   1,    1.0
+withdrawal,      1,   2,    1.0
+dispute,         1,   1,
+";
+    let output = String::from(
+        "client, available, held, total, locked
+1,-1.0,1.0,0.0,false
+",
+    );
+    let stderr = "account is overdrawn account=client: 1";
+    run_prepared_transactions_with_args(
+        "dispute_withdrawn_allow_negative",
+        &["--allow-negative"],
+        input,
+        output,
+        stderr,
+    )?;
+    Ok(())
+}
+
+#[test]
+fn chargeback_withdrawn_allow_negative() -> Result<(), Box<dyn Error>> {
+    let input = "type,       client,  tx, amount
+deposit,         1,   1,    1.0
+withdrawal,      1,   2,    1.0
+dispute,         1,   1,
+chargeback,      1,   1,
+";
+    let output = String::from(
+        "client, available, held, total, locked
+1,-1.0,0.0,-1.0,true
+",
+    );
+    let stderr = "account is overdrawn account=client: 1";
+    run_prepared_transactions_with_args(
+        "chargeback_withdrawn_allow_negative",
+        &["--allow-negative"],
+        input,
+        output,
+        stderr,
+    )?;
+    Ok(())
+}
+
+#[test]
+fn transfer() -> Result<(), Box<dyn Error>> {
+    let input = "type,       client,  tx, amount, to_client
+deposit,         1,   1,    3.0
+transfer,        1,   2,    1.0,    2
+transfer,        1,   3,    5.0,    2
+transfer,        1,   4,    1.0,
+";
+    let output = String::from(
+        "client, available, held, total, locked
+1,2.0,0.0,2.0,false
+2,1.0,0.0,1.0,false
+",
+    );
+    let stderr = "MissedMandatoryToClientInInputRecord";
+    run_prepared_transactions("transfer", input, output, stderr)?;
+    Ok(())
+}
+
+#[test]
+fn chargeback_transfer_to_closed_account() -> Result<(), Box<dyn Error>> {
+    let input = "type,       client,  tx, amount, to_client
+deposit,         1,   1,    10.0
+transfer,        1,   2,    4.0,    2
+withdrawal,      1,   3,    6.0
+close,           1,   4,       ,
+dispute,         2,   2,
+chargeback,      2,   2,
+";
+    let output = String::from(
+        "client, available, held, total, locked
+1,0.0,0.0,0.0,false
+2,0.0,4.0,4.0,false
+",
+    );
+    let stderr = "AccountClosed(1)";
+    run_prepared_transactions(
+        "chargeback_transfer_to_closed_account",
+        input,
+        output,
+        stderr,
+    )?;
+    Ok(())
+}
+
+#[test]
+fn fees() -> Result<(), Box<dyn Error>> {
+    let fees = prepare(
+        "fees_schedule",
+        "operation,  client, from, flat, percent
+deposit,          ,    0,    0,     1
+withdrawal,       ,    0,    0.5,
+withdrawal,      2,    0,    0,     0
+",
+    )?;
+
+    let input = "type,       client,  tx, amount
+deposit,         1,   1,    100.0
+withdrawal,      1,   2,    10.0
+deposit,         2,   3,    10.0
+withdrawal,      2,   4,    1.0
+";
+    let output = String::from(
+        "client, available, held, total, locked
+1,88.5000,0.0,88.5000,false
+2,8.9000,0.0,8.9000,false
+9,1.6000,0.0,1.6000,false
+",
+    );
+    run_prepared_transactions_with_args(
+        "fees",
+        &["--fees", fees.to_str().unwrap(), "--house-client", "9"],
+        input,
+        output,
+        "",
+    )?;
+    Ok(())
+}
+
+#[test]
+fn limits() -> Result<(), Box<dyn Error>> {
+    let limits = prepare(
+        "limits_rules",
+        "rule,            client, amount, count, window
+max_deposit,           ,  100,       ,
+withdrawal_cap,        ,  10,          ,     day
+max_operations,       2,      ,     1,       10
+",
+    )?;
+
+    let input = "type,       client,  tx, amount, timestamp
+deposit,         1,   1,    101.0,  0
+deposit,         1,   2,    100.0,  0
+withdrawal,      1,   3,    6.0,    100
+withdrawal,      1,   4,    6.0,    200
+withdrawal,      1,   5,    6.0,    86400
+deposit,         2,   6,    1.0,    0
+deposit,         2,   7,    1.0,    0
+";
+    let output = String::from(
+        "client, available, held, total, locked
+1,88.0,0.0,88.0,false
+2,1.0,0.0,1.0,false
+",
+    );
+    run_prepared_transactions_with_args(
+        "limits",
+        &["--limits", limits.to_str().unwrap()],
+        input,
+        output,
+        "DepositAboveLimit",
+    )?;
+    Ok(())
+}
+
+#[test]
+fn admin_operations() -> Result<(), Box<dyn Error>> {
+    let input = "type,       client,  tx, amount, asset, reason
+deposit,         1,   1,    5.0
+deposit,         2,   2,    5.0
+deposit,         3,   3,    5.0
+lock,            1,   4,       ,      ,\"fraud, \"\"suspected\"\"\"
+freeze,          2,   5,       ,      , audit
+deposit,         2,   6,    1.0
+withdrawal,      2,   7,    1.0
+withdrawal,      3,   8,    5.0
+close,           3,   9,       ,      , client request
+deposit,         3,  10,    1.0
+";
+    let output = String::from(
+        "client, available, held, total, locked, status, reason
+1,5.0,0.0,5.0,true,locked,\"fraud, \"\"suspected\"\"\"
+2,6.0,0.0,6.0,false,frozen,audit
+3,0.0,0.0,0.0,false,closed,client request
+",
+    );
+    run_prepared_transactions_with_args(
+        "admin_operations",
+        &["--with-status"],
+        input,
+        output,
+        "AccountClosed(3)",
+    )?;
+    Ok(())
+}
+
+#[test]
+fn reconcile() -> Result<(), Box<dyn Error>> {
+    let file = prepare(
+        "reconcile",
+        "type,       client,  tx, amount, asset, to_client
+deposit,         1,   1,    10.0,  USD
+deposit,         2,   2,    5.0,   USD
+transfer,        1,   3,    4.0,   USD,  2
+dispute,         2,   2,
+withdrawal,      1,   4,    1.5,   USD
+deposit,         3,   5,    7,     EUR
+dispute,         3,   5,
+chargeback,      3,   5,
+",
+    )?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    cmd.arg("reconcile");
+    cmd.arg(file);
+    cmd.assert().success().stdout(
+        "asset, deposited, withdrawn, exchanged_in, exchanged_out, charged_back, returned, fees, available, held, disputed, total, balanced
+EUR,7.0,0.0,0.0,0.0,7.0,0.0,0.0,0.0,0.0,0.0,0.0,true
+USD,15.0,1.5000,0.0,0.0,0.0,0.0,0.0,8.5000,5.0,5.0,13.5000,true
+",
+    );
+
+    Ok(())
+}
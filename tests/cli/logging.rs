@@ -0,0 +1,58 @@
+// Standard paths
+use std::error::Error;
+use std::process::Command; // Run programs // Used for writing assertions
+
+// Crate paths
+use crate::cli::common::prepare;
+
+// External paths
+use assert_cmd::prelude::*; // Add methods on commands
+use predicates::prelude::*;
+
+const INPUT: &str = "type,       client,  tx, amount
+deposit,         1,   1,    1.0
+withdrawal,      1,   2,    2.0
+";
+
+#[test]
+fn logging_quiet() -> Result<(), Box<dyn Error>> {
+    let file = prepare("logging_quiet", INPUT)?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    cmd.arg("-q").arg(file);
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("CannotWithdrawal").not());
+
+    Ok(())
+}
+
+#[test]
+fn logging_json() -> Result<(), Box<dyn Error>> {
+    let file = prepare("logging_json", INPUT)?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    cmd.args([
+        "-v",
+        "--log-format",
+        "json",
+        "--summary",
+        "tmp/logging_json.json",
+    ])
+    .arg(file);
+    let output = cmd.assert().success().get_output().stderr.clone();
+
+    // Each line is separate event
+    let events = String::from_utf8(output)?
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<Vec<serde_json::Value>, _>>()?;
+    assert!(events.iter().any(|event| event["level"] == "WARN"
+        && event["fields"]["line"] == 3
+        && event["span"]["name"] == "file"));
+    assert!(events
+        .iter()
+        .any(|event| event["fields"]["message"] == "processed file"));
+
+    Ok(())
+}
@@ -0,0 +1,53 @@
+// Standard paths
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::process::Command; // Run programs // Used for writing assertions
+
+// Crate paths
+use crate::cli::common::prepare;
+
+// External paths
+use assert_cmd::prelude::*; // Add methods on commands
+use predicates::prelude::*;
+
+const INPUT: &str = "type,       client,  tx, amount
+deposit,         1,   1,    10.0
+deposit,         2,   2,    5.0
+withdrawal,      1,   3,    20.0
+dispute,         2,   2,
+chargeback,      2,   2,
+";
+
+#[test]
+fn metrics_file() -> Result<(), Box<dyn Error>> {
+    let file = prepare("metrics_file", INPUT)?;
+    let metrics = Path::new("tmp").join("metrics_file.prom");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    cmd.arg(file).arg("--metrics").arg(&metrics);
+    cmd.assert().success();
+
+    let written = fs::read_to_string(metrics)?;
+    assert!(written.contains("transactions_processor_records_total{type=\"deposit\"} 2\n"));
+    assert!(written.contains("transactions_processor_errors_total{code=\"CannotWithdrawal\"} 1\n"));
+    assert!(written.contains("transactions_processor_record_duration_seconds_count 5\n"));
+    assert!(written.contains("transactions_processor_accounts{status=\"locked\"} 1\n"));
+
+    Ok(())
+}
+
+#[test]
+fn metrics_file_rejected_by_server() -> Result<(), Box<dyn Error>> {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    cmd.arg("serve")
+        .arg("--address")
+        .arg("127.0.0.1:0")
+        .arg("--metrics")
+        .arg("tmp/metrics_file_rejected_by_server.prom");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("GET /metrics"));
+
+    Ok(())
+}
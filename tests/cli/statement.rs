@@ -0,0 +1,147 @@
+// Standard paths
+use std::error::Error;
+use std::process::Command; // Run programs // Used for writing assertions
+
+// Crate paths
+use crate::cli::common::prepare;
+
+// External paths
+use assert_cmd::prelude::*; // Add methods on commands
+use predicates::prelude::*;
+
+fn run_statement(
+    testname: &str,
+    args: &[&str],
+    input: &str,
+    output: String,
+) -> Result<(), Box<dyn Error>> {
+    let file = prepare(testname, input)?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    cmd.arg("statement");
+    cmd.args(args);
+    cmd.arg(file);
+    cmd.assert().success().stdout(output);
+
+    Ok(())
+}
+
+const INPUT: &str = "type,       client,  tx, amount, to_client
+deposit,         1,   1,    10.0
+deposit,         2,   2,    5.0
+transfer,        1,   3,    4.0,   2
+dispute,         2,   2,
+chargeback,      2,   2,
+withdrawal,      1,   4,    20.0
+";
+
+#[test]
+fn statement_csv() -> Result<(), Box<dyn Error>> {
+    let output = String::from(
+        "client, tx, event, amount, available, held, reason
+2,2,deposit,5.0,5.0,0.0,
+2,3,transfer,4.0,9.0,0.0,
+2,2,dispute,0.0,4.0,5.0,
+2,2,chargeback,-5.0,4.0,0.0,chargeback
+1,1,deposit,10.0,10.0,0.0,
+1,3,transfer,-4.0,6.0,0.0,
+",
+    );
+    run_statement(
+        "statement_csv",
+        &["--client", "2", "--client", "1"],
+        INPUT,
+        output,
+    )?;
+    Ok(())
+}
+
+#[test]
+fn statement_json() -> Result<(), Box<dyn Error>> {
+    let output = String::from(
+        r#"[
+  {
+    "client": 1,
+    "asset": "",
+    "tx": 1,
+    "event": "deposit",
+    "amount": "10.0000",
+    "available": "10.0000",
+    "held": "0.0000"
+  },
+  {
+    "client": 1,
+    "asset": "",
+    "tx": 3,
+    "event": "transfer",
+    "amount": "-4.0000",
+    "available": "6.0000",
+    "held": "0.0000"
+  }
+]
+"#,
+    );
+    run_statement(
+        "statement_json",
+        &["--client", "1", "--output", "json"],
+        INPUT,
+        output,
+    )?;
+    Ok(())
+}
+
+#[test]
+fn statement_with_admin_operations() -> Result<(), Box<dyn Error>> {
+    let input = "type,       client,  tx, amount, reason
+deposit,         1,   1,    10.0
+freeze,          1,   2,        , audit
+unlock,          1,   3,
+lock,            1,   1,        , reused
+";
+    let output = String::from(
+        "client, tx, event, amount, available, held, reason
+1,1,deposit,10.0,10.0,0.0,
+1,2,freeze,0.0,10.0,0.0,audit
+1,3,unlock,0.0,10.0,0.0,
+",
+    );
+    run_statement(
+        "statement_with_admin_operations",
+        &["--client", "1"],
+        input,
+        output,
+    )?;
+    Ok(())
+}
+
+#[test]
+fn statement_with_quoted_asset() -> Result<(), Box<dyn Error>> {
+    let input = "type,       client,  tx, amount, asset, reason
+deposit,         1,   1,    10.0,\"A,B\"
+lock,            1,   2,        ,\"A,B\",\"fraud, suspected\"
+";
+    let output = String::from(
+        "client, asset, tx, event, amount, available, held, reason
+1,\"A,B\",1,deposit,10.0,10.0,0.0,
+1,\"A,B\",2,lock,0.0,10.0,0.0,\"fraud, suspected\"
+",
+    );
+    run_statement(
+        "statement_with_quoted_asset",
+        &["--client", "1"],
+        input,
+        output,
+    )?;
+    Ok(())
+}
+
+#[test]
+fn statement_needs_client() -> Result<(), Box<dyn Error>> {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    cmd.args(["statement", "transactions.csv"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("--client"));
+
+    Ok(())
+}
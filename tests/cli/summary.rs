@@ -0,0 +1,57 @@
+// Standard paths
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::process::Command; // Run programs // Used for writing assertions
+
+// Crate paths
+use crate::cli::common::prepare;
+
+// External paths
+use assert_cmd::prelude::*; // Add methods on commands
+use predicates::prelude::*;
+
+const INPUT: &str = "type,       client,  tx, amount
+deposit,         1,   1,    10.0
+deposit,         2,   2,    5.0
+withdrawal,      1,   3,    20.0
+dispute,         2,   2,
+chargeback,      2,   2,
+chargeback,      2,   9,
+";
+
+#[test]
+fn summary_stderr() -> Result<(), Box<dyn Error>> {
+    let file = prepare("summary_stderr", INPUT)?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    cmd.arg(file);
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("records read: 6"))
+        .stderr(predicate::str::contains("applied deposit: 2"))
+        .stderr(predicate::str::contains("rejected CannotWithdrawal: 1"))
+        .stderr(predicate::str::contains("accounts locked: 1"));
+
+    Ok(())
+}
+
+#[test]
+fn summary_json() -> Result<(), Box<dyn Error>> {
+    let file = prepare("summary_json", INPUT)?;
+    let summary = Path::new("tmp").join("summary_json.json");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    cmd.arg(file).arg("--summary").arg(&summary);
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("SUMMARY").not());
+
+    let written: serde_json::Value = serde_json::from_str(&fs::read_to_string(summary)?)?;
+    assert_eq!(written["records_read"], 6);
+    assert_eq!(written["applied"]["chargeback"], 1);
+    assert_eq!(written["rejected"]["CannotFindTransaction"], 1);
+    assert_eq!(written["accounts_created"], 2);
+
+    Ok(())
+}
@@ -1,4 +1,13 @@
+mod api {
+    mod server;
+}
 mod cli {
+    mod asset;
+    mod common;
     mod currency;
     mod engine;
+    mod logging;
+    mod metrics;
+    mod statement;
+    mod summary;
 }